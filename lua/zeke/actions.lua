@@ -13,7 +13,9 @@ local api = vim.api
 local cli = require('zeke.cli')
 local lsp_context = require('zeke.lsp.context')
 local mentions = require('zeke.mentions')
+local snapshots = require('zeke.context.snapshots')
 local logger = require('zeke.logger')
+local utils = require('zeke.utils')
 
 -- Action definitions with smart context
 M.actions = {
@@ -258,13 +260,10 @@ function M.gather_context()
     end
   end
 
-  -- Check if in git repo
-  local git_check = vim.fn.system('git rev-parse --git-dir 2>/dev/null')
-  ctx.in_git_repo = vim.v.shell_error == 0
-
-  -- Get git status if in repo
+  -- Git status from the background snapshot (fails outside a repository)
+  local git_status, in_repo = snapshots.git('git_status')
+  ctx.in_git_repo = in_repo
   if ctx.in_git_repo then
-    local git_status = vim.fn.system('git status --porcelain')
     ctx.has_git_changes = git_status ~= ""
   end
 
@@ -345,7 +344,7 @@ function M.execute_action(action, ctx)
 
       -- Add context if selection
       if ctx.selection then
-        prompt = input .. "\n\n```" .. ctx.filetype .. "\n" .. ctx.selection_text .. "\n```"
+        prompt = input .. "\n\n" .. utils.format_code_block(ctx.selection_text, ctx.filetype)
       else
         prompt = input
       end
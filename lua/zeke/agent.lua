@@ -335,11 +335,6 @@ function M._do_send_message(input_bufnr, message, processed_message)
       return
     end
 
-    -- Track token usage
-    if estimate then
-      tokens.track_usage(estimate.input_tokens, estimate.output_tokens, estimate.cost)
-    end
-
     -- Track rate limiting
     safety.track_request()
 
@@ -391,12 +386,6 @@ function M._do_send_message(input_bufnr, message, processed_message)
           logger.info("agent", "Response received")
           table.insert(M.state.conversation_history, { role = "assistant", content = full_response })
 
-          -- Update token usage with actual output
-          if estimate then
-            local output_tokens = tokens.estimate_tokens(full_response)
-            local actual_cost = tokens.calculate_cost(estimate.input_tokens, output_tokens, current_model.name)
-            tokens.track_usage(estimate.input_tokens, output_tokens, actual_cost)
-          end
         else
           append_to_chat("Error: Request failed", "assistant")
           logger.error("agent", "Request failed with exit code " .. exit_code)
@@ -0,0 +1,171 @@
+--[[
+  Agent Checkpoints
+
+  Before an agent run changes a file (write_file), the file's current
+  contents are saved in the run's checkpoint, once per file. Reverting the
+  checkpoint restores every file the run touched and deletes the ones it
+  created:
+
+    :ZekeAgentUndo        " revert the latest run
+    require('zeke').agent_undo(3)
+
+  A file edited after the run wrote it is not reverted over: revert()
+  refuses and lists the changed files unless forced (:ZekeAgentUndo!).
+
+  Checkpoints live for the session. Files changed by run_command (e.g. a
+  formatter) are not captured.
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local audit = require('zeke.audit')
+
+-- run_id -> { run_id, goal, created_at, files = { path -> lines|false },
+--   written = { path -> hash|false }, order = { path }, reverted_at }
+M.checkpoints = {}
+
+-- Hash of a file's bytes, or false when it doesn't exist
+local function file_hash(path)
+  if vim.fn.filereadable(path) ~= 1 then
+    return false
+  end
+  return vim.fn.sha256(table.concat(vim.fn.readfile(path, 'b'), '\n'))
+end
+
+---Start a checkpoint for a run
+---@param run_id number
+---@param goal string
+function M.create(run_id, goal)
+  M.checkpoints[run_id] = { run_id = run_id, goal = goal, created_at = os.time(), files = {}, written = {}, order = {} }
+end
+
+---Save a file's contents before the run first changes it
+---@param run_id number
+---@param path string Absolute path
+function M.snapshot(run_id, path)
+  local checkpoint = M.checkpoints[run_id]
+  if not checkpoint or checkpoint.files[path] ~= nil then
+    return
+  end
+  -- Binary mode keeps the file byte-for-byte, including a missing final newline
+  checkpoint.files[path] = vim.fn.filereadable(path) == 1 and vim.fn.readfile(path, 'b') or false
+  table.insert(checkpoint.order, path)
+end
+
+---Remember what the run left in a file, after each write
+---@param run_id number
+---@param path string Absolute path
+function M.record_write(run_id, path)
+  local checkpoint = M.checkpoints[run_id]
+  if checkpoint and checkpoint.files[path] ~= nil then
+    checkpoint.written[path] = file_hash(path)
+  end
+end
+
+---Files of a run changed since the run last wrote them
+---@param run_id number
+---@return string[]
+function M.changed_since(run_id)
+  local checkpoint = M.checkpoints[run_id]
+  local changed = {}
+  for _, path in ipairs(checkpoint and checkpoint.order or {}) do
+    local expected = checkpoint.written[path]
+    if expected == nil then
+      -- The write failed, so the file should still be the original
+      local original = checkpoint.files[path]
+      expected = original and vim.fn.sha256(table.concat(original, '\n')) or false
+    end
+    if file_hash(path) ~= expected then
+      table.insert(changed, path)
+    end
+  end
+  return changed
+end
+
+---Checkpoint of a run
+---@param run_id number
+---@return table|nil {run_id, goal, created_at, files = string[], reverted_at}
+function M.get(run_id)
+  local checkpoint = M.checkpoints[run_id]
+  if not checkpoint then
+    return nil
+  end
+  return {
+    run_id = checkpoint.run_id,
+    goal = checkpoint.goal,
+    created_at = checkpoint.created_at,
+    files = vim.deepcopy(checkpoint.order),
+    reverted_at = checkpoint.reverted_at,
+  }
+end
+
+---Checkpoints that changed files, newest first
+---@return table[]
+function M.list()
+  local list = {}
+  for run_id, checkpoint in pairs(M.checkpoints) do
+    if #checkpoint.order > 0 then
+      table.insert(list, M.get(run_id))
+    end
+  end
+  table.sort(list, function(a, b) return a.run_id > b.run_id end)
+  return list
+end
+
+---Restore every file a run changed
+---@param run_id number
+---@param opts table|nil {force} revert even files changed since the run
+---@return number|nil restored Files restored or deleted
+---@return string|nil error
+function M.revert(run_id, opts)
+  opts = opts or {}
+  local checkpoint = M.checkpoints[run_id]
+  if not checkpoint or #checkpoint.order == 0 then
+    return nil, string.format('Agent run #%s changed no files', tostring(run_id))
+  end
+  if checkpoint.reverted_at then
+    return nil, string.format('Agent run #%d was already reverted', run_id)
+  end
+  if not opts.force then
+    local changed = M.changed_since(run_id)
+    if #changed > 0 then
+      return nil, string.format('Not reverting agent run #%d: changed since the run: %s (use :ZekeAgentUndo! to revert anyway)',
+        run_id, table.concat(vim.tbl_map(function(path) return vim.fn.fnamemodify(path, ':~:.') end, changed), ', '))
+    end
+  end
+
+  local restored, failed = 0, {}
+  for _, path in ipairs(checkpoint.order) do
+    local original = checkpoint.files[path]
+    local current = audit.read_file(path)
+    local ok
+    if original == false then
+      ok = vim.fn.filereadable(path) == 0 or vim.fn.delete(path) == 0
+    else
+      ok = vim.fn.writefile(original, path, 'b') == 0
+    end
+
+    if ok then
+      restored = restored + 1
+      audit.record('file_write', {
+        source = 'agent_undo',
+        path = path,
+        before = current,
+        after = original and table.concat(original, '\n') or '',
+      })
+    else
+      table.insert(failed, path)
+    end
+  end
+
+  checkpoint.reverted_at = os.time()
+  vim.cmd('checktime')
+  logger.info('agent_checkpoints', string.format('Reverted run #%d: %d files', run_id, restored))
+  if #failed > 0 then
+    return restored, 'Could not restore: ' .. table.concat(failed, ', ')
+  end
+  return restored
+end
+
+return M
@@ -0,0 +1,421 @@
+--[[
+  Agent Loop
+
+  Turns a goal into a sequence of tool calls: each step asks the model for
+  a JSON action (a tool call or a final answer), runs the tool from
+  zeke.agent_tools, and feeds the observation back, until the model
+  answers or the run is stopped. A run also stops, with a summary of what
+  it did, once it hits any of its limits: max_steps, max_tokens, max_cost
+  (estimated USD) or max_time_s.
+
+    require('zeke').agent_run('Make the failing parser test pass', {
+      on_done = function(run) print(run.status, run.answer) end,
+    })
+
+  With M.plan() the model first writes a numbered plan, which opens in a
+  buffer: edit it, then :w to approve and start the run (or close it to
+  cancel). With approve_steps, every mutating step (write_file,
+  run_command) is confirmed before it runs.
+
+  Files a run writes are checkpointed first (zeke.agent_checkpoints), so
+  :ZekeAgentUndo reverts the whole run.
+
+  Steps don't block the editor; stopping a run cancels its pending request.
+  Runs are kept in M.runs with every step's tool, arguments and result.
+  Progress fires ZekeAgentStep and ZekeAgentDone User autocmds.
+--]]
+
+local M = {}
+
+local cli = require('zeke.cli')
+local logger = require('zeke.logger')
+local agent_tools = require('zeke.agent_tools')
+local checkpoints = require('zeke.agent_checkpoints')
+
+M.config = {
+  max_steps = 20,
+  max_tokens = nil,          -- Estimated prompt + response tokens per run
+  max_cost = nil,            -- Estimated USD per run (local providers cost nothing)
+  max_time_s = nil,          -- Wall-clock seconds per run
+  observation_chars = 4000,  -- Tool output kept per step in the model's context
+  plan_first = false,        -- :ZekeAgent writes a plan for approval before running
+  approve_steps = false,     -- Confirm each mutating step before it runs
+}
+
+-- id -> run
+M.runs = {}
+local next_id = 1
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+local function emit(event, data)
+  vim.api.nvim_exec_autocmds('User', { pattern = event, data = data, modeline = false })
+end
+
+local function step_schema(tools)
+  return {
+    type = 'object',
+    properties = {
+      thought = { type = 'string' },
+      tool = { type = 'string', enum = tools },
+      args = { type = 'object' },
+      answer = { type = 'string' },
+    },
+    required = { 'thought' },
+  }
+end
+
+local function truncate(text, max)
+  if #text <= max then
+    return text
+  end
+  return text:sub(1, max) .. string.format('\n... (%d more characters)', #text - max)
+end
+
+-- The goal, the tools and everything observed so far
+local function build_prompt(run)
+  local parts = {
+    'You are a coding agent working in the project at ' .. run.cwd .. '.',
+    'Reach the goal by calling one tool per step; you see each result before the next step.',
+    'When the goal is reached (or cannot be), reply with "answer" instead of "tool".',
+    '',
+    'Tools:',
+    agent_tools.describe(run.tools),
+    '',
+    'Goal: ' .. run.goal,
+  }
+
+  if run.plan then
+    table.insert(parts, '')
+    table.insert(parts, 'Approved plan (follow it in order; do not take actions outside it):')
+    for i, item in ipairs(run.plan) do
+      table.insert(parts, string.format('%d. %s', i, item))
+    end
+  end
+
+  if #run.steps > 0 then
+    table.insert(parts, '')
+    table.insert(parts, 'Steps so far:')
+    for i, step in ipairs(run.steps) do
+      table.insert(parts, string.format('%d. %s\n   %s %s -> %s\n%s', i, step.thought, step.tool,
+        vim.json.encode(step.args or {}), step.ok and 'ok' or 'failed', truncate(step.result or '', M.config.observation_chars)))
+    end
+  end
+
+  return table.concat(parts, '\n')
+end
+
+local function finish(run, status, answer)
+  if run.finished_at then
+    return
+  end
+  run.status = status
+  run.answer = answer
+  run.finished_at = os.time()
+  if run.cancel then
+    run.cancel()
+    run.cancel = nil
+  end
+  logger.info('agent_loop', string.format('Run #%d %s after %d steps', run.id, status, #run.steps))
+  emit('ZekeAgentDone', { id = run.id, status = status, steps = #run.steps })
+  if run.on_done then
+    run.on_done(run)
+  end
+end
+
+local function elapsed(run)
+  return os.time() - run.started_at
+end
+
+-- Name of the first limit the run has reached, if any
+local function limit_reached(run)
+  local limits = run.limits
+  if #run.steps >= limits.max_steps then
+    return string.format('step limit (%d)', limits.max_steps)
+  elseif limits.max_tokens and run.usage.tokens >= limits.max_tokens then
+    return string.format('token limit (%d)', limits.max_tokens)
+  elseif limits.max_cost and run.usage.cost >= limits.max_cost then
+    return string.format('cost limit ($%.2f)', limits.max_cost)
+  elseif limits.max_time_s and elapsed(run) >= limits.max_time_s then
+    return string.format('time limit (%ds)', limits.max_time_s)
+  end
+  return nil
+end
+
+local function stop_at_limit(run, reason)
+  local lines = {
+    string.format('Stopped at the %s after %d steps (~%d tokens, ~$%.4f, %ds)',
+      reason, #run.steps, run.usage.tokens, run.usage.cost, elapsed(run)),
+  }
+  for i, entry in ipairs(run.steps) do
+    table.insert(lines, string.format('%d. %s %s: %s', i, entry.tool,
+      entry.ok == nil and '(unfinished)' or (entry.ok and 'ok' or 'failed'), entry.thought))
+  end
+  finish(run, 'limit', table.concat(lines, '\n'))
+end
+
+-- Add a step's estimated tokens and cost to the run: every request it sent,
+-- schema repairs and retries included (failed requests too)
+local function account(run, usage)
+  if usage then
+    run.usage.tokens = run.usage.tokens + usage.tokens
+    run.usage.cost = run.usage.cost + usage.cost
+  end
+end
+
+local act
+
+local function step(run)
+  if run.status ~= 'running' then
+    return
+  end
+  local reason = limit_reached(run)
+  if reason then
+    stop_at_limit(run, reason)
+    return
+  end
+
+  local prompt = build_prompt(run)
+  local request_opts = { task = 'edit' }
+  run.cancel = cli.generate_structured(prompt, step_schema(run.tools), request_opts, function(action, err, usage)
+    run.cancel = nil
+    account(run, usage)
+    if run.status ~= 'running' then
+      return
+    end
+    if not action then
+      finish(run, 'failed', err)
+      return
+    end
+    act(run, action)
+  end)
+end
+
+-- Run the tool the model picked (after approval, with approve_steps)
+act = function(run, action)
+  if not action.tool then
+    finish(run, 'done', action.answer or action.thought)
+    return
+  end
+
+  local entry = { thought = action.thought, tool = action.tool, args = action.args, started_at = os.time() }
+  table.insert(run.steps, entry)
+  local function complete(ok, result)
+    entry.ok = ok
+    entry.result = result or ''
+    emit('ZekeAgentStep', { id = run.id, step = #run.steps, tool = entry.tool, ok = ok })
+    if run.on_step then
+      run.on_step(run, entry)
+    end
+    vim.schedule(function() step(run) end)
+  end
+
+  local tool = agent_tools.tools[action.tool]
+  if not (run.approve_steps and tool and tool.mutating) then
+    agent_tools.call(action.tool, action.args, vim.schedule_wrap(complete), { run_id = run.id, cwd = run.cwd })
+    return
+  end
+
+  vim.ui.select({ 'Run step', 'Skip step', 'Stop run' }, {
+    prompt = string.format('Agent #%d step %d: %s\n%s %s', run.id, #run.steps, entry.thought,
+      entry.tool, vim.json.encode(entry.args or {})),
+  }, function(choice)
+    if choice == 'Run step' then
+      agent_tools.call(action.tool, action.args, vim.schedule_wrap(complete), { run_id = run.id, cwd = run.cwd })
+    elseif choice == 'Skip step' then
+      complete(false, 'The user skipped this step')
+    else
+      entry.ok = false
+      entry.result = 'The user stopped the run'
+      finish(run, 'stopped', nil)
+    end
+  end)
+end
+
+---Start an agent run
+---@param goal string
+---@param opts table|nil {max_steps, max_tokens, max_cost, max_time_s, tools = subset of tool names, cwd,
+---  plan = string[], approve_steps, on_step(run, step), on_done(run)}
+---@return table run {id, goal, status, steps, answer, usage = {tokens, cost}}
+function M.run(goal, opts)
+  opts = opts or {}
+  local run = {
+    id = next_id,
+    goal = goal,
+    cwd = opts.cwd or vim.fn.getcwd(),
+    tools = opts.tools or agent_tools.names(),
+    limits = {
+      max_steps = opts.max_steps or M.config.max_steps,
+      max_tokens = opts.max_tokens or M.config.max_tokens,
+      max_cost = opts.max_cost or M.config.max_cost,
+      max_time_s = opts.max_time_s or M.config.max_time_s,
+    },
+    usage = { tokens = 0, cost = 0 },
+    plan = opts.plan,
+    approve_steps = opts.approve_steps == nil and M.config.approve_steps or opts.approve_steps,
+    status = 'running',
+    steps = {},
+    started_at = os.time(),
+    on_step = opts.on_step,
+    on_done = opts.on_done,
+  }
+  next_id = next_id + 1
+  M.runs[run.id] = run
+  checkpoints.create(run.id, goal)
+
+  logger.info('agent_loop', string.format('Run #%d: %s', run.id, goal))
+  vim.schedule(function() step(run) end)
+
+  -- Also stop while waiting on a tool or an approval prompt
+  if run.limits.max_time_s then
+    vim.defer_fn(function()
+      if run.status == 'running' then
+        stop_at_limit(run, string.format('time limit (%ds)', run.limits.max_time_s))
+      end
+    end, run.limits.max_time_s * 1000)
+  end
+  return run
+end
+
+local plan_schema = {
+  type = 'object',
+  properties = {
+    steps = { type = 'array', items = { type = 'string' } },
+  },
+  required = { 'steps' },
+}
+
+-- Numbered lines ("1. ..." or "1) ...") of an edited plan
+local function parse_plan(lines)
+  local plan = {}
+  for _, line in ipairs(lines) do
+    local item = line:match('^%s*%d+[.)]%s+(.-)%s*$')
+    if item and item ~= '' then
+      table.insert(plan, item)
+    end
+  end
+  return plan
+end
+
+-- Plan buffer: writing it starts the run with the edited plan
+local function open_plan(goal, opts, tools, steps)
+  local lines = {
+    '# Agent plan: ' .. goal,
+    '# Edit, reorder or delete steps, then :w to approve and run. Close the buffer to cancel.',
+    '',
+  }
+  for i, item in ipairs(steps) do
+    table.insert(lines, string.format('%d. %s', i, item))
+  end
+
+  vim.cmd('botright new')
+  local buf = vim.api.nvim_get_current_buf()
+  vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+  vim.bo[buf].buftype = 'acwrite'
+  vim.bo[buf].bufhidden = 'wipe'
+  vim.bo[buf].filetype = 'markdown'
+  vim.bo[buf].modified = false
+  vim.api.nvim_buf_set_name(buf, 'zeke://agent-plan/' .. buf)
+
+  vim.api.nvim_create_autocmd('BufWriteCmd', {
+    buffer = buf,
+    once = true,
+    callback = function()
+      local plan = parse_plan(vim.api.nvim_buf_get_lines(buf, 0, -1, false))
+      vim.bo[buf].modified = false
+      vim.api.nvim_buf_delete(buf, { force = true })
+      if #plan == 0 then
+        vim.notify('Empty plan; agent run cancelled', vim.log.levels.WARN)
+        return
+      end
+      M.run(goal, vim.tbl_extend('force', opts, { plan = plan, tools = tools }))
+    end,
+  })
+end
+
+---Ask the model for a plan and open it for review; writing the buffer
+---approves the (edited) plan and starts the run
+---@param goal string
+---@param opts table|nil Same as M.run
+---@param callback function|nil Called with (ok, error) once the plan is open or planning failed
+function M.plan(goal, opts, callback)
+  opts = opts or {}
+  callback = callback or function() end
+  local tools = opts.tools or agent_tools.names()
+  local prompt = table.concat({
+    'You are a coding agent working in the project at ' .. (opts.cwd or vim.fn.getcwd()) .. '.',
+    'Before doing anything, write a short numbered plan for the goal below: one concrete action per step,',
+    'using these tools:',
+    agent_tools.describe(tools),
+    '',
+    'Goal: ' .. goal,
+  }, '\n')
+
+  cli.generate_structured(prompt, plan_schema, { task = 'edit' }, function(result, err)
+    if not result then
+      callback(false, err)
+      return
+    end
+    open_plan(goal, opts, tools, result.steps)
+    callback(true, nil)
+  end)
+end
+
+---Stop a run after its current step
+---@param id number
+---@return boolean
+function M.stop(id)
+  local run = M.runs[id]
+  if not run or run.status ~= 'running' then
+    return false
+  end
+  finish(run, 'stopped', nil)
+  return true
+end
+
+---Runs, newest first
+---@return table[]
+function M.list()
+  local runs = vim.tbl_values(M.runs)
+  table.sort(runs, function(a, b) return a.id > b.id end)
+  return runs
+end
+
+---Show a run's steps in a scratch buffer
+---@param id number|nil Default: latest run
+function M.show(id)
+  local run = id and M.runs[id] or M.list()[1]
+  if not run then
+    vim.notify('No agent runs yet', vim.log.levels.INFO)
+    return
+  end
+
+  local lines = { string.format('# Agent run #%d (%s)', run.id, run.status), '', 'Goal: ' .. run.goal, '' }
+  if run.plan then
+    table.insert(lines, '## Plan')
+    for i, item in ipairs(run.plan) do
+      table.insert(lines, string.format('%d. %s', i, item))
+    end
+    table.insert(lines, '')
+  end
+  for i, entry in ipairs(run.steps) do
+    table.insert(lines, string.format('## %d. %s %s', i, entry.tool, entry.ok == nil and '…' or (entry.ok and '✓' or '✗')))
+    table.insert(lines, entry.thought or '')
+    table.insert(lines, '')
+  end
+  if run.answer then
+    table.insert(lines, '## Result')
+    vim.list_extend(lines, vim.split(run.answer, '\n', { plain = true }))
+  end
+
+  vim.cmd('botright new')
+  local buf = vim.api.nvim_get_current_buf()
+  vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+  vim.bo[buf].buftype = 'nofile'
+  vim.bo[buf].bufhidden = 'wipe'
+  vim.bo[buf].filetype = 'markdown'
+end
+
+return M
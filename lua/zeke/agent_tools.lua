@@ -0,0 +1,333 @@
+--[[
+  Agent Tool Registry
+
+  Tools the agent loop (zeke.agent_loop) lets the model call. Each tool has
+  a description, a JSON Schema for its arguments, and an async handler:
+
+    require('zeke.agent_tools').register('open_issues', {
+      description = 'List open issues of this repository',
+      schema = { type = 'object', properties = {} },
+      handler = function(args, done) done(true, vim.fn.system('gh issue list')) end,
+    })
+
+  Handlers call done(ok, result_text) exactly once; the third argument is
+  the calling run's context ({run_id, cwd}). Built-in tools resolve paths
+  against ctx.cwd and refuse paths outside it. Tools marked
+  `mutating` change files or run commands; they go through the approval
+  prompt (write_file) or the terminal command policy (run_command).
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local json_schema = require('zeke.json_schema')
+local approval = require('zeke.approval')
+local audit = require('zeke.audit')
+local privacy = require('zeke.privacy')
+local checkpoints = require('zeke.agent_checkpoints')
+
+M.config = {
+  max_read_lines = 400,    -- Lines returned by one read_file call
+  max_results = 200,       -- Entries returned by grep / list_dir
+}
+
+-- name -> { description, schema, mutating, handler(args, done, ctx) }
+M.tools = {}
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+---Register (or replace) a tool
+---@param name string
+---@param spec table {description, schema, mutating, handler(args, done, ctx)}
+function M.register(name, spec)
+  M.tools[name] = spec
+end
+
+---Remove a tool
+---@param name string
+function M.unregister(name)
+  M.tools[name] = nil
+end
+
+---Registered tool names, sorted
+---@return string[]
+function M.names()
+  local names = vim.tbl_keys(M.tools)
+  table.sort(names)
+  return names
+end
+
+---Tool list for the model's instructions
+---@param names string[]|nil Subset to describe (default: all)
+---@return string
+function M.describe(names)
+  local lines = {}
+  for _, name in ipairs(names or M.names()) do
+    local tool = M.tools[name]
+    if tool then
+      table.insert(lines, string.format('- %s: %s\n  args: %s', name, tool.description, vim.json.encode(tool.schema)))
+    end
+  end
+  return table.concat(lines, '\n')
+end
+
+---Call a tool
+---@param name string
+---@param args table|nil
+---@param done function Called with (ok, result_text)
+---@param ctx table|nil {run_id}
+function M.call(name, args, done, ctx)
+  local tool = M.tools[name]
+  if not tool then
+    done(false, 'Unknown tool: ' .. tostring(name))
+    return
+  end
+
+  args = args or {}
+  local problems = json_schema.validate(args, tool.schema or { type = 'object' })
+  if #problems > 0 then
+    done(false, 'Invalid arguments: ' .. table.concat(problems, '; '))
+    return
+  end
+
+  local called = false
+  local ok, err = pcall(tool.handler, args, function(success, result)
+    if called then
+      return
+    end
+    called = true
+    done(success, result)
+  end, ctx or {})
+  if not ok and not called then
+    called = true
+    logger.error('agent_tools', string.format('%s failed: %s', name, err))
+    done(false, tostring(err))
+  end
+end
+
+local function real_path(path)
+  return (vim.fn.resolve(vim.fn.simplify(vim.fn.fnamemodify(path, ':p'))):gsub('(.)/$', '%1'))
+end
+
+-- Absolute path inside the run's project root (symlinks followed), or nil, error.
+-- Paths come from the model, so they are never expanded ($VARS, wildcards)
+function M.resolve_path(path, ctx)
+  local root = real_path(ctx.cwd or vim.fn.getcwd())
+  path = path and path ~= '' and path or '.'
+  local full = path
+  if not full:match('^/') and not full:match('^~') then
+    full = root .. '/' .. full
+  end
+  full = real_path(full)
+  if full ~= root and full:sub(1, #root + 1) ~= root .. '/' then
+    return nil, string.format('Refusing %s: outside the project (%s)', path, root)
+  end
+  return full
+end
+
+M.register('read_file', {
+  description = 'Read a text file (optionally a 1-based inclusive line range); lines are numbered',
+  schema = {
+    type = 'object',
+    properties = {
+      path = { type = 'string' },
+      start_line = { type = 'integer' },
+      end_line = { type = 'integer' },
+    },
+    required = { 'path' },
+  },
+  handler = function(args, done, ctx)
+    local path, outside = M.resolve_path(args.path, ctx)
+    if not path then
+      done(false, outside)
+      return
+    end
+    local readable, refused = privacy.check_path(path)
+    if not readable then
+      done(false, refused)
+      return
+    end
+
+    -- Prefer the loaded buffer: it may have unsaved changes
+    local lines
+    local bufnr = vim.fn.bufnr(path)
+    if bufnr ~= -1 and vim.api.nvim_buf_is_loaded(bufnr) then
+      lines = vim.api.nvim_buf_get_lines(bufnr, 0, -1, false)
+    elseif vim.fn.filereadable(path) == 1 then
+      lines = vim.fn.readfile(path)
+    else
+      done(false, 'File not found: ' .. args.path)
+      return
+    end
+
+    local first = math.max(1, args.start_line or 1)
+    local last = math.min(#lines, args.end_line or #lines, first + M.config.max_read_lines - 1)
+    local out = {}
+    for i = first, last do
+      table.insert(out, string.format('%d: %s', i, lines[i]))
+    end
+    if last < #lines then
+      table.insert(out, string.format('... (%d more lines; read with start_line = %d)', #lines - last, last + 1))
+    end
+    done(true, table.concat(out, '\n'))
+  end,
+})
+
+M.register('list_dir', {
+  description = 'List the entries of a directory (directories end with "/")',
+  schema = {
+    type = 'object',
+    properties = { path = { type = 'string' } },
+  },
+  handler = function(args, done, ctx)
+    local path, outside = M.resolve_path(args.path, ctx)
+    if not path then
+      done(false, outside)
+      return
+    end
+    if vim.fn.isdirectory(path) == 0 then
+      done(false, 'Not a directory: ' .. (args.path or '.'))
+      return
+    end
+
+    local entries = {}
+    for name, kind in vim.fs.dir(path) do
+      if #entries >= M.config.max_results then
+        table.insert(entries, '...')
+        break
+      end
+      table.insert(entries, kind == 'directory' and name .. '/' or name)
+    end
+    table.sort(entries)
+    done(true, table.concat(entries, '\n'))
+  end,
+})
+
+M.register('grep', {
+  description = 'Search files for a regular expression; returns path:line:text matches',
+  schema = {
+    type = 'object',
+    properties = {
+      pattern = { type = 'string' },
+      path = { type = 'string' },
+    },
+    required = { 'pattern' },
+  },
+  handler = function(args, done, ctx)
+    local path, outside = M.resolve_path(args.path, ctx)
+    if not path then
+      done(false, outside)
+      return
+    end
+    local cmd
+    if vim.fn.executable('rg') == 1 then
+      cmd = { 'rg', '--line-number', '--no-heading', '--color=never', '--max-columns=300', '--', args.pattern, path }
+    else
+      cmd = { 'grep', '-rnI', '-E', '--', args.pattern, path }
+    end
+
+    local matches = {}
+    local job = vim.fn.jobstart(cmd, {
+      cwd = real_path(ctx.cwd or vim.fn.getcwd()),
+      stdout_buffered = true,
+      on_stdout = function(_, data)
+        for _, line in ipairs(data or {}) do
+          local file = line:match('^(.-):%d+:')
+          -- Privacy mode keeps sensitive files out of the results
+          if line ~= '' and not (file and privacy.enabled() and privacy.is_sensitive(file)) then
+            table.insert(matches, line)
+          end
+        end
+      end,
+      on_exit = function(_, code)
+        if code > 1 then
+          done(false, 'Search failed (exit ' .. code .. ')')
+          return
+        end
+        local total = #matches
+        if total > M.config.max_results then
+          matches = vim.list_slice(matches, 1, M.config.max_results)
+          table.insert(matches, string.format('... (%d more matches)', total - M.config.max_results))
+        end
+        done(true, total == 0 and 'No matches' or table.concat(matches, '\n'))
+      end,
+    })
+    if job <= 0 then
+      done(false, 'Could not start ' .. cmd[1])
+    end
+  end,
+})
+
+M.register('write_file', {
+  description = 'Create or overwrite a file with the given content (the user approves each write)',
+  mutating = true,
+  schema = {
+    type = 'object',
+    properties = {
+      path = { type = 'string' },
+      content = { type = 'string' },
+    },
+    required = { 'path', 'content' },
+  },
+  handler = function(args, done, ctx)
+    local path, outside = M.resolve_path(args.path, ctx)
+    if not path then
+      done(false, outside)
+      return
+    end
+    local kind = vim.fn.filereadable(path) == 1 and 'modify' or 'create'
+    approval.request({ kind = kind, path = path, content = args.content }, function(approved)
+      if not approved then
+        done(false, 'The user denied writing ' .. args.path)
+        return
+      end
+
+      if ctx.run_id then
+        checkpoints.snapshot(ctx.run_id, path)
+      end
+      local previous = audit.read_file(path)
+      vim.fn.mkdir(vim.fn.fnamemodify(path, ':h'), 'p')
+      if vim.fn.writefile(vim.split(args.content, '\n', { plain = true }), path) ~= 0 then
+        done(false, 'Could not write ' .. args.path)
+        return
+      end
+      if ctx.run_id then
+        checkpoints.record_write(ctx.run_id, path)
+      end
+      audit.record('file_write', { source = 'agent', path = path, before = previous, after = args.content })
+      vim.cmd('checktime')
+      done(true, string.format('Wrote %s (%s)', args.path, kind == 'create' and 'created' or 'modified'))
+    end)
+  end,
+})
+
+M.register('run_command', {
+  description = 'Run a shell command in the project root and return its exit code and output (the user approves it)',
+  mutating = true,
+  schema = {
+    type = 'object',
+    properties = { command = { type = 'string' } },
+    required = { 'command' },
+  },
+  handler = function(args, done, ctx)
+    local terminal = require('zeke.terminal')
+    local output = {}
+    terminal.propose(args.command, {
+      cwd = ctx.cwd,
+      on_line = function(line)
+        table.insert(output, line)
+      end,
+      on_exit = function(code)
+        local tail = vim.list_slice(output, math.max(1, #output - M.config.max_results + 1))
+        done(code == 0, string.format('exit code %s\n%s', tostring(code), table.concat(tail, '\n')))
+      end,
+      on_denied = function()
+        done(false, 'The user denied running: ' .. args.command)
+      end,
+    })
+  end,
+})
+
+return M
@@ -0,0 +1,206 @@
+--[[
+  ANSI escape sequence handling for captured task output
+
+  M.strip(text) removes escape sequences. M.parse(text) also turns SGR
+  color/style codes into highlight spans so a buffer can show the output
+  the way a terminal would, without the control codes in the text.
+--]]
+
+local M = {}
+
+local api = vim.api
+
+M.namespace = api.nvim_create_namespace('zeke_ansi')
+
+-- Standard and bright terminal colors (0-15)
+local palette = {
+  '#000000', '#cd0000', '#00cd00', '#cdcd00', '#0000ee', '#cd00cd', '#00cdcd', '#e5e5e5',
+  '#7f7f7f', '#ff0000', '#00ff00', '#ffff00', '#5c5cff', '#ff00ff', '#00ffff', '#ffffff',
+}
+
+-- Color of a 256-color palette index
+local function color_256(n)
+  if n < 16 then
+    return palette[n + 1]
+  elseif n < 232 then
+    n = n - 16
+    local steps = { 0, 95, 135, 175, 215, 255 }
+    return string.format('#%02x%02x%02x', steps[math.floor(n / 36) + 1], steps[math.floor(n / 6) % 6 + 1], steps[n % 6 + 1])
+  end
+  local level = 8 + (n - 232) * 10
+  return string.format('#%02x%02x%02x', level, level, level)
+end
+
+-- Drop everything a terminal would have overwritten with a carriage return
+local function apply_carriage_returns(text)
+  return text:match('([^\r]*)\r*$') or text
+end
+
+---Remove ANSI escape sequences (CSI, OSC and two-byte escapes)
+---@param text string
+---@return string
+function M.strip(text)
+  text = text:gsub('\27%[[0-?]*[ -/]*[@-~]', '')
+  text = text:gsub('\27%].-\7', ''):gsub('\27%].-\27\\', '')
+  text = text:gsub('\27[@-Z\\-_]', '')
+  return apply_carriage_returns(text)
+end
+
+-- Apply SGR parameters to a style table {fg, bg, bold, italic, underline, reverse}
+local function apply_sgr(style, params)
+  local codes = {}
+  for code in (params == '' and '0' or params):gmatch('%d+') do
+    table.insert(codes, tonumber(code))
+  end
+
+  local i = 1
+  while i <= #codes do
+    local code = codes[i]
+    if code == 0 then
+      for key in pairs(style) do
+        style[key] = nil
+      end
+    elseif code == 1 then
+      style.bold = true
+    elseif code == 3 then
+      style.italic = true
+    elseif code == 4 then
+      style.underline = true
+    elseif code == 7 then
+      style.reverse = true
+    elseif code == 22 then
+      style.bold = nil
+    elseif code == 23 then
+      style.italic = nil
+    elseif code == 24 then
+      style.underline = nil
+    elseif code == 27 then
+      style.reverse = nil
+    elseif code >= 30 and code <= 37 then
+      style.fg = palette[code - 29]
+    elseif code >= 90 and code <= 97 then
+      style.fg = palette[code - 81]
+    elseif code >= 40 and code <= 47 then
+      style.bg = palette[code - 39]
+    elseif code >= 100 and code <= 107 then
+      style.bg = palette[code - 91]
+    elseif code == 39 then
+      style.fg = nil
+    elseif code == 49 then
+      style.bg = nil
+    elseif code == 38 or code == 48 then
+      -- 38;5;n / 38;2;r;g;b (48 for background)
+      local key = code == 38 and 'fg' or 'bg'
+      if codes[i + 1] == 5 and codes[i + 2] then
+        style[key] = color_256(codes[i + 2])
+        i = i + 2
+      elseif codes[i + 1] == 2 and codes[i + 4] then
+        style[key] = string.format('#%02x%02x%02x', codes[i + 2], codes[i + 3], codes[i + 4])
+        i = i + 4
+      end
+    end
+    i = i + 1
+  end
+end
+
+-- Highlight group name for a style. Groups are only defined when a span is
+-- highlighted, so parsing stays safe in libuv callbacks.
+local styles = {}
+local defined = {}
+local function style_group(style)
+  if not next(style) then
+    return nil
+  end
+
+  local name = 'ZekeAnsi'
+    .. (style.fg and style.fg:sub(2) or 'x') .. '_'
+    .. (style.bg and style.bg:sub(2) or 'x') .. '_'
+    .. (style.bold and 'b' or '') .. (style.italic and 'i' or '')
+    .. (style.underline and 'u' or '') .. (style.reverse and 'r' or '')
+
+  if not styles[name] then
+    styles[name] = vim.deepcopy(style)
+  end
+  return name
+end
+
+local function define_group(name)
+  if defined[name] or not styles[name] then
+    return
+  end
+  local style = styles[name]
+  api.nvim_set_hl(0, name, {
+    fg = style.fg,
+    bg = style.bg,
+    bold = style.bold,
+    italic = style.italic,
+    underline = style.underline,
+    reverse = style.reverse,
+  })
+  defined[name] = true
+end
+
+---Strip escape sequences, keeping SGR styling as highlight spans
+---@param text string
+---@return string plain Text without escape sequences
+---@return table[] spans { {start_col, end_col, hl_group} } (0-based byte columns, end exclusive)
+function M.parse(text)
+  text = apply_carriage_returns(text)
+
+  local parts = {}
+  local spans = {}
+  local style = {}
+  local col = 0
+  local pos = 1
+
+  local function add_text(chunk)
+    if chunk == '' then
+      return
+    end
+    local group = style_group(style)
+    if group then
+      table.insert(spans, { col, col + #chunk, group })
+    end
+    table.insert(parts, chunk)
+    col = col + #chunk
+  end
+
+  while pos <= #text do
+    local esc = text:find('\27', pos, true)
+    if not esc then
+      add_text(text:sub(pos))
+      break
+    end
+    add_text(text:sub(pos, esc - 1))
+
+    local params, final, stop = text:match('^%[([0-?]*)[ -/]*([@-~])()', esc + 1)
+    if params then
+      if final == 'm' then
+        apply_sgr(style, params)
+      end
+      pos = stop
+    else
+      -- OSC (terminated by BEL or ST) or a two-byte escape
+      local osc_end = text:match('^%].-\7()', esc + 1) or text:match('^%].-\27\\()', esc + 1)
+      pos = osc_end or (esc + 2)
+    end
+  end
+
+  return table.concat(parts), spans
+end
+
+---Add highlight spans from M.parse to a buffer line
+---@param buf number
+---@param row number 0-based line
+---@param spans table[]
+function M.highlight(buf, row, spans)
+  for _, span in ipairs(spans or {}) do
+    define_group(span[3])
+    pcall(api.nvim_buf_set_extmark, buf, M.namespace, row, span[1], {
+      end_col = span[2],
+      hl_group = span[3],
+    })
+  end
+end
+
+return M
@@ -0,0 +1,118 @@
+--[[
+  Approval for AI-proposed file changes
+
+  Every file create/modify/delete proposed by the AI goes through
+  M.request(). The user can allow it once, allow that kind of change for the
+  rest of the session, or deny it. A custom handler can replace the built-in
+  prompt via `approval.handler` in setup().
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local privacy = require('zeke.privacy')
+
+M.config = {
+  -- function(change, decide) calling decide("once" | "session" | "deny")
+  handler = nil,
+}
+
+-- Change kinds allowed for the rest of the session
+M.session = {}
+
+function M.setup(opts)
+  M.config = vim.tbl_extend('force', M.config, opts or {})
+end
+
+---Whether a kind of change was allowed for the session
+---@param kind string "create" | "modify" | "delete"
+function M.is_allowed(kind)
+  return M.session[kind] == true
+end
+
+---Allow a kind of change for the rest of the session
+---@param kind string "create" | "modify" | "delete"
+function M.allow_session(kind)
+  M.session[kind] = true
+  logger.info('approval', 'Allowed ' .. kind .. ' for this session')
+end
+
+-- Forget session-wide approvals
+function M.reset()
+  M.session = {}
+end
+
+---Unified diff of a proposed change against the file on disk
+---@param change table {kind, path, content}
+---@return string
+function M.diff(change)
+  local old = ""
+  if vim.fn.filereadable(change.path) == 1 then
+    old = table.concat(vim.fn.readfile(change.path), "\n") .. "\n"
+  end
+  local new = change.kind == "delete" and "" or (change.content or "")
+  if new ~= "" and not new:match("\n$") then
+    new = new .. "\n"
+  end
+  return vim.diff(old, new, { result_type = "unified" }) or ""
+end
+
+local function show_diff(change)
+  local lines = vim.split(M.diff(change), "\n", { plain = true })
+  vim.cmd('tabnew')
+  local buf = vim.api.nvim_get_current_buf()
+  vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+  vim.bo[buf].buftype = 'nofile'
+  vim.bo[buf].bufhidden = 'wipe'
+  vim.bo[buf].filetype = 'diff'
+  vim.api.nvim_buf_set_name(buf, 'zeke://proposed/' .. change.path)
+end
+
+local function prompt(change, decide)
+  local choices = { 'Allow once', 'Allow for this session', 'Show diff', 'Deny' }
+  vim.ui.select(choices, {
+    prompt = string.format('AI wants to %s %s', change.kind, change.path),
+  }, function(choice)
+    if choice == 'Allow once' then
+      decide("once")
+    elseif choice == 'Allow for this session' then
+      decide("session")
+    elseif choice == 'Show diff' then
+      show_diff(change)
+      prompt(change, decide)
+    else
+      decide("deny")
+    end
+  end)
+end
+
+---Ask the user to approve a file change
+---@param change table {kind = "create"|"modify"|"delete", path = string, content = string|nil}
+---@param callback function Called with approved (boolean)
+function M.request(change, callback)
+  -- Privacy mode refuses file changes without asking
+  local permitted, refused = privacy.check_action('file_write')
+  if not permitted then
+    vim.notify(refused, vim.log.levels.WARN)
+    callback(false)
+    return
+  end
+
+  if M.is_allowed(change.kind) then
+    callback(true)
+    return
+  end
+
+  local handler = M.config.handler or prompt
+  handler(change, function(decision)
+    if decision == "session" then
+      M.allow_session(change.kind)
+    end
+
+    local approved = decision == "once" or decision == "session"
+    logger.info('approval', string.format('%s %s: %s', change.kind, change.path, approved and 'approved' or 'denied'))
+    callback(approved)
+  end)
+end
+
+return M
@@ -0,0 +1,156 @@
+--[[
+  Audit Log of AI-Driven Changes
+
+  Append-only JSON Lines record of every file write, buffer edit and
+  command that originated from the AI: accepted diffs and hunks, generated
+  files, test fixes, inline completions, MCP edits and commands, and
+  approved model-suggested shell commands. Each entry carries a timestamp,
+  the provider and model that produced the change, SHA-256 digests of the
+  content before and after, and a unified diff (capped at max_diff_chars).
+
+    {"time":"2026-01-01T12:00:00","kind":"file_write","source":"diff",
+     "path":"/src/main.rs","provider":"ollama","model":"qwen2.5-coder:7b",
+     "before_sha256":"...","after_sha256":"...","diff":"@@ -1,3 +1,4 @@ ..."}
+--]]
+
+local M = {}
+
+M.config = {
+  enabled = true,
+  file = '~/.local/state/zeke/audit.jsonl',
+  max_diff_chars = 20000,  -- Larger diffs keep only the digests
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+---Path of the audit log
+---@return string
+function M.path()
+  return vim.fn.expand(M.config.file)
+end
+
+local function as_text(content)
+  if type(content) == 'table' then
+    return table.concat(content, '\n') .. '\n'
+  end
+  return content or ''
+end
+
+-- Provider and model of the request the change came from
+local function origin()
+  local ok, cli = pcall(require, 'zeke.cli')
+  local last = ok and cli.last_response or {}
+  return last.provider, last.model
+end
+
+---Record an AI-driven change
+---@param kind string "file_write" | "buffer_edit" | "command"
+---@param entry table {source, path, before, after, command, cwd, provider, model, ...}
+---  before/after are strings or line lists; they are stored as digests and a diff
+function M.record(kind, entry)
+  if not M.config.enabled then
+    return
+  end
+
+  entry = vim.deepcopy(entry or {})
+  local before, after = entry.before, entry.after
+  entry.before, entry.after = nil, nil
+
+  local record = vim.tbl_extend('force', {
+    time = os.date('%Y-%m-%dT%H:%M:%S'),
+    kind = kind,
+  }, entry)
+  if record.path and record.path ~= '' then
+    record.path = vim.fn.fnamemodify(record.path, ':p')
+  end
+  if not record.provider and not record.model then
+    record.provider, record.model = origin()
+  end
+
+  if before or after then
+    before, after = as_text(before), as_text(after)
+    record.before_sha256 = vim.fn.sha256(before)
+    record.after_sha256 = vim.fn.sha256(after)
+    local diff = vim.diff(before, after, { result_type = 'unified' }) or ''
+    if #diff <= M.config.max_diff_chars then
+      record.diff = diff
+    else
+      record.diff_truncated = true
+    end
+  end
+
+  local path = M.path()
+  vim.fn.mkdir(vim.fn.fnamemodify(path, ':h'), 'p')
+  local file = io.open(path, 'a')
+  if file then
+    file:write(vim.json.encode(record) .. '\n')
+    file:close()
+  end
+end
+
+---Contents of a file on disk, for the "before" side of a write
+---@param path string
+---@return string
+function M.read_file(path)
+  if vim.fn.filereadable(path) == 0 then
+    return ''
+  end
+  return as_text(vim.fn.readfile(path))
+end
+
+---Query the audit log, newest first
+---@param filter table|nil {kind, source, path, provider, since = "YYYY-MM-DD[THH:MM:SS]", limit}
+---@return table[]
+function M.entries(filter)
+  filter = filter or {}
+  local path = M.path()
+  if vim.fn.filereadable(path) == 0 then
+    return {}
+  end
+
+  local full_path = filter.path and vim.fn.fnamemodify(filter.path, ':p')
+  local results = {}
+  local lines = vim.fn.readfile(path)
+  for i = #lines, 1, -1 do
+    local ok, record = pcall(vim.json.decode, lines[i])
+    if ok and type(record) == 'table'
+      and (not filter.kind or record.kind == filter.kind)
+      and (not filter.source or record.source == filter.source)
+      and (not filter.provider or record.provider == filter.provider)
+      and (not full_path or record.path == full_path)
+      and (not filter.since or (record.time or '') >= filter.since) then
+      table.insert(results, record)
+      if filter.limit and #results >= filter.limit then
+        break
+      end
+    end
+  end
+  return results
+end
+
+---Show recent entries in a scratch buffer
+---@param limit number|nil Default 50
+function M.show(limit)
+  local lines = {}
+  for _, record in ipairs(M.entries({ limit = limit or 50 })) do
+    local target = record.path and vim.fn.fnamemodify(record.path, ':~:.') or record.command or ''
+    table.insert(lines, string.format('%s  %-12s %-10s %-22s %s', record.time, record.kind,
+      record.source or '', (record.provider or '?') .. '/' .. (record.model or '?'), target))
+  end
+  if #lines == 0 then
+    vim.notify('Audit log is empty: ' .. M.path(), vim.log.levels.INFO)
+    return
+  end
+
+  vim.cmd('botright new')
+  local buf = vim.api.nvim_get_current_buf()
+  vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+  vim.bo[buf].buftype = 'nofile'
+  vim.bo[buf].bufhidden = 'wipe'
+  vim.bo[buf].modifiable = false
+  vim.api.nvim_buf_set_name(buf, 'zeke://audit')
+end
+
+return M
@@ -0,0 +1,192 @@
+--[[
+  Response Cache
+
+  Disk-backed cache for deterministic operations (explain, analyze,
+  embeddings): the same input sent to the same model with the same
+  parameters returns the stored response instead of another request.
+
+  Entries are content-addressed JSON files under
+  stdpath("cache")/zeke/responses, expire after ttl_hours, and the least
+  recently used are evicted once max_size_mb is exceeded. The cache is off
+  by default, since entries hold prompts and responses on disk, and is
+  skipped entirely while privacy mode is on.
+
+    response_cache = { enabled = true, operations = { "explain", "analyze", "embeddings" }, ttl_hours = 168 }
+
+  Callers opt in per request with opts.cache = "<operation>"; opts.cache =
+  false bypasses the cache. Embeddings are cached per text, so re-indexing
+  after small edits only embeds the chunks that changed.
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local privacy = require('zeke.privacy')
+
+M.config = {
+  enabled = false,
+  dir = vim.fn.stdpath('cache') .. '/zeke/responses',
+  operations = { 'explain', 'analyze', 'embeddings' },
+  ttl_hours = 168,
+  max_size_mb = 50,
+}
+
+-- Session counters
+M.stats = { hits = 0, misses = 0, writes = 0, expired = 0, evicted = 0 }
+
+function M.setup(opts)
+  M.config = vim.tbl_extend('force', M.config, opts or {})
+end
+
+---Whether an operation's responses are cached
+---@param operation string|false|nil
+---@return boolean
+function M.enabled_for(operation)
+  if privacy.enabled() then
+    return false
+  end
+  return M.config.enabled and type(operation) == 'string' and vim.tbl_contains(M.config.operations, operation)
+end
+
+---Cache key for a request
+---@param operation string
+---@param parts table Everything the response depends on (command, model, parameters, file contents)
+---@return string
+function M.key(operation, parts)
+  return vim.fn.sha256(operation .. '\0' .. vim.json.encode(parts))
+end
+
+local function entry_path(key)
+  return string.format('%s/%s/%s.json', vim.fn.expand(M.config.dir), key:sub(1, 2), key)
+end
+
+local function entries()
+  return vim.fn.glob(vim.fn.expand(M.config.dir) .. '/*/*.json', false, true)
+end
+
+---Stored response for a key
+---@param key string
+---@return string|nil
+function M.get(key)
+  local path = entry_path(key)
+  if vim.fn.filereadable(path) == 0 then
+    M.stats.misses = M.stats.misses + 1
+    return nil
+  end
+
+  local ok, entry = pcall(vim.json.decode, table.concat(vim.fn.readfile(path), '\n'))
+  if not ok or type(entry) ~= 'table' or type(entry.response) ~= 'string' then
+    vim.fn.delete(path)
+    M.stats.misses = M.stats.misses + 1
+    return nil
+  end
+
+  if os.time() - (entry.created or 0) > M.config.ttl_hours * 3600 then
+    vim.fn.delete(path)
+    M.stats.expired = M.stats.expired + 1
+    M.stats.misses = M.stats.misses + 1
+    return nil
+  end
+
+  -- The modification time doubles as the last-used time for eviction
+  local now = os.time()
+  vim.loop.fs_utime(path, now, now)
+  M.stats.hits = M.stats.hits + 1
+  logger.debug('cache', 'Hit for ' .. (entry.operation or '?') .. ' (' .. key:sub(1, 12) .. ')')
+  return entry.response
+end
+
+-- Drop least recently used entries until the cache fits max_size_mb
+local function evict()
+  local limit = M.config.max_size_mb * 1024 * 1024
+  local files, total = {}, 0
+  for _, path in ipairs(entries()) do
+    local size = vim.fn.getfsize(path)
+    total = total + size
+    table.insert(files, { path = path, size = size, used = vim.fn.getftime(path) })
+  end
+  if total <= limit then
+    return
+  end
+
+  table.sort(files, function(a, b) return a.used < b.used end)
+  for _, file in ipairs(files) do
+    if total <= limit then
+      break
+    end
+    vim.fn.delete(file.path)
+    total = total - file.size
+    M.stats.evicted = M.stats.evicted + 1
+  end
+end
+
+---Store a response
+---@param key string
+---@param response string
+---@param meta table|nil {operation, model, provider}
+function M.put(key, response, meta)
+  M.put_many({ [key] = response }, meta)
+end
+
+---Store several responses, evicting once afterwards
+---@param responses table key -> response
+---@param meta table|nil {operation, model, provider}
+function M.put_many(responses, meta)
+  local written = false
+  for key, response in pairs(responses) do
+    local path = entry_path(key)
+    vim.fn.mkdir(vim.fn.fnamemodify(path, ':h'), 'p')
+    local entry = vim.tbl_extend('force', meta or {}, { created = os.time(), response = response })
+    if vim.fn.writefile({ vim.json.encode(entry) }, path) == 0 then
+      M.stats.writes = M.stats.writes + 1
+      written = true
+    end
+  end
+  if written then
+    evict()
+  end
+end
+
+---Delete every cached response
+---@return number removed
+function M.clear()
+  local removed = 0
+  for _, path in ipairs(entries()) do
+    if vim.fn.delete(path) == 0 then
+      removed = removed + 1
+    end
+  end
+  logger.info('cache', string.format('Cleared %d cached responses', removed))
+  return removed
+end
+
+---Session counters plus what's on disk
+---@return table {hits, misses, writes, expired, evicted, hit_rate, entries, size_bytes, dir}
+function M.get_stats()
+  local files = entries()
+  local size = 0
+  for _, path in ipairs(files) do
+    size = size + vim.fn.getfsize(path)
+  end
+  local lookups = M.stats.hits + M.stats.misses
+  return vim.tbl_extend('force', vim.deepcopy(M.stats), {
+    hit_rate = lookups > 0 and M.stats.hits / lookups or 0,
+    entries = #files,
+    size_bytes = size,
+    dir = vim.fn.expand(M.config.dir),
+  })
+end
+
+---Human-readable statistics
+---@return string
+function M.format_stats()
+  local stats = M.get_stats()
+  return table.concat({
+    'Response cache' .. (M.config.enabled and '' or ' (disabled)'),
+    string.format('  Entries: %d (%.1f KB of %d MB)', stats.entries, stats.size_bytes / 1024, M.config.max_size_mb),
+    string.format('  This session: %d hits, %d misses (%.0f%% hit rate)', stats.hits, stats.misses, stats.hit_rate * 100),
+    string.format('  Written: %d, expired: %d, evicted: %d', stats.writes, stats.expired, stats.evicted),
+  }, '\n')
+end
+
+return M
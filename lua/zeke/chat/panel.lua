@@ -7,6 +7,7 @@ local api = vim.api
 local cli = require('zeke.cli')
 local logger = require('zeke.logger')
 local config = require('zeke.config')
+local stream_buffer = require('zeke.stream_buffer')
 
 -- State
 M.state = {
@@ -189,13 +190,24 @@ function M.stream_response(prompt)
     end
   end
 
+  -- Append streamed lines in place instead of re-rendering the panel
+  local writer = stream_buffer.writer(M.state.buf, {
+    prefix = '│ ',
+    footer = 2,
+    on_write = function(last_row)
+      if M.state.win and api.nvim_win_is_valid(M.state.win) then
+        api.nvim_win_set_cursor(M.state.win, { last_row + 1, 0 })
+      end
+    end,
+  })
+
   -- Stream response using CLI
   M.state.job_id = cli.stream_chat(context_prompt,
     -- on_chunk callback
     function(chunk)
       M.state.current_message.content = M.state.current_message.content .. chunk
       vim.schedule(function()
-        M.render()
+        writer.write(chunk)
       end)
     end,
     -- on_complete callback
@@ -248,11 +260,14 @@ function M.render()
     table.insert(lines, '')
   end
 
-  -- Render current streaming message
-  if M.state.is_streaming and M.state.current_message.content ~= '' then
+  -- Render current streaming message (chunks are then written in place by
+  -- the stream writer, above the cursor and border lines)
+  if M.state.is_streaming then
     table.insert(lines, '┌─ ZEKE (streaming...) ─────────────────────────────────────────────────┐')
-    for _, line in ipairs(vim.split(M.state.current_message.content, '\n')) do
-      table.insert(lines, '│ ' .. line)
+    if M.state.current_message.content ~= '' then
+      for _, line in ipairs(vim.split(M.state.current_message.content, '\n')) do
+        table.insert(lines, '│ ' .. line)
+      end
     end
     table.insert(lines, '│ ▋')  -- Cursor indicator
     table.insert(lines, '└───────────────────────────────────────────────────────────────────────┘')
@@ -3,9 +3,10 @@
 
   This module provides a clean interface for calling the Zeke CLI
   from Neovim. It replaces the HTTP client approach with direct
-  command execution via vim.fn.system().
+  command execution via vim.fn.system() and jobstart(). Commands are
+  argument lists, so prompts never pass through a local shell.
 
-  Architecture: Neovim (Lua) → vim.fn.system('zeke ...') → Zeke CLI
+  Architecture: Neovim (Lua) → vim.fn.system({ 'zeke', ... }) → Zeke CLI
 --]]
 
 local M = {}
@@ -13,10 +14,79 @@ local M = {}
 local requests = require('zeke.requests')
 local logger = require('zeke.logger')
 local errors = require('zeke.errors')
+local config = require('zeke.config')
+local transcript = require('zeke.transcript')
+local postprocess = require('zeke.postprocess')
+local json_schema = require('zeke.json_schema')
+local examples = require('zeke.examples')
+local wire_log = require('zeke.wire_log')
+local otlp = require('zeke.otlp')
+local tokens = require('zeke.tokens')
+local secrets = require('zeke.secrets')
+local privacy = require('zeke.privacy')
+local cache = require('zeke.cache')
+local warmup = require('zeke.warmup')
+
+-- Fire a User autocmd (ZekeStreamStart/Chunk/End/Error, ZekeStatusChanged)
+-- with a data payload
+local function emit(event, data)
+  vim.api.nvim_exec_autocmds("User", { pattern = event, data = data, modeline = false })
+end
+
+-- Last known CLI state: "unknown", "ready", "missing" or "error"
+M.state = { status = "unknown" }
+
+-- Provider and model behind the most recent successful response, so
+-- changes applied from it can be attributed (see zeke.audit)
+M.last_response = {}
+
+-- Record the CLI state, emitting ZekeStatusChanged on transitions
+local function set_state(status, err)
+  local previous = M.state.status
+  M.state.status = status
+  M.state.error = err
+  M.state.updated_at = os.time()
+
+  if status ~= previous then
+    logger.debug("cli", string.format("CLI status: %s -> %s", previous, status))
+    emit("ZekeStatusChanged", { status = status, previous = previous, error = err })
+  end
+end
+
+-- Remote host settings when the CLI runs over ssh (nil for local)
+function M.remote()
+  local remote = config.options and config.options.remote
+  if remote and remote.host then
+    return remote
+  end
+  return nil
+end
+
+-- Wrap a command to run on the remote host over ssh
+local function with_remote(cmd)
+  local remote = M.remote()
+  if not remote then
+    return cmd
+  end
+
+  if remote.cwd then
+    cmd = "cd " .. vim.fn.shellescape(remote.cwd) .. " && " .. cmd
+  end
+
+  local parts = { "ssh" }
+  for _, arg in ipairs(remote.ssh_args or {}) do
+    table.insert(parts, vim.fn.shellescape(arg))
+  end
+  table.insert(parts, vim.fn.shellescape(remote.host))
+  table.insert(parts, vim.fn.shellescape(cmd))
+
+  return table.concat(parts, " ")
+end
 
 -- Check if Zeke CLI is available
 function M.check_installation()
-  local handle = io.popen("which zeke 2>/dev/null")
+  local check = with_remote("which zeke") .. " 2>/dev/null"
+  local handle = io.popen(check)
   if not handle then
     return false, "Unable to check for zeke command"
   end
@@ -25,56 +95,407 @@ function M.check_installation()
   handle:close()
 
   if result == "" or result == nil then
+    if M.remote() then
+      return false, "Zeke CLI not found on " .. M.remote().host .. " (or ssh failed)"
+    end
     return false, "Zeke CLI not found in PATH. Install from: https://github.com/ghostkellz/zeke"
   end
 
-  return true, result:gsub("%s+$", "")
+  result = result:gsub("%s+$", "")
+  if M.remote() then
+    result = M.remote().host .. ":" .. result
+  end
+  return true, result
+end
+
+-- Environment overrides passed to the zeke process, derived from plugin config
+---@param task string|nil Task type ("chat", "edit", "completion", "analyze")
+---@param opts table|nil Per-call overrides {model, parameters, response_language}
+function M.build_env(task, opts)
+  opts = opts or {}
+  local env = {}
+  local insecure_providers = {}
+  local providers = (config.options and config.options.providers) or {}
+
+  for name, provider in pairs(providers) do
+    if type(provider) == "table" then
+      local base_url = provider.base_url
+      -- Ollama and LiteLLM fall back to their named host tables
+      if not base_url and name == "ollama" then
+        base_url = config.get_ollama_host()
+      elseif not base_url and name == "litellm" then
+        base_url = config.get_litellm_host()
+      end
+
+      local prefix = "ZEKE_" .. name:upper()
+      if base_url then
+        env[prefix .. "_BASE_URL"] = base_url
+      end
+
+      if provider.ca_bundle then
+        env[prefix .. "_CA_BUNDLE"] = vim.fn.expand(provider.ca_bundle)
+      end
+
+      if provider.insecure then
+        env[prefix .. "_TLS_INSECURE"] = "1"
+        insecure_providers[name] = true
+      end
+    end
+  end
+
+  local ca_bundle = config.options and config.options.ca_bundle
+  if ca_bundle then
+    env.ZEKE_CA_BUNDLE = vim.fn.expand(ca_bundle)
+    env.SSL_CERT_FILE = env.ZEKE_CA_BUNDLE
+  end
+
+  local proxy = (config.options and config.options.proxy) or {}
+  local http_proxy = proxy.http or proxy.url
+  local https_proxy = proxy.https or proxy.url
+
+  if http_proxy or https_proxy then
+    local no_proxy = proxy.no_proxy or os.getenv("NO_PROXY") or os.getenv("no_proxy")
+      or "localhost,127.0.0.1,::1"
+
+    local vars = {
+      HTTP_PROXY = http_proxy,
+      HTTPS_PROXY = https_proxy,
+      ALL_PROXY = proxy.url,
+      NO_PROXY = no_proxy,
+    }
+
+    -- Set both spellings; tools disagree on which one they read
+    for name, value in pairs(vars) do
+      env[name] = value
+      env[name:lower()] = value
+    end
+  end
+
+  -- The plugin's model selection (or the task's preset model, or a per-call
+  -- opts.model) applies to every request, with aliases resolved to concrete models.
+  -- opts.parameters overrides temperature / max_tokens / top_p for one call
+  local params = vim.tbl_extend("force", config.get_parameters(task), opts.parameters or {})
+  local models_ok, models = pcall(require, 'zeke.models')
+  local current = models_ok and models.get_current()
+  local model_id = opts.model or params.model or (current and current.id)
+  local provider = nil
+
+  if model_id and models_ok then
+    model_id, provider = models.resolve(model_id)
+    env.ZEKE_MODEL = model_id
+  end
+
+  provider = provider or (config.options and config.options.default_provider)
+  if provider then
+    env.ZEKE_PROVIDER = provider
+  end
+
+  -- Keep warmed-up Ollama models loaded between requests
+  local keep_alive = provider == "ollama" and warmup.keep_alive(model_id)
+  if keep_alive then
+    env.ZEKE_OLLAMA_KEEP_ALIVE = keep_alive
+  end
+
+  if params.temperature ~= nil then
+    env.ZEKE_TEMPERATURE = tostring(params.temperature)
+  end
+  if params.max_tokens ~= nil then
+    env.ZEKE_MAX_TOKENS = tostring(params.max_tokens)
+  end
+  if params.top_p ~= nil then
+    env.ZEKE_TOP_P = tostring(params.top_p)
+  end
+
+  local language = M.response_language(opts)
+  if language then
+    env.ZEKE_RESPONSE_LANGUAGE = language
+  end
+
+  -- The CLI's own log verbosity follows the plugin's
+  env.ZEKE_LOG = logger.cli_level()
+
+  -- Let the CLI enforce privacy mode on its side too
+  if privacy.enabled() then
+    env.ZEKE_OFFLINE = "1"
+  end
+
+  -- Provider-native JSON mode, where the CLI supports it
+  if opts.response_format then
+    env.ZEKE_RESPONSE_FORMAT = opts.response_format
+  end
+  if opts.response_schema then
+    env.ZEKE_RESPONSE_SCHEMA = vim.json.encode(opts.response_schema)
+  end
+
+  if not vim.tbl_isempty(insecure_providers) and not M._warned_insecure then
+    M._warned_insecure = true
+    logger.warn("cli", "TLS verification disabled for: " .. table.concat(vim.tbl_keys(insecure_providers), ", "))
+  end
+
+  return env
+end
+
+-- Command line for a CLI call given as an argument list, e.g.
+-- { "zeke", "chat", prompt }. Locally the list runs without a shell and the
+-- overrides go into the job environment; over ssh every argument is quoted
+-- for the remote shell and the overrides are inlined with `env`.
+-- Returns argv and the jobstart env (nil for remote calls).
+function M.command_line(args, opts)
+  local env = M.build_env(opts.task, opts)
+  local remote = M.remote()
+  if not remote then
+    return args, env
+  end
+
+  local keys = vim.tbl_keys(env)
+  table.sort(keys)
+  local words = { "env" }
+  for _, key in ipairs(keys) do
+    table.insert(words, key .. "=" .. vim.fn.shellescape(env[key]))
+  end
+  for _, arg in ipairs(args) do
+    table.insert(words, vim.fn.shellescape(arg))
+  end
+  local cmd = table.concat(words, " ")
+  if remote.cwd then
+    cmd = "cd " .. vim.fn.shellescape(remote.cwd) .. " && " .. cmd
+  end
+
+  local argv = { "ssh" }
+  vim.list_extend(argv, remote.ssh_args or {})
+  vim.list_extend(argv, { remote.host, cmd })
+  return argv, nil
+end
+
+
+-- Provider a request will go to (explicit, or the current model's)
+function M.current_provider(opts)
+  if opts and opts.provider then
+    return opts.provider
+  end
+
+  local ok, models = pcall(require, 'zeke.models')
+  if ok and opts and opts.model then
+    local _, provider = models.resolve(opts.model)
+    if provider then
+      return provider
+    end
+  end
+  local model = ok and models.get_current()
+  return model and model.provider or nil
 end
+local current_provider = M.current_provider
 
--- Escape string for shell command
-local function escape_shell(str)
-  if not str then return "" end
-  -- Replace quotes and backslashes
-  return str:gsub('"', '\\"'):gsub("'", "'\\''")
+-- Report a finished request: add its estimated cost to the usage totals
+-- and record it in the wire log and OTLP export, when enabled. Returns the
+-- cost estimate (nil when nothing was produced).
+-- fields: kind, status, exit_code, attempts, prompt, response, error, started_ns
+local function report(opts, fields)
+  local env = M.build_env(opts.task, opts)
+  local response = fields.response or ""
+  local provider = current_provider(opts)
+  local input_tokens = tokens.count(fields.prompt, env.ZEKE_MODEL)
+  local output_tokens = tokens.count(response, env.ZEKE_MODEL)
+
+  -- Cancelled and failed streams still pay for what they produced
+  local usage = nil
+  if fields.status == "ok" then
+    M.last_response = { provider = provider, model = env.ZEKE_MODEL, task = opts.task or "chat" }
+  end
+  if fields.status == "ok" or response ~= "" then
+    usage = tokens.record_request({
+      model = env.ZEKE_MODEL,
+      provider = provider,
+      input_tokens = input_tokens,
+      output_tokens = output_tokens,
+    })
+  end
+
+  if not wire_log.config.enabled and not otlp.config.enabled then
+    return usage
+  end
+  local entry = {
+    kind = fields.kind,
+    task = opts.task or "chat",
+    provider = provider,
+    model = env.ZEKE_MODEL,
+    status = fields.status,
+    exit_code = fields.exit_code,
+    attempts = fields.attempts,
+    duration_ms = math.floor((vim.loop.hrtime() - fields.started_ns) / 1e6),
+    prompt_chars = #fields.prompt,
+    response_chars = #response,
+    tokens = { prompt = input_tokens, response = output_tokens, estimated = true },
+    cost = usage and usage.cost or 0,
+    error = fields.error,
+    env = env,
+  }
+  wire_log.record(entry)
+  otlp.export(entry)
+  return usage
+end
+
+-- Privacy mode only lets requests through to local providers
+local function check_privacy(opts)
+  local env = M.build_env(opts.task or "chat", opts)
+  return privacy.check_provider(env.ZEKE_PROVIDER or current_provider(opts))
 end
 
--- Execute zeke command and return output (with retry support)
-local function execute(cmd, opts)
+-- Longest a blocking call waits for a response, retries included: every
+-- attempt and its backoff, capped at retry.max_wait_ms
+local function sync_deadline(policy, max_retries)
+  local cap = policy.max_wait_ms or 180000
+  if not policy.timeout_ms then
+    return cap
+  end
+  return math.min(cap, (policy.timeout_ms + policy.backoff.max_ms) * (max_retries + 1))
+end
+
+-- Run a zeke command with retries, without blocking. args is an argument
+-- list ({ "zeke", "chat", prompt }); it never goes through a local shell, so
+-- prompts, file contents and model output are passed as is.
+-- callback(result|nil, error, info) runs on the main loop, with
+-- info = {attempts, model, provider}. Returns cancel(), which stops the
+-- running attempt and any retries.
+local function execute_async(args, opts, callback)
   opts = opts or {}
-  logger.debug("cli", "Executing: " .. cmd)
+  local env_model = M.build_env(opts.task, opts).ZEKE_MODEL
+  local provider = current_provider(opts)
+  local info = { attempts = 0, model = env_model, provider = provider }
+  local function noop() end
+
+  local allowed, refused = check_privacy(opts)
+  if not allowed then
+    vim.schedule(function() callback(nil, refused, info) end)
+    return noop
+  end
+  local text = table.concat(args, " ")
+  logger.debug("cli", "Executing: " .. text)
+  local prompt = opts.prompt or text
+  local started_ns = vim.loop.hrtime()
+
+  -- Deterministic operations (opts.cache = "<operation>") are answered from
+  -- the response cache when the same input went to the same model before
+  local cache_key = nil
+  if cache.enabled_for(opts.cache) then
+    local env = M.build_env(opts.task, opts)
+    cache_key = cache.key(opts.cache, {
+      cmd = args,
+      inputs = opts.cache_inputs,
+      model = env.ZEKE_MODEL,
+      provider = env.ZEKE_PROVIDER,
+      temperature = env.ZEKE_TEMPERATURE,
+      max_tokens = env.ZEKE_MAX_TOKENS,
+      top_p = env.ZEKE_TOP_P,
+      language = env.ZEKE_RESPONSE_LANGUAGE,
+    })
+    local cached = cache.get(cache_key)
+    if cached then
+      vim.schedule(function() callback(cached, nil, info) end)
+      return noop
+    end
+  end
+
+  local policy = config.get_retry_policy(provider)
+  local argv, env = M.command_line(args, opts)
 
   -- Create request tracking
   local request = requests.create({
-    prompt = opts.prompt or cmd,
+    prompt = prompt,
     model = opts.model,
-    provider = opts.provider,
-    max_retries = opts.max_retries or 3,
+    provider = provider,
+    max_retries = opts.max_retries or policy.max_retries,
+    timeout = policy.timeout_ms,
+    backoff = policy.backoff,
   })
 
-  -- Execute with automatic retry
-  local result = nil
-  local error_msg = nil
+  local job, cancelled = nil, false
 
+  -- Execute with automatic retry
   requests.execute_with_retry(
     request,
     function(req, on_success, on_error)
-      -- Actual execution
-      local output = vim.fn.system(cmd)
-      local exit_code = vim.v.shell_error
-
-      if exit_code ~= 0 then
-        logger.error("cli", "Command failed with exit code " .. exit_code)
-        logger.error("cli", "Output: " .. output)
-        on_error("Command failed: " .. output)
-      else
-        on_success(output)
+      info.attempts = req.attempts
+      if cancelled then
+        on_error("Request cancelled")
+        return
+      end
+
+      local stdout, stderr, timed_out, timer = {}, {}, false, nil
+      job = vim.fn.jobstart(argv, {
+        env = env,
+        stdout_buffered = true,
+        stderr_buffered = true,
+        on_stdout = function(_, data)
+          stdout = data or {}
+        end,
+        on_stderr = function(_, data)
+          stderr = data or {}
+        end,
+        on_exit = function(_, exit_code)
+          job = nil
+          if timer and not timer:is_closing() then
+            timer:stop()
+            timer:close()
+          end
+          local output = table.concat(stdout, "\n")
+
+          if timed_out then
+            logger.error("cli", string.format("Command timed out after %dms", policy.timeout_ms))
+            on_error(string.format("Request timeout after %dms", policy.timeout_ms))
+          elseif cancelled then
+            on_error("Request cancelled")
+          elseif exit_code ~= 0 then
+            output = vim.trim(table.concat(stderr, "\n") .. "\n" .. output)
+            logger.error("cli", "Command failed with exit code " .. exit_code)
+            logger.error("cli", "Output: " .. output)
+            on_error("Command failed: " .. output)
+          else
+            on_success(postprocess.apply(output, opts.task, opts.postprocess))
+          end
+        end,
+      })
+      if job <= 0 then
+        job = nil
+        on_error("Failed to start zeke")
+        return
+      end
+      vim.fn.chanclose(job, "stdin")
+
+      -- Enforce the per-attempt timeout on the process itself
+      if policy.timeout_ms then
+        timer = vim.loop.new_timer()
+        timer:start(policy.timeout_ms, 0, vim.schedule_wrap(function()
+          timed_out = true
+          if job then
+            vim.fn.jobstop(job)
+          end
+        end))
       end
     end,
-    function(response)
-      result = response
+    function(response, req)
+      set_state("ready")
+      requests.record_outcome(provider, { latency_ms = math.floor((vim.loop.hrtime() - started_ns) / 1e6) })
+      report(opts, {
+        kind = "request", status = "ok", exit_code = 0, attempts = req.attempts,
+        prompt = prompt, response = response, started_ns = started_ns,
+      })
+      if cache_key then
+        cache.put(cache_key, response, { operation = opts.cache, provider = provider })
+      end
+      callback(response, nil, info)
     end,
     function(error, req, reason)
-      error_msg = error
+      if cancelled then
+        callback(nil, "Request cancelled", info)
+        return
+      end
+      set_state("error", error)
+      requests.record_outcome(provider, { latency_ms = math.floor((vim.loop.hrtime() - started_ns) / 1e6), error = error })
+      report(opts, {
+        kind = "request", status = error:match("timeout") and "timeout" or "error", attempts = req.attempts,
+        prompt = prompt, error = reason and (error .. " (" .. reason .. ")") or error, started_ns = started_ns,
+      })
       logger.error("cli", "Request failed after retries: " .. error)
       if reason then
         logger.error("cli", "Reason: " .. reason)
@@ -82,102 +503,695 @@ local function execute(cmd, opts)
 
       -- Show helpful error message
       errors.show(error)
+      callback(nil, error, info)
     end
   )
 
-  -- Wait for completion (blocking for now)
-  local timeout = 0
-  while result == nil and error_msg == nil and timeout < 300 do
-    vim.wait(100)
-    timeout = timeout + 1
+  return function()
+    cancelled = true
+    -- No further attempts once the current one ends
+    request.max_retries = 0
+    if job then
+      vim.fn.jobstop(job)
+    end
   end
+end
 
-  if error_msg then
-    return nil, error_msg
-  end
+-- Blocking form of execute_async(): waits up to sync_deadline() and
+-- returns the result, or nil and an error
+local function execute(args, opts)
+  opts = opts or {}
+  local done, result, error_msg = false, nil, nil
+  local cancel = execute_async(args, opts, function(response, err)
+    done, result, error_msg = true, response, err
+  end)
 
-  return result, nil
+  local policy = config.get_retry_policy(current_provider(opts))
+  local deadline = sync_deadline(policy, opts.max_retries or policy.max_retries)
+  if not vim.wait(deadline, function() return done end, 50) then
+    cancel()
+    return nil, string.format("Request timeout after %dms", deadline)
+  end
+  return result, error_msg
 end
 
 --[[
   Chat Commands
 --]]
 
-function M.chat(message)
-  local escaped = escape_shell(message)
-  local cmd = string.format('zeke chat "%s"', escaped)
-  return execute(cmd)
+-- Response language for a call: opts.response_language, else config
+function M.response_language(opts)
+  if opts and opts.response_language ~= nil then
+    return opts.response_language ~= false and opts.response_language or nil
+  end
+  return config.options and config.options.response_language or nil
+end
+
+-- Ask for the reply in the configured language (code stays untranslated)
+local function with_language_instruction(message, opts)
+  local language = M.response_language(opts)
+  if not language then
+    return message
+  end
+  return string.format("%s\n\nRespond in %s. Keep code, identifiers and commands unchanged.", message, language)
+end
+
+-- Whether a call should stream: per-call opts.stream, else config.stream
+local function use_stream(opts)
+  if opts and opts.stream ~= nil then
+    return opts.stream
+  end
+  return not (config.options and config.options.stream == false)
+end
+
+function M.chat(message, opts)
+  opts = opts or {}
+  message = examples.apply(message, "chat", opts.examples)
+
+  local call_opts = vim.tbl_extend("force", opts, { task = opts.task or "chat" })
+  call_opts.on_chunk = nil
+
+  if use_stream(opts) then
+    -- Stream and assemble the full response (opts.on_chunk sees partial output)
+    local done, response, exit_code = false, nil, nil
+    local id = M.stream_chat(message, opts.on_chunk, function(full_response, code)
+      response, exit_code, done = full_response, code, true
+    end, call_opts)
+
+    -- Same allowance as execute()
+    local policy = config.get_retry_policy(current_provider(call_opts))
+    local deadline = sync_deadline(policy, opts.max_retries or policy.max_retries)
+    vim.wait(deadline, function() return done end, 50)
+
+    if not done then
+      if id then
+        M.cancel_stream(id)
+      end
+      return nil, string.format("Request timeout after %dms", deadline)
+    elseif exit_code ~= 0 then
+      return nil, (response and response ~= "") and response or ("Stream failed with exit code " .. exit_code)
+    end
+    return response, nil
+  end
+
+  local prompt, blocked = secrets.check(message, opts.secret_scan)
+  if not prompt then
+    return nil, blocked
+  end
+  return execute({ "zeke", "chat", with_language_instruction(prompt, opts) }, call_opts)
 end
 
-function M.stream_chat(message, on_chunk, on_complete)
-  local escaped = escape_shell(message)
-  local cmd = string.format('zeke chat --stream "%s"', escaped)
+-- Streams keyed by the id returned to the caller; retries start new jobs
+M.active_streams = {}
+M.next_stream_id = 1
+
+-- jobstart delivers output in arbitrary chunks: the first item of each
+-- callback continues the last item of the previous one. Returns feed(data),
+-- calling on_line for every complete line, and flush() for the remainder.
+local function line_reader(on_line)
+  local partial = ""
+
+  local function feed(data)
+    partial = partial .. data[1]
+    for i = 2, #data do
+      on_line(partial)
+      partial = data[i]
+    end
+  end
+
+  local function flush()
+    if partial ~= "" then
+      on_line(partial)
+    end
+    partial = ""
+  end
+
+  return feed, flush
+end
+
+function M.stream_chat(message, on_chunk, on_complete, opts)
+  opts = opts or {}
+  local allowed, blocked = check_privacy(opts)
+  if allowed then
+    message, blocked = secrets.check(message, opts.secret_scan)
+  else
+    message = nil
+  end
+  if not message then
+    emit("ZekeStreamError", { error = blocked })
+    if on_complete then
+      on_complete(blocked, -1)
+    end
+    return nil
+  end
+  if (opts.task or "chat") == "chat" then
+    message = with_language_instruction(message, opts)
+  end
+  local streaming = use_stream(opts)
+
+  -- The prompt (and a resumed response's tail) is a single argument, never shell text
+  local function command_for(prompt)
+    local args = { "zeke", "chat" }
+    if streaming then
+      table.insert(args, "--stream")
+    end
+    table.insert(args, prompt)
+    return M.command_line(args, opts)
+  end
+  local provider = current_provider(opts)
+  local policy = config.get_retry_policy(provider)
+  local backlog_size = (config.options and config.options.stream_backlog) or 200
+
+  -- delivered: every line handed to on_chunk, across attempts
+  local stream = { id = M.next_stream_id, attempts = 0, cancelled = false, backlog = {}, subscribers = {}, delivered = {} }
+  M.next_stream_id = M.next_stream_id + 1
+
+  -- Tee the response into the session transcript (transcripts.enabled)
+  local tee = opts.transcript ~= false and transcript.records(opts.task)
+
+  -- Timing from the first attempt's start; chars from every attempt
+  local timing = { started_ns = nil, first_ns = nil, chunks = 0, chars = 0 }
+
+  local function stream_metrics()
+    local now = vim.loop.hrtime()
+    local duration_ms = timing.started_ns and math.floor((now - timing.started_ns) / 1e6) or 0
+    local tokens = math.ceil(timing.chars / 4)
+    local generating_s = timing.first_ns and (now - timing.first_ns) / 1e9 or 0
+    return {
+      ttft_ms = timing.first_ns and math.floor((timing.first_ns - timing.started_ns) / 1e6) or nil,
+      duration_ms = duration_ms,
+      chunks = timing.chunks,
+      tokens = tokens,
+      tokens_per_sec = generating_s > 0 and tokens / generating_s or 0,
+    }
+  end
+
+  -- Lines reach on_chunk, subscribers and ZekeStreamChunk in batches every
+  -- coalesce_ms (0 = one call per line), so fast streams don't flood the
+  -- callbacks. The batch table is reused; batch_size marks its live part.
+  local coalesce_ms = opts.coalesce_ms or (config.options and config.options.stream_coalesce_ms) or 0
+  local batch, batch_size = {}, 0
+  local batch_timer = nil
+
+  local function dispatch(text)
+    if on_chunk then
+      on_chunk(text)
+    end
+    for _, handler in ipairs(stream.subscribers) do
+      handler(text)
+    end
+    emit("ZekeStreamChunk", { id = stream.id, chunk = text })
+  end
+
+  local function flush_batch()
+    if batch_size == 0 or stream.cancelled then
+      batch_size = 0
+      return
+    end
+    local text = table.concat(batch, "", 1, batch_size)
+    batch_size = 0
+    dispatch(text)
+  end
+
+  local function deliver(text)
+    if coalesce_ms <= 0 then
+      dispatch(text)
+      return
+    end
+    batch_size = batch_size + 1
+    batch[batch_size] = text
+    batch_timer = batch_timer or vim.loop.new_timer()
+    if not batch_timer:is_active() then
+      batch_timer:start(coalesce_ms, 0, vim.schedule_wrap(flush_batch))
+    end
+  end
+
+  local function finish(full_response, exit_code)
+    if batch_timer then
+      batch_timer:stop()
+      batch_timer:close()
+      batch_timer = nil
+    end
+    flush_batch()
+    M.active_streams[stream.id] = nil
+    -- Streams cancelled in the queue never took a slot
+    if stream.attempts > 0 then
+      requests.release(provider)
+    end
+    local metrics = stream_metrics()
+    requests.record_stream(metrics)
+    if exit_code == 0 then
+      set_state("ready")
+      if config.options and config.options.stream_summary and metrics.ttft_ms then
+        vim.notify(string.format("Zeke: %dms to first token, %.1f tokens/s, %.1fs total",
+          metrics.ttft_ms, metrics.tokens_per_sec, metrics.duration_ms / 1000), vim.log.levels.INFO)
+      end
+    elseif not stream.cancelled then
+      set_state("error", "Stream failed with exit code " .. exit_code)
+      emit("ZekeStreamError", { id = stream.id, exit_code = exit_code })
+    end
+    if tee and stream.attempts > 0 then
+      transcript.finish(stream.id, exit_code, stream.cancelled)
+    end
+    if not stream.cancelled and stream.attempts > 0 then
+      requests.record_outcome(provider, {
+        latency_ms = metrics.duration_ms,
+        ttft_ms = metrics.ttft_ms,
+        error = exit_code ~= 0 and (stream.last_error or ("exit code " .. exit_code)) or nil,
+      })
+    end
+    local usage = report(opts, {
+      kind = "stream",
+      status = stream.cancelled and "cancelled" or (exit_code == 0 and "ok" or "error"),
+      exit_code = exit_code,
+      attempts = stream.attempts,
+      prompt = message,
+      response = full_response,
+      started_ns = timing.started_ns or vim.loop.hrtime(),
+    })
+    emit("ZekeStreamEnd", {
+      id = stream.id, exit_code = exit_code, cancelled = stream.cancelled, metrics = metrics, usage = usage,
+    })
+    if on_complete then
+      on_complete(full_response, exit_code)
+    end
+  end
+
+  -- Prompt asking the model to pick up an interrupted response
+  local function resume_prompt(tail)
+    return string.format(
+      "%s\n\n[Your previous response was cut off. It ended with:]\n%s\n[Continue exactly where it stopped, without repeating anything already written.]",
+      message, table.concat(tail, "\n"))
+  end
+
+  -- resume_tail: last delivered lines when resuming an interrupted stream
+  local function start(resume_tail)
+    -- Cancelled while waiting to retry: nothing is running, just release the slot
+    if stream.cancelled then
+      finish(table.concat(stream.delivered, "\n"), -1)
+      return
+    end
+
+    stream.attempts = stream.attempts + 1
+    timing.started_ns = timing.started_ns or vim.loop.hrtime()
+    local chunks = {}
+    local timer = nil
+    local process = postprocess.pipeline(opts.task, opts.postprocess)
+
+    -- A resumed response may repeat the lines it was shown; skip them
+    local skipping_overlap = resume_tail ~= nil
+
+    local feed, flush = line_reader(function(line)
+      if stream.stopped then
+        return
+      end
+      if skipping_overlap then
+        if line == "" or vim.tbl_contains(resume_tail, line) then
+          return
+        end
+        skipping_overlap = false
+      end
+      if process then
+        local stop
+        line, stop = process(line)
+        if stop then
+          -- Stop sequence reached: end the stream as a success
+          stream.stopped = true
+          vim.fn.jobstop(stream.job)
+        end
+        if not line then
+          return
+        end
+      end
 
-  -- Use jobstart for streaming
-  local chunks = {}
+      timing.first_ns = timing.first_ns or vim.loop.hrtime()
+      timing.chunks = timing.chunks + 1
+      timing.chars = timing.chars + #line + 1
+      if tee then
+        transcript.write(stream.id, line)
+      end
+      table.insert(chunks, line)
+      table.insert(stream.delivered, line)
+      table.insert(stream.backlog, line)
+      if #stream.backlog > backlog_size then
+        table.remove(stream.backlog, 1)
+      end
+      deliver(line .. "\n")  -- Add newline for proper rendering
+    end)
 
-  local job_id = vim.fn.jobstart(cmd, {
-    on_stdout = function(_, data, _)
-      for _, line in ipairs(data) do
-        if line ~= "" then
-          table.insert(chunks, line)
-          if on_chunk then
-            on_chunk(line .. "\n")  -- Add newline for proper rendering
+    -- Use jobstart for streaming
+    local argv, env = command_for(resume_tail and resume_prompt(resume_tail) or message)
+    local job_id = vim.fn.jobstart(argv, {
+      env = env,
+      on_stdout = function(_, data, _)
+        feed(data)
+      end,
+      -- CLI diagnostics go to the log file instead of being dropped
+      on_stderr = function(_, data, _)
+        for _, line in ipairs(data) do
+          if line ~= "" then
+            logger.record(logger.levels.WARN, "zeke", line)
+            stream.last_error = line
           end
         end
+      end,
+      on_exit = function(_, exit_code, _)
+        if timer and not timer:is_closing() then
+          timer:stop()
+          timer:close()
+        end
+        -- Output without a trailing newline
+        flush()
+        if stream.stopped then
+          exit_code = 0
+        end
+
+        -- Only streams that failed before any output are safe to retry
+        local can_retry = policy.stream_retries and not stream.cancelled
+          and exit_code ~= 0 and #chunks == 0
+          and stream.attempts <= policy.max_retries
+        if can_retry then
+          local delay = requests.calculate_backoff(stream.attempts, policy.backoff.base_ms, policy.backoff.max_ms)
+          logger.info("cli", string.format("Retrying stream in %dms (attempt %d)", delay, stream.attempts + 1))
+          vim.defer_fn(function() start(resume_tail) end, delay)
+          return
+        end
+
+        -- Streams cut off mid-response continue from where they stopped
+        local can_resume = policy.stream_resume and not stream.cancelled
+          and exit_code ~= 0 and #chunks > 0
+          and stream.attempts <= policy.max_retries
+        if can_resume then
+          local tail = vim.list_slice(stream.delivered, math.max(1, #stream.delivered - 19))
+          local delay = requests.calculate_backoff(stream.attempts, policy.backoff.base_ms, policy.backoff.max_ms)
+          logger.info("cli", string.format("Stream interrupted, resuming in %dms (attempt %d)", delay, stream.attempts + 1))
+          emit("ZekeStreamResumed", { id = stream.id, attempt = stream.attempts + 1, delivered = #stream.delivered })
+          vim.defer_fn(function() start(tail) end, delay)
+          return
+        end
+
+        finish(table.concat(stream.delivered, "\n"), exit_code)
+      end,
+    })
+
+    stream.job = job_id
+
+    if job_id <= 0 then
+      logger.error("cli", "Failed to start zeke process")
+      finish("", -1)
+      return
+    end
+
+    if stream.attempts == 1 then
+      emit("ZekeStreamStart", { id = stream.id, task = opts.task or "chat", provider = provider })
+      if tee then
+        transcript.begin(stream.id, message, { task = opts.task, provider = provider })
       end
-    end,
-    on_exit = function(_, exit_code, _)
-      local full_response = table.concat(chunks, "\n")
+    end
+
+    if policy.timeout_ms then
+      timer = vim.loop.new_timer()
+      timer:start(policy.timeout_ms, 0, vim.schedule_wrap(function()
+        logger.warn("cli", string.format("Stream timed out after %dms", policy.timeout_ms))
+        stream.last_error = "timed out"
+        vim.fn.jobstop(job_id)
+      end))
+    end
+  end
+
+  stream.start = start
+  stream.finish = finish
+  M.active_streams[stream.id] = stream
+
+  -- Queued streams dropped on overflow never held a slot
+  local function dropped(reason)
+    M.active_streams[stream.id] = nil
+    logger.warn("cli", "Request dropped: " .. reason)
+    emit("ZekeStreamError", { id = stream.id, error = reason })
+    if on_complete then
+      on_complete(reason, -1)
+    end
+  end
+
+  -- Starts now, or later once a concurrency slot frees up
+  local accepted, reason = requests.acquire(provider, start, dropped)
+  if not accepted then
+    M.active_streams[stream.id] = nil
+    logger.warn("cli", "Request rejected: " .. reason)
+    emit("ZekeStreamError", { id = stream.id, error = reason })
+    vim.schedule(function()
       if on_complete then
-        on_complete(full_response, exit_code)
+        on_complete(reason, -1)
       end
-    end,
-  })
+    end)
+    return nil
+  end
+
+  return stream.id
+end
+
+-- Recent output of an active stream (last `stream_backlog` lines)
+function M.stream_backlog(stream_id)
+  local stream = M.active_streams[stream_id]
+  return stream and vim.deepcopy(stream.backlog) or nil
+end
+
+-- Attach a late handler to an active stream: replays the backlog, then
+-- receives new chunks like on_chunk. Returns false if the stream has ended.
+function M.stream_subscribe(stream_id, handler)
+  local stream = M.active_streams[stream_id]
+  if not stream then
+    return false
+  end
+
+  for _, line in ipairs(stream.backlog) do
+    handler(line .. "\n")
+  end
+  table.insert(stream.subscribers, handler)
+  return true
+end
+
+function M.cancel_stream(stream_id)
+  local stream = M.active_streams[stream_id]
+  if not stream then
+    return
+  end
+
+  stream.cancelled = true
+  M.active_streams[stream_id] = nil
 
-  return job_id
+  if stream.job then
+    -- Stopping the CLI process closes its HTTP connection, so the provider
+    -- stops generating; on_exit (or a pending retry) releases the slot
+    vim.fn.jobstop(stream.job)
+  elseif requests.dequeue(stream.start) then
+    -- Still queued: end it like a stream cancelled while waiting to retry
+    stream.finish("", -1)
+  end
 end
 
-function M.cancel_stream(job_id)
-  vim.fn.jobstop(job_id)
+-- Race a prompt across several models ("provider/model" or model IDs,
+-- default: race.targets): all stream concurrently, the first successful
+-- response wins and the others are cancelled.
+-- on_complete(response, exit_code, winner); on failure of every target,
+-- on_complete(last_error, exit_code, nil). Returns the stream IDs.
+function M.race(message, on_complete, opts)
+  opts = opts or {}
+  local targets = opts.targets or (config.options and config.options.race or {}).targets or {}
+  if #targets == 0 then
+    vim.schedule(function()
+      on_complete("No race targets configured (race.targets)", -1, nil)
+    end)
+    return {}
+  end
+
+  -- Stream ID per target position (nil when the request was blocked);
+  -- the same model may be listed twice, e.g. on two providers
+  local ids = {}
+  local failures = 0
+  local decided = false
+  local last_error, last_code = nil, -1
+
+  local function started()
+    local list = {}
+    for i = 1, #targets do
+      if ids[i] then
+        table.insert(list, ids[i])
+      end
+    end
+    return list
+  end
+
+  for index, target in ipairs(targets) do
+    local target_opts = vim.tbl_extend("force", opts, { model = target })
+    target_opts.targets = nil
+    local id = M.stream_chat(message, nil, function(response, exit_code)
+      if decided then
+        return
+      end
+      if exit_code == 0 and response and vim.trim(response) ~= "" then
+        decided = true
+        for other = 1, #targets do
+          if other ~= index and ids[other] then
+            M.cancel_stream(ids[other])
+          end
+        end
+        logger.info("cli", "Race won by " .. target)
+        emit("ZekeRaceWon", { target = target, streams = started() })
+        on_complete(response, 0, target)
+        return
+      end
+
+      failures = failures + 1
+      last_error, last_code = response, exit_code
+      logger.warn("cli", string.format("Race target %s failed (exit %s)", target, tostring(exit_code)))
+      if failures == #targets then
+        decided = true
+        on_complete(last_error, last_code, nil)
+      end
+    end, target_opts)
+    ids[index] = id
+  end
+
+  return started()
 end
 
 --[[
   Code Operations
 --]]
 
-function M.explain(code, language)
-  local escaped_code = escape_shell(code)
-  local cmd
-  if language then
-    cmd = string.format('zeke explain "%s" %s', escaped_code, language)
-  else
-    cmd = string.format('zeke explain "%s"', escaped_code)
+function M.explain(code, language, opts)
+  local checked, blocked = secrets.check(code, opts and opts.secret_scan)
+  if not checked then
+    return nil, blocked
   end
-  return execute(cmd)
+  return execute({ "zeke", "explain", checked, language }, vim.tbl_extend("force", { cache = "explain" }, opts or {}, { task = "analyze" }))
 end
 
 function M.generate(description, language)
-  local escaped_desc = escape_shell(description)
-  local cmd
-  if language then
-    cmd = string.format('zeke generate "%s" %s', escaped_desc, language)
-  else
-    cmd = string.format('zeke generate "%s"', escaped_desc)
+  local checked, blocked = secrets.check(examples.apply(description, "generate"))
+  if not checked then
+    return nil, blocked
   end
-  return execute(cmd)
+  return execute({ "zeke", "generate", checked, language }, { task = "edit" })
 end
 
-function M.debug_code(error_description)
-  local escaped = escape_shell(error_description)
-  local cmd = string.format('zeke debug "%s"', escaped)
-  return execute(cmd)
+function M.debug_code(error_description, opts)
+  local checked, blocked = secrets.check(examples.apply(error_description, "debug", opts and opts.examples),
+    opts and opts.secret_scan)
+  if not checked then
+    return nil, blocked
+  end
+  return execute({ "zeke", "debug", checked }, vim.tbl_extend("force", opts or {}, { task = "analyze" }))
 end
 
-function M.analyze(file_path, analysis_type)
+function M.analyze(file_path, analysis_type, opts)
   analysis_type = analysis_type or "quality"
-  local cmd = string.format('zeke analyze "%s" %s', file_path, analysis_type)
-  return execute(cmd)
+  -- The analysis depends on the file's contents, not just its name
+  local contents = vim.fn.filereadable(file_path) == 1 and vim.fn.readfile(file_path) or nil
+  return execute({ "zeke", "analyze", file_path, analysis_type }, vim.tbl_extend("force", { cache = "analyze", cache_inputs = contents }, opts or {},
+    { task = "analyze" }))
+end
+
+--[[
+  Structured Output
+--]]
+
+-- Ask for JSON matching a JSON Schema. Uses the provider's JSON mode where
+-- the CLI supports it; replies that don't parse or validate are retried with
+-- the problems listed. With a callback the call doesn't block and
+-- callback(value|nil, error, usage) runs when it is done (returns cancel());
+-- without one it waits and returns the decoded value, or nil and an error,
+-- plus usage. usage = {tokens, cost, requests} covers every attempt sent.
+-- opts: max_attempts (default 3), task (default "analyze"), provider
+function M.generate_structured(prompt, schema, opts, callback)
+  opts = opts or {}
+  if not callback then
+    local done, value, error_msg, usage = false, nil, nil, nil
+    local cancel = M.generate_structured(prompt, schema, opts, function(result, err, spent)
+      done, value, error_msg, usage = true, result, err, spent
+    end)
+    local policy = config.get_retry_policy(current_provider(opts))
+    local deadline = sync_deadline(policy, opts.max_retries or policy.max_retries)
+    if not vim.wait(deadline, function() return done end, 50) then
+      cancel()
+      return nil, string.format("Request timeout after %dms", deadline)
+    end
+    return value, error_msg, usage
+  end
+
+  local blocked
+  prompt, blocked = secrets.check(prompt, opts.secret_scan)
+  if not prompt then
+    vim.schedule(function() callback(nil, blocked) end)
+    return function() end
+  end
+  local max_attempts = opts.max_attempts or 3
+  local instruction = string.format(
+    "%s\n\nRespond with only a JSON value matching this JSON Schema, without prose or code fences:\n%s",
+    prompt, vim.json.encode(schema))
+  local call_opts = vim.tbl_extend("force", opts, {
+    task = opts.task or "analyze",
+    response_format = "json",
+    response_schema = schema,
+    postprocess = false,
+  })
+
+  -- Estimated tokens and cost of every request sent, repairs and retries included
+  local usage = { tokens = 0, cost = 0, requests = 0 }
+  local function add_usage(request, response, info)
+    if not info or info.attempts == 0 then
+      return
+    end
+    local input = tokens.count(request, info.model) * info.attempts
+    local output = response and tokens.count(response, info.model) or 0
+    usage.requests = usage.requests + info.attempts
+    usage.tokens = usage.tokens + input + output
+    if not tokens.local_providers[info.provider or ""] then
+      usage.cost = usage.cost + tokens.calculate_cost(input, output, info.model)
+    end
+  end
+
+  local cancel_current, cancelled = nil, false
+  local function attempt(n, request)
+    cancel_current = execute_async({ "zeke", "chat", request }, call_opts, function(response, err, info)
+      if cancelled then
+        return
+      end
+      add_usage(request, response, info)
+      if not response then
+        callback(nil, err, usage)
+        return
+      end
+
+      local problems
+      local value, parse_err = json_schema.extract(response)
+      if value == nil then
+        problems = { "not valid JSON: " .. parse_err }
+      else
+        problems = json_schema.validate(value, schema)
+        if #problems == 0 then
+          callback(value, nil, usage)
+          return
+        end
+      end
+
+      logger.debug("cli", string.format("Structured output rejected (attempt %d/%d): %s",
+        n, max_attempts, table.concat(problems, "; ")))
+      if n >= max_attempts then
+        callback(nil, "Response did not match the schema: " .. table.concat(problems, "; "), usage)
+        return
+      end
+      attempt(n + 1, string.format("%s\n\nYour previous reply was rejected:\n- %s\n\nPrevious reply:\n%s",
+        instruction, table.concat(problems, "\n- "), response))
+    end)
+  end
+  attempt(1, instruction)
+
+  return function()
+    cancelled = true
+    cancel_current()
+  end
 end
 
 --[[
@@ -185,20 +1199,36 @@ end
 --]]
 
 function M.file_read(path)
-  local cmd = string.format('zeke file read "%s"', path)
-  return execute(cmd)
+  return execute({ "zeke", "file", "read", path })
 end
 
 function M.file_write(path, content)
-  local escaped_content = escape_shell(content)
-  local cmd = string.format('zeke file write "%s" "%s"', path, escaped_content)
-  return execute(cmd)
+  return execute({ "zeke", "file", "write", path, content }, { task = "edit" })
 end
 
-function M.file_edit(path, instruction)
-  local escaped = escape_shell(instruction)
-  local cmd = string.format('zeke file edit "%s" "%s"', path, escaped)
-  return execute(cmd)
+-- With a callback the edit doesn't block: callback(response|nil, error)
+function M.file_edit(path, instruction, callback)
+  local function fail(err)
+    if callback then
+      vim.schedule(function() callback(nil, err) end)
+      return
+    end
+    return nil, err
+  end
+  local readable, refused = privacy.check_path(path)
+  if not readable then
+    return fail(refused)
+  end
+  local checked, blocked = secrets.check(examples.apply(instruction, "edit"))
+  if not checked then
+    return fail(blocked)
+  end
+  local args = { "zeke", "file", "edit", path, checked }
+  if callback then
+    execute_async(args, { task = "edit" }, function(response, err) callback(response, err) end)
+    return
+  end
+  return execute(args, { task = "edit" })
 end
 
 --[[
@@ -206,7 +1236,7 @@ end
 --]]
 
 function M.provider_list()
-  local output, err = execute('zeke provider list')
+  local output, err = execute({ "zeke", "provider", "list" })
   if not output then
     return nil, err
   end
@@ -224,12 +1254,11 @@ function M.provider_list()
 end
 
 function M.provider_switch(provider)
-  local cmd = string.format('zeke provider switch %s', provider)
-  return execute(cmd)
+  return execute({ "zeke", "provider", "switch", provider })
 end
 
 function M.provider_status()
-  return execute('zeke provider status')
+  return execute({ "zeke", "provider", "status" })
 end
 
 --[[
@@ -237,16 +1266,15 @@ end
 --]]
 
 function M.model_list()
-  return execute('zeke model list')
+  return execute({ "zeke", "model", "list" })
 end
 
 function M.model_set(model)
-  local cmd = string.format('zeke model %s', model)
-  return execute(cmd)
+  return execute({ "zeke", "model", model })
 end
 
 function M.model_current()
-  return execute('zeke model')
+  return execute({ "zeke", "model" })
 end
 
 --[[
@@ -288,11 +1316,11 @@ end
 --]]
 
 function M.git_status()
-  return execute('zeke git status')
+  return execute({ "zeke", "git", "status" })
 end
 
 function M.git_diff()
-  return execute('zeke git diff')
+  return execute({ "zeke", "git", "diff" })
 end
 
 --[[
@@ -302,6 +1330,7 @@ end
 function M.health_check()
   local ok, path = M.check_installation()
   if not ok then
+    set_state("missing", path)
     return {
       installed = false,
       error = path,
@@ -309,7 +1338,7 @@ function M.health_check()
   end
 
   -- Check if we can run a simple command
-  local output, err = execute('zeke --version')
+  local output, err = execute({ "zeke", "--version" })
   if not output then
     return {
       installed = true,
@@ -319,11 +1348,61 @@ function M.health_check()
     }
   end
 
+  M.state.version = output:match("ZEKE v([%d%.]+)") or "unknown"
+
+  local compatible, version_msg = M.check_version(M.state.version)
+
   return {
     installed = true,
     path = path,
     working = true,
-    version = output:match("ZEKE v([%d%.]+)") or "unknown",
+    version = M.state.version,
+    compatible = compatible,
+    version_message = version_msg,
+  }
+end
+
+-- CLI versions this plugin speaks to: min inclusive, max exclusive
+M.supported_versions = { min = "0.3.0", max = "1.0.0" }
+
+-- Check a CLI version string against the supported range
+---@param version string|nil e.g. "0.3.2"
+---@return boolean compatible
+---@return string|nil message Reason when incompatible or unknown
+function M.check_version(version)
+  local ok, parsed = pcall(vim.version.parse, version or "")
+  if not ok or not parsed then
+    return true, "Could not determine Zeke CLI version"
+  end
+
+  local range = M.supported_versions
+  if vim.version.lt(parsed, range.min) then
+    return false, string.format("Zeke CLI %s is too old (need >= %s)", version, range.min)
+  end
+  if not vim.version.lt(parsed, range.max) then
+    return false, string.format("Zeke CLI %s is newer than supported (< %s); update zeke.nvim", version, range.max)
+  end
+
+  return true
+end
+
+-- Current CLI state plus stream activity (for statuslines and health checks)
+function M.status()
+  local stats = requests.get_stats()
+  local remote = M.remote()
+
+  return {
+    status = M.state.status,
+    version = M.state.version,
+    error = M.state.error,
+    updated_at = M.state.updated_at,
+    remote = remote and remote.host or nil,
+    privacy = privacy.status(),
+    streams = {
+      active = vim.tbl_count(M.active_streams),
+      running = stats.running,
+      queued = stats.queued,
+    },
   }
 end
 
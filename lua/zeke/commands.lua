@@ -15,6 +15,10 @@ local diff = require('zeke.diff')
 local backup = require('zeke.backup')
 local safety = require('zeke.safety')
 local progress = require('zeke.progress')
+local approval = require('zeke.approval')
+local audit = require('zeke.audit')
+local privacy = require('zeke.privacy')
+local terminal = require('zeke.terminal')
 
 -- Helper: Get buffer content
 local function get_buffer_content()
@@ -111,12 +115,45 @@ function M.chat(message)
   show_floating_window(response, { title = ' Chat Response ' })
 end
 
+--[[
+  Race Command
+--]]
+function M.race(message)
+  if not message or message == '' then
+    vim.ui.input({ prompt = 'Race message: ' }, function(input)
+      if input then
+        M.race(input)
+      end
+    end)
+    return
+  end
+
+  logger.info('commands', 'Race: ' .. message)
+  vim.notify('Racing the configured models...', vim.log.levels.INFO)
+
+  cli.race(message, function(response, exit_code, winner)
+    vim.schedule(function()
+      if exit_code ~= 0 then
+        vim.notify('Error: ' .. (response or 'every race target failed'), vim.log.levels.ERROR)
+        return
+      end
+      show_floating_window(response, { title = ' Chat Response (' .. winner .. ') ' })
+    end)
+  end)
+end
+
 --[[
   Explain Command
 --]]
 function M.explain()
   logger.info('commands', 'Explain current buffer')
 
+  local readable, refused = privacy.check_buffer(0)
+  if not readable then
+    vim.notify(refused, vim.log.levels.WARN)
+    return
+  end
+
   local content = get_buffer_content()
   local filetype = get_filetype()
 
@@ -236,17 +273,27 @@ function M.create_file(description)
       return
     end
 
-    -- Write to file
-    local file = io.open(file_path, 'w')
-    if file then
-      file:write(response)
-      file:close()
-      vim.notify('Created: ' .. file_path, vim.log.levels.INFO)
-      -- Open the file
-      vim.cmd('edit ' .. file_path)
-    else
-      vim.notify('Failed to write file', vim.log.levels.ERROR)
-    end
+    local kind = vim.fn.filereadable(file_path) == 1 and 'modify' or 'create'
+    approval.request({ kind = kind, path = file_path, content = response }, function(approved)
+      if not approved then
+        vim.notify('File creation denied', vim.log.levels.INFO)
+        return
+      end
+
+      -- Write to file
+      local previous = audit.read_file(file_path)
+      local file = io.open(file_path, 'w')
+      if file then
+        file:write(response)
+        file:close()
+        audit.record('file_write', { source = 'create_file', path = file_path, before = previous, after = response })
+        vim.notify('Created: ' .. file_path, vim.log.levels.INFO)
+        -- Open the file
+        vim.cmd('edit ' .. file_path)
+      else
+        vim.notify('Failed to write file', vim.log.levels.ERROR)
+      end
+    end)
   end)
 end
 
@@ -394,18 +441,42 @@ function M.show_model_picker()
 end
 
 --[[
-  Task Management (Placeholders)
+  Task Management
 --]]
 function M.list_tasks()
-  vim.notify('Task management coming soon', vim.log.levels.INFO)
+  local lines = { 'Zeke Tasks', '' }
+
+  for _, task in ipairs(terminal.get_active_tasks()) do
+    if task.status == 'queued' then
+      table.insert(lines, string.format('#%d queued (%d)  %s', task.id, task.queue_position, task.cmd))
+    else
+      table.insert(lines, string.format('#%d running %ds  %s', task.id, task.duration, task.cmd))
+    end
+  end
+
+  local history = terminal.get_task_history()
+  for i = #history, math.max(1, #history - 9), -1 do
+    local task = history[i]
+    table.insert(lines, string.format('#%d %s (exit %s, %.1fs)  %s',
+      task.id, task.status, tostring(task.exit_code), task.duration_ms / 1000, task.cmd))
+  end
+
+  if #lines == 2 then
+    table.insert(lines, 'No tasks')
+  end
+  vim.notify(table.concat(lines, '\n'), vim.log.levels.INFO)
 end
 
 function M.cancel_task(task_id)
-  vim.notify('Task cancellation coming soon', vim.log.levels.INFO)
+  if not task_id then
+    vim.notify('Usage: :ZekeCancelTask {id}', vim.log.levels.WARN)
+    return
+  end
+  terminal.cancel_task(task_id)
 end
 
 function M.cancel_all_tasks()
-  vim.notify('Cancel all tasks coming soon', vim.log.levels.INFO)
+  terminal.cancel_all_tasks()
 end
 
 return M
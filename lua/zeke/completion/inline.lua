@@ -7,6 +7,8 @@ local api = vim.api
 local cli = require('zeke.cli')
 local logger = require('zeke.logger')
 local config = require('zeke.config')
+local utils = require('zeke.utils')
+local audit = require('zeke.audit')
 
 -- Namespace for extmarks
 M.ns_id = api.nvim_create_namespace('zeke_inline_completion')
@@ -125,10 +127,7 @@ function M.request_completion()
 
   -- Build prompt
   local filename = api.nvim_buf_get_name(bufnr)
-  local prompt = string.format(
-    "Complete the following %s code. Provide ONLY the completion text that should appear after the cursor, nothing else:\n\n```%s\n%s",
-    ft, ft, context
-  )
+  local prompt = config.render_prompt('completion', { filetype = ft, code = context })
 
   -- Store current position
   M.state.bufnr = bufnr
@@ -152,21 +151,27 @@ function M.request_completion()
         M.state.active_job = nil
         if exit_code == 0 then
           -- Clean up the response (remove markdown fences, etc.)
-          local cleaned = M.clean_response(full_response)
+          local cleaned = M.clean_response(full_response, ft)
           M.state.suggestions = { cleaned }
           M.state.current_idx = 1
           M.render_suggestion(cleaned, bufnr, line_num, col)
         end
       end)
-    end
+    end,
+    { task = 'completion' }
   )
 end
 
 -- Clean AI response to extract just the code
-function M.clean_response(response)
-  -- Remove markdown code blocks
-  local cleaned = response:gsub('```[%w]*\n?', '')
-  cleaned = cleaned:gsub('```', '')
+function M.clean_response(response, filetype)
+  -- Keep only the code of a fenced block; otherwise drop stray fence lines
+  local block = utils.first_code_block(response, filetype)
+  local cleaned = response
+  if block and block:match('%S') then
+    cleaned = block
+  else
+    cleaned = cleaned:gsub('\n?[ \t]*[`~][`~][`~]+[^\n]*', '')
+  end
 
   -- Remove common prefixes
   cleaned = cleaned:gsub('^Here[^\n]*\n', '')
@@ -245,6 +250,17 @@ function M.render_suggestion(text, bufnr, line_num, col)
   end
 end
 
+-- Record an accepted completion replacing one line
+local function audit_accept(bufnr, line_num, old_line, new_lines)
+  audit.record('buffer_edit', {
+    source = 'completion',
+    path = api.nvim_buf_get_name(bufnr),
+    lines = { line_num, line_num },
+    before = { old_line },
+    after = new_lines,
+  })
+end
+
 -- Accept current suggestion
 function M.accept()
   if not M.state.suggestions or #M.state.suggestions == 0 then
@@ -274,6 +290,7 @@ function M.accept()
     -- Single line: insert at cursor
     local new_line = before_cursor .. lines[1] .. after_cursor
     api.nvim_buf_set_lines(bufnr, line_num - 1, line_num, false, { new_line })
+    audit_accept(bufnr, line_num, current_line, { new_line })
 
     -- Move cursor to end of insertion
     api.nvim_win_set_cursor(0, { line_num, #before_cursor + #lines[1] })
@@ -290,6 +307,7 @@ function M.accept()
     table.insert(new_lines, lines[#lines] .. after_cursor)
 
     api.nvim_buf_set_lines(bufnr, line_num - 1, line_num, false, new_lines)
+    audit_accept(bufnr, line_num, current_line, new_lines)
 
     -- Move cursor to end of last inserted line (before after_cursor)
     api.nvim_win_set_cursor(0, { line_num + #lines - 1, #lines[#lines] })
@@ -332,6 +350,7 @@ function M.accept_word()
   local new_line = current_line:sub(1, col) .. word .. current_line:sub(col + 1)
 
   api.nvim_buf_set_lines(bufnr, line_num - 1, line_num, false, { new_line })
+  audit_accept(bufnr, line_num, current_line, { new_line })
   api.nvim_win_set_cursor(0, { line_num, col + #word })
 
   -- Update suggestion to remove accepted word
@@ -369,6 +388,7 @@ function M.accept_line()
   local new_line = current_line:sub(1, col) .. first_line .. current_line:sub(col + 1)
 
   api.nvim_buf_set_lines(bufnr, line_num - 1, line_num, false, { new_line })
+  audit_accept(bufnr, line_num, current_line, { new_line })
   api.nvim_win_set_cursor(0, { line_num, col + #first_line })
 
   M.clear()
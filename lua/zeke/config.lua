@@ -13,6 +13,16 @@ local M = {}
 M.defaults = {
   -- Model selection
   default_model = 'smart',  -- 'smart', 'fast', 'balanced', or specific model ID
+  default_provider = nil,   -- Provider used when the model is an alias (e.g. 'ollama')
+
+  -- Model aliases usable anywhere a model ID is accepted, e.g.
+  -- { fast = "groq/llama-3.1-70b", smart = "claude-sonnet-4-5" }
+  aliases = {},
+
+  -- Global sampling defaults; parameters.<task> overrides these
+  temperature = nil,
+  max_tokens = nil,
+  top_p = nil,
 
   -- Ollama hosts (localhost + remote)
   ollama_hosts = {
@@ -42,6 +52,9 @@ M.defaults = {
   },
 
   -- Provider configuration
+  -- Any provider accepts base_url to route through a gateway or
+  -- OpenAI-compatible proxy, e.g. openai = { base_url = "https://llm.corp/v1" }
+  -- TLS per provider: ca_bundle = "/etc/ssl/corp-ca.pem", insecure = true
   providers = {
     claude = { enabled = true },
     openai = { enabled = true },
@@ -53,6 +66,310 @@ M.defaults = {
     litellm = { enabled = true, default_host = "localhost" },
   },
 
+  -- Proxy for provider traffic. When unset, the CLI inherits
+  -- HTTP_PROXY / HTTPS_PROXY / NO_PROXY from the environment.
+  proxy = {
+    url = nil,       -- Applies to all schemes: "http://proxy.corp:3128", "socks5://127.0.0.1:1080"
+    http = nil,      -- Overrides url for http://
+    https = nil,     -- Overrides url for https://
+    no_proxy = nil,  -- Defaults to $NO_PROXY, or localhost when a proxy is set
+  },
+
+  -- Language for explanations and analysis (e.g. "German"); nil = model default
+  response_language = nil,
+
+  -- Stream responses (per-call opts.stream overrides). When off, streaming
+  -- UIs receive the whole response as one chunk.
+  stream = true,
+
+  -- Lines of output kept per active stream so late listeners can catch up
+  stream_backlog = 200,
+
+  -- Deliver streamed lines to callbacks in batches every N ms (e.g. 30-50)
+  -- instead of one call per line; helps with very fast providers. 0 = off
+  stream_coalesce_ms = 0,
+
+  -- Notify time-to-first-token and tokens/s when a stream completes
+  stream_summary = false,
+
+  -- Transform stages applied to responses per task: "strip_fences",
+  -- "collapse_blank_lines", "trim_leading_blank", "trim_trailing_whitespace"
+  -- and { "stop", "<seq>", ... } (ends the response at a stop sequence)
+  postprocess = {
+    completion = { "strip_fences" },
+  },
+
+  -- Few-shot example sets (<name>.md with "## Input" / "## Output" pairs,
+  -- from .zeke/examples/ or dir) appended to operations' prompts:
+  -- operations = { generate = { "tests" }, edit = { "style" } }. Operations
+  -- are chat, edit, generate, debug and prompt template names.
+  few_shot = {
+    dir = "~/.config/zeke/prompts/examples",
+    operations = {},
+  },
+
+  -- Saved prompts (:ZekePromptSave, :ZekePrompt), one Markdown file each
+  prompt_library = {
+    dir = "~/.config/zeke/prompts",
+  },
+
+  -- JSON Lines record of each CLI request's metadata: sizes, estimated
+  -- tokens, status, timing and the CLI environment with credentials masked
+  wire_log = {
+    enabled = false,
+    file = "~/.local/state/zeke/wire.jsonl",
+  },
+
+  -- Load local Ollama / llama.cpp models as soon as they're selected and
+  -- keep Ollama models loaded (keep_alive) so the first request is fast.
+  -- Per-model overrides: models = { ["qwen2.5-coder:7b"] = { keep_alive = "2h" } },
+  -- or false to skip a model
+  warmup = {
+    enabled = false,
+    keep_alive = "30m",
+    models = {},
+  },
+
+  -- Gather git diff/status and LSP symbols in the background (refreshed on
+  -- writes, focus and directory changes) so sending a request doesn't wait on them
+  context_snapshots = {
+    enabled = true,
+    debounce_ms = 500,
+    max_age_s = 60,
+  },
+
+  -- Embedding backend for require("zeke").embed(): "openai", "ollama",
+  -- "cohere" or "voyage". API keys come from providers.<name>.api_key or
+  -- OPENAI_API_KEY / COHERE_API_KEY / VOYAGE_API_KEY
+  embeddings = {
+    provider = "ollama",
+    model = nil,  -- Default: nomic-embed-text, text-embedding-3-small, embed-english-v3.0, voyage-code-3
+    batch_size = 64,   -- Texts per request (OpenAI accepts up to 2048)
+    concurrency = 2,   -- Batches in flight at once
+    timeout_s = 60,
+  },
+
+  -- Reranking for retrieval (require("zeke").rerank()): "llamacpp" is any
+  -- local server with a /v1/rerank endpoint (llama.cpp --reranking, vLLM,
+  -- Infinity); "cohere" needs COHERE_API_KEY
+  rerank = {
+    provider = "llamacpp",
+    model = nil,  -- Default: bge-reranker-v2-m3, rerank-v3.5
+    url = nil,    -- Default: providers.<name>.base_url or http://localhost:8080
+    timeout_s = 30,
+  },
+
+  -- Speech to text for voice prompts (:ZekeTranscribe): "whispercpp" is a
+  -- local whisper.cpp server, "openai" the Whisper API
+  transcription = {
+    provider = "whispercpp",
+    model = nil,     -- Default: whisper-1 (openai); whisper.cpp uses its loaded model
+    url = nil,       -- Default: providers.<name>.base_url or http://localhost:8080
+    language = nil,  -- e.g. "en"; nil = detect
+    timeout_s = 120,
+  },
+
+  -- Local OpenAI-compatible endpoint (/v1/chat/completions, /v1/models) so
+  -- other tools can use zeke's providers, routing and cost tracking. Set
+  -- token to require "Authorization: Bearer <token>"
+  server = {
+    enabled = false,
+    host = "127.0.0.1",
+    port = 8790,
+    token = nil,
+    max_body_kb = 4096,
+  },
+
+  -- Agent runs (:ZekeAgent): the model calls tools (read_file, list_dir,
+  -- grep, write_file, run_command) one step at a time until it answers.
+  -- Writes go through the approval prompt, commands through terminal policy.
+  -- Limits stop a run with a summary; max_tokens / max_cost (USD) are
+  -- estimates, e.g. max_tokens = 200000, max_cost = 0.50, max_time_s = 600
+  agent = {
+    max_steps = 20,
+    observation_chars = 4000,  -- Tool output kept per step in the prompt
+    plan_first = false,        -- Review and approve a numbered plan before the run starts
+    approve_steps = false,     -- Confirm each file write / command step before it runs
+    tools = {
+      max_read_lines = 400,
+      max_results = 200,
+    },
+  },
+
+  -- :ZekeRace / require("zeke").race(): send a prompt to all of these
+  -- models at once ("provider/model" or model IDs) and keep the first
+  -- successful response
+  race = {
+    targets = {},
+  },
+
+  -- Disk cache for deterministic operations (:ZekeCache): identical input,
+  -- model and parameters return the stored response. Off by default and
+  -- skipped in privacy mode; dir defaults to stdpath("cache")/zeke/responses
+  response_cache = {
+    enabled = false,
+    operations = { "explain", "analyze", "embeddings" },
+    ttl_hours = 168,
+    max_size_mb = 50,
+  },
+
+  -- Privacy/offline mode (:ZekePrivacy): requests only to local providers,
+  -- files matching sensitive_paths (globs) never sent, and AI-driven file
+  -- writes and commands refused
+  privacy = {
+    enabled = false,
+    local_providers = { "ollama", "lmstudio", "llamacpp", "whispercpp" },
+    sensitive_paths = {
+      ".env", ".env.*", "*.pem", "*.key", "*.p12", "*.pfx", "id_rsa*", "id_ed25519*",
+      "*/.ssh/*", "*/.aws/*", "*/.gnupg/*", "*/secrets/*", ".netrc", "*.kdbx",
+    },
+  },
+
+  -- Append-only record of every file write, buffer edit and command that
+  -- came from the AI, with provider, digests and diffs (:ZekeAudit)
+  audit = {
+    enabled = true,
+    file = "~/.local/state/zeke/audit.jsonl",
+    max_diff_chars = 20000,
+  },
+
+  -- Scan outgoing prompts for likely secrets (cloud/API keys, private key
+  -- blocks, bearer tokens, .env credentials): "redact" them, "warn" and
+  -- send anyway, "block" the request, or "off". `allow` lists Lua patterns
+  -- of values never flagged. Per call: opts.secret_scan = "<mode>".
+  secret_scan = {
+    mode = "redact",
+    allow = {},
+  },
+
+  -- Per-model prices (USD per 1K tokens) overriding the bundled table,
+  -- e.g. { ["gpt-4o"] = { input = 0.0025, output = 0.01 } }
+  pricing = {},
+
+  -- Export a span per request to an OTLP/HTTP collector (JSON encoding)
+  otlp = {
+    enabled = false,
+    endpoint = "http://localhost:4318/v1/traces",
+    headers = {},          -- e.g. { Authorization = "Bearer ..." }
+    service_name = "zeke.nvim",
+    flush_interval_ms = 5000,
+    max_batch = 50,
+  },
+
+  -- Tee streamed responses into a Markdown transcript per session
+  transcripts = {
+    enabled = false,
+    dir = nil,             -- Default: stdpath("state")/zeke/transcripts
+    redact = true,         -- Mask API keys, tokens and passwords before writing
+    exclude_tasks = { "completion" },
+  },
+
+  -- Sampling parameters per task type (temperature, max_tokens, top_p,
+  -- and optionally a model/alias to route that task to)
+  parameters = {
+    chat = { temperature = 0.7 },
+    edit = { temperature = 0 },
+    completion = { temperature = 0.2, max_tokens = 256 },
+    analyze = { temperature = 0.3 },
+  },
+
+  -- Request timeout and retry policy. Providers may override any of these
+  -- keys, e.g. ollama = { timeout_ms = 300000, max_retries = 0 }
+  retry = {
+    timeout_ms = 120000,
+    max_retries = 3,
+    backoff = { base_ms = 1000, max_ms = 32000 },
+    stream_retries = false,  -- Retry streams that fail before the first chunk
+    stream_resume = false,   -- Ask the model to continue streams cut off mid-response
+    max_wait_ms = 180000,    -- Longest a blocking request freezes the editor, retries included
+  },
+
+  -- Concurrent async requests (chat streams, completions)
+  concurrency = {
+    max_requests = 4,
+    max_per_provider = 2,
+    queue_size = 16,
+    -- When all slots are busy: "queue" waits (rejecting once the queue is full),
+    -- "drop_oldest" evicts the longest-waiting request instead, "reject" fails immediately
+    overflow = "queue",
+  },
+
+  -- Port range for this instance's ~/.zeke/ide lock file; each Neovim picks a
+  -- port no other live instance has claimed
+  lockfile_ports = { 7878, 7977 },
+
+  -- Seconds between sweeps removing lock files of dead instances (0 = only at startup)
+  lockfile_cleanup_interval = 300,
+
+  -- Background tasks run through the terminal module
+  tasks = {
+    timeout_ms = 0,  -- Kill tasks running longer than this (0 = no limit)
+    shell = nil,     -- sh, bash, zsh, fish, pwsh, powershell or cmd (default: cmd on Windows, sh elsewhere)
+    max_output_lines = 5000,  -- Output lines kept per task (older lines are dropped)
+    max_concurrent = 4,       -- Running tasks at once; more wait in a queue (0 = unlimited)
+    -- Escape codes in captured output: "strip", "preserve" (kept as buffer
+    -- highlights, removed from the text) or "raw" (left as-is)
+    ansi = "strip",
+
+    -- Defaults for long-running watchers (:ZekeWatch)
+    watchers = {
+      restart = "on-failure",  -- "on-failure", "always" or "never"
+      restart_delay_ms = 1000,
+      max_restarts = 5,
+    },
+
+    -- Sandbox for model-suggested commands: writes limited to the project
+    -- directory, network off unless enabled
+    sandbox = {
+      enabled = false,
+      backend = nil,   -- "bwrap", "firejail" or "sandbox-exec" (default: first available)
+      network = false,
+    },
+
+    -- Checked before any task starts (Lua patterns). Deny wins; a non-empty
+    -- allow list blocks everything it doesn't match; confirm always asks.
+    policy = {
+      allow = {},
+      deny = {},
+      confirm = {
+        "^%s*rm%s",
+        "^%s*sudo%s",
+        "git%s+push.*%-%-force",
+        "git%s+reset%s+%-%-hard",
+        "curl.*|%s*sh",
+      },
+    },
+  },
+
+  -- :ZekeTestFix: run the tests, ask the AI to fix failures, repeat
+  test_fix = {
+    command = nil,     -- Test command (default: cargo test, jest or pytest, detected from the project)
+    max_rounds = 3,    -- Test runs before giving up
+    output_lines = 80, -- Tail of the test output sent with each fix request
+  },
+
+  -- Run the Zeke CLI on another machine over ssh
+  remote = {
+    host = nil,       -- e.g. "devbox" or "user@devbox" (nil = run locally)
+    cwd = nil,        -- Remote project directory to run commands in
+    ssh_args = { "-o", "BatchMode=yes" },
+  },
+
+  -- Approval for AI-proposed file changes (allow once / for session / deny)
+  approval = {
+    handler = nil,  -- function(change, decide) to replace the built-in prompt
+  },
+
+  -- MCP server: editor tools exposed to external agents (see :ZekeMcpConfig)
+  mcp = {
+    allow_edits = true,
+    allow_commands = false,   -- Let agents run Ex commands
+    require_approval = true,  -- Confirm edits/commands in the editor
+  },
+
+  -- Extra CA bundle trusted for all providers (per-provider ca_bundle wins)
+  ca_bundle = nil,
+
   -- Model cycling behavior
   cycling = {
     -- Models to include in Tab cycling
@@ -92,13 +409,36 @@ M.defaults = {
     quick_smart = '<leader>zms',   -- Quick switch to 'smart'
     quick_fast = '<leader>zmf',    -- Quick switch to 'fast'
     quick_local = '<leader>zml',   -- Quick switch to local (Ollama)
+
+    -- Quick actions
+    explain_code = '<leader>ze',   -- :ZekeExplainCode
+    fix_code = '<leader>zf',       -- :ZekeFixCode
+    refactor_code = '<leader>zr',  -- :ZekeRefactorCode
+    generate_tests = '<leader>zt', -- :ZekeGenerateTests
+
+    -- AI assistance shortcuts (set any mapping to false to disable it)
+    ai_ask = '<leader>aa',
+    ai_fix = '<leader>af',
+    ai_edit = '<leader>ae',
+    ai_explain = '<leader>ax',
+    ai_chat = '<leader>ac',
+    ai_toggle_completions = '<leader>at',
+  },
+
+  -- User commands: map a built-in name to false to skip it or to a string to rename it
+  -- e.g. commands = { ZekeCode = "Agent", ZekeBackups = false }
+  commands = {
+    enabled = true,
   },
 
   -- Logging
   logger = {
-    level = "INFO",
-    file = "~/.cache/nvim/zeke.log",
+    level = "INFO",              -- Change at runtime with :ZekeLogLevel
+    file = "~/.local/state/zeke/zeke.nvim.log",
     show_timestamp = true,
+    max_size = 1048576,          -- Roll over at 1 MB
+    max_files = 3,               -- Rolled files kept (zeke.nvim.log.1 ... .3)
+    cli_level = nil,             -- ZEKE_LOG for the CLI (default: level)
   },
 
   -- Selection tracking
@@ -126,14 +466,301 @@ M.defaults = {
     script_dirs = { ".zeke", "scripts" },
     fallback_to_lua = true,
   },
+
+  -- Prompt templates: {code}, {filetype}, {language}, {fence}, {instruction}, {selection},
+  -- {diagnostics} placeholders and {?var}...{/var} sections. Per-provider
+  -- overrides go in prompts.providers.<provider>.<name>; files in
+  -- templates_dir (<name>.md, <name>.<provider>.md) take precedence.
+  templates_dir = "~/.config/zeke/templates",
+  prompts = {
+    completion = "Complete the following {?language}{language} {/language}code. Provide ONLY the completion text that should appear after the cursor, nothing else:\n\n```{fence}\n{code}",
+    edit_selection = "Edit the following {?language}{language} {/language}code:\n\n```{fence}\n{code}\n```\n\nInstruction: {instruction}\n\nReturn the result in a ```{fence} code block.",
+    fix_diagnostic = "{code}\n\nPlease provide a fix for the diagnostic issue on this line. Return ONLY the corrected code for the affected area.",
+    explain_diagnostic = "{code}\n\nPlease explain what this diagnostic means and how to fix it.",
+  },
+
+  -- File-based config (JSON), layered over the setup() table:
+  -- defaults < setup() < global file < project file. A project file is
+  -- only loaded once trusted (:trust, same prompt as 'exrc' files)
+  config_files = {
+    global = "~/.config/zeke/nvim.json",
+    project = ".zeke/config.json",
+    watch = true,  -- Reload live when either file changes
+  },
 }
 
 -- Current configuration (merged with user options)
 M.options = {}
 
+-- Options passed to setup(), kept so reloads can re-layer the files
+M.user_opts = {}
+
+-- Active file watchers, keyed by absolute path
+M.watchers = {}
+
+-- Read a JSON config file, returning nil when it doesn't exist. Files that
+-- come with a checked-out project can redirect providers or turn off
+-- safety options, so those go through vim.secure.read() (asks once, then
+-- remembers the decision in the trust database)
+local function read_config_file(path, untrusted)
+  if vim.fn.filereadable(path) ~= 1 then
+    return nil
+  end
+
+  local ok, content
+  if untrusted then
+    if not (vim.secure and vim.secure.read) then
+      return nil, "Ignoring " .. path .. ": project config files need Neovim 0.9+ (vim.secure)"
+    end
+    ok, content = pcall(vim.secure.read, path)
+    if ok and content == nil then
+      return nil  -- Not trusted
+    end
+  else
+    local lines
+    ok, lines = pcall(vim.fn.readfile, path)
+    content = ok and table.concat(lines, "\n") or nil
+  end
+  if not ok then
+    return nil, "Failed to read " .. path
+  end
+
+  local decode_ok, data = pcall(vim.json.decode, content)
+  if not decode_ok or type(data) ~= "table" then
+    return nil, "Invalid JSON in " .. path
+  end
+
+  return data
+end
+
+-- Absolute paths of the global and project config files
+function M.config_file_paths(files)
+  files = files or (M.options.config_files or M.defaults.config_files)
+  local paths = {}
+
+  if files.global then
+    paths.global = vim.fn.expand(files.global)
+  end
+
+  if files.project then
+    paths.project = vim.fn.getcwd() .. "/" .. files.project
+  end
+
+  return paths
+end
+
+-- Expand $VAR / ${VAR} in a string ($$ for a literal $)
+function M.expand_env(value)
+  local function lookup(name)
+    return os.getenv(name) or ""
+  end
+
+  local result = value:gsub("%$%$", "\0")
+    :gsub("%${([%a_][%w_]*)}", lookup)
+    :gsub("%$([%a_][%w_]*)", lookup)
+    :gsub("%z", "$")
+  return result
+end
+
+-- Values that expand $VAR: credentials, URLs, hosts and paths. Everything
+-- else (prompt templates, patterns, commands) keeps its $ as written.
+M.env_keys = { "key$", "token$", "secret$", "password$", "url$", "^endpoint$", "host$",
+  "^file$", "^dir$", "_dir$", "_path$", "^ca_bundle$" }
+M.env_tables = { ollama_hosts = true, litellm_hosts = true, proxy = true, headers = true, script_dirs = true }
+
+local function expands_env(key, parent)
+  if parent and M.env_tables[parent] then
+    return true
+  end
+  key = tostring(key)
+  for _, pattern in ipairs(M.env_keys) do
+    if key:match(pattern) then
+      return true
+    end
+  end
+  return false
+end
+
+-- Recursively expand environment variables in credential, URL and path values
+local function expand_env_values(tbl, parent)
+  local expanded = {}
+  for key, value in pairs(tbl) do
+    if type(value) == "string" and expands_env(key, parent) then
+      expanded[key] = M.expand_env(value)
+    elseif type(value) == "table" then
+      expanded[key] = expand_env_values(value, key)
+    else
+      expanded[key] = value
+    end
+  end
+  return expanded
+end
+
+-- Merge defaults, setup() options and config files into one table
+local function resolve(opts)
+  local merged = vim.tbl_deep_extend("force", M.defaults, opts)
+  local paths = M.config_file_paths(merged.config_files)
+
+  for _, scope in ipairs({ "global", "project" }) do
+    local path = paths[scope]
+    if path then
+      local data, err = read_config_file(path, scope == "project")
+      if data then
+        local problems = M.validate(data)
+        if #problems > 0 then
+          vim.notify("zeke.nvim config (" .. path .. "):\n  " .. table.concat(problems, "\n  "), vim.log.levels.WARN)
+        end
+        merged = vim.tbl_deep_extend("force", merged, data)
+      elseif err then
+        vim.notify("zeke.nvim: " .. err, vim.log.levels.WARN)
+      end
+    end
+  end
+
+  return expand_env_values(merged)
+end
+
+-- Options whose defaults are nil, or that accept more than their default's type
+M.schema_types = {
+  ["proxy.url"] = "string",
+  ["proxy.http"] = "string",
+  ["proxy.https"] = "string",
+  ["proxy.no_proxy"] = "string",
+  ["remote.host"] = "string",
+  ["remote.cwd"] = "string",
+  ["approval.handler"] = "function",
+  ["tasks.shell"] = "string",
+  ["tasks.sandbox.backend"] = "string",
+  ["test_fix.command"] = "string",
+  ["transcripts.dir"] = "string",
+  ["logger.cli_level"] = "string",
+  ["response_cache.dir"] = "string",
+  ["embeddings.model"] = "string",
+  ["rerank.model"] = "string",
+  ["rerank.url"] = "string",
+  ["transcription.model"] = "string",
+  ["transcription.url"] = "string",
+  ["transcription.language"] = "string",
+  ["server.token"] = "string",
+  ["agent.max_tokens"] = "number",
+  ["agent.max_cost"] = "number",
+  ["agent.max_time_s"] = "number",
+  ["ca_bundle"] = "string",
+  ["default_provider"] = "string",
+  ["temperature"] = "number",
+  ["max_tokens"] = "number",
+  ["top_p"] = "number",
+  ["response_language"] = "string",
+  ["completion"] = "table|boolean",
+  ["statusline"] = "table|boolean",
+  ["chat"] = "table",
+  ["create_lockfile"] = "boolean",
+  ["keymaps.chat_panel"] = "string",
+  ["keymaps.fix_diagnostic"] = "string",
+  ["keymaps.explain_diagnostic"] = "string",
+}
+
+-- Tables whose children are user-defined (host names, provider settings, ...)
+M.schema_open = {
+  ollama_hosts = true,
+  litellm_hosts = true,
+  providers = true,
+  prompts = true,
+  aliases = true,
+  parameters = true,
+  commands = true,
+  postprocess = true,
+  few_shot = true,
+  otlp = true,
+  pricing = true,
+  warmup = true,
+}
+
+local function collect_schema(tbl, prefix, out)
+  for key, value in pairs(tbl) do
+    local path = prefix and (prefix .. "." .. key) or key
+    local is_map = type(value) == "table" and not vim.tbl_islist(value)
+
+    out[path] = {
+      key = path,
+      type = M.schema_types[path] or type(value),
+      default = value,
+    }
+
+    if is_map then
+      collect_schema(value, path, out)
+    end
+  end
+end
+
+---Machine-readable description of every config key
+---@return table List of {key, type, default}, sorted by key
+function M.schema()
+  local by_path = {}
+  collect_schema(M.defaults, nil, by_path)
+
+  for path, type_name in pairs(M.schema_types) do
+    if not by_path[path] then
+      by_path[path] = { key = path, type = type_name, default = nil }
+    end
+  end
+
+  local entries = vim.tbl_values(by_path)
+  table.sort(entries, function(a, b) return a.key < b.key end)
+  return entries
+end
+
+---Validate a setup table against the schema
+---@param opts table Options as passed to setup()
+---@return table List of problem descriptions (empty when valid)
+function M.validate(opts)
+  local by_path = {}
+  for _, entry in ipairs(M.schema()) do
+    by_path[entry.key] = entry
+  end
+
+  local problems = {}
+
+  local function walk(tbl, prefix)
+    for key, value in pairs(tbl) do
+      local path = prefix and (prefix .. "." .. key) or tostring(key)
+      local entry = by_path[path]
+
+      if not entry then
+        if not (prefix and M.schema_open[prefix:match("^[^.]+")]) then
+          table.insert(problems, "Unknown option: " .. path)
+        end
+      else
+        local allowed = vim.split(entry.type, "|", { plain = true })
+        -- `false` disables any string/table option (e.g. keymaps.chat = false)
+        local ok = vim.tbl_contains(allowed, type(value))
+          or (value == false and (vim.tbl_contains(allowed, "string") or vim.tbl_contains(allowed, "table")))
+
+        if not ok then
+          table.insert(problems, string.format("%s: expected %s, got %s", path, entry.type, type(value)))
+        elseif type(value) == "table" and not vim.tbl_islist(value) then
+          walk(value, path)
+        end
+      end
+    end
+  end
+
+  walk(opts or {}, nil)
+  return problems
+end
+
+local function warn_invalid(opts)
+  local problems = M.validate(opts)
+  if #problems > 0 then
+    vim.notify("zeke.nvim config:\n  " .. table.concat(problems, "\n  "), vim.log.levels.WARN)
+  end
+end
+
 function M.setup(opts)
   opts = opts or {}
-  M.options = vim.tbl_deep_extend("force", M.defaults, opts)
+  M.user_opts = opts
+  M.options = resolve(opts)
+  warn_invalid(opts)
 
   -- Validate Ollama hosts
   for name, host in pairs(M.options.ollama_hosts) do
@@ -155,9 +782,124 @@ function M.setup(opts)
     end
   end
 
+  if M.options.config_files.watch then
+    M.watch()
+  end
+
   return M.options
 end
 
+-- Re-read config files and notify listeners via the ZekeConfigReloaded event
+function M.reload()
+  local previous = M.options
+  M.options = resolve(M.user_opts)
+  warn_invalid(M.user_opts)
+
+  vim.api.nvim_exec_autocmds("User", {
+    pattern = "ZekeConfigReloaded",
+    data = { previous = previous, options = M.options },
+  })
+
+  return M.options
+end
+
+-- Stop all config file watchers
+function M.unwatch()
+  for path, handle in pairs(M.watchers) do
+    handle:stop()
+    handle:close()
+    M.watchers[path] = nil
+  end
+end
+
+-- Watch the global and project config files for changes
+function M.watch()
+  M.unwatch()
+
+  local reload_timer = nil
+  local function schedule_reload()
+    -- Editors often write in several steps; debounce into a single reload
+    if reload_timer then
+      reload_timer:stop()
+      reload_timer:close()
+    end
+    reload_timer = vim.loop.new_timer()
+    reload_timer:start(100, 0, vim.schedule_wrap(function()
+      reload_timer:close()
+      reload_timer = nil
+      M.reload()
+      -- Atomic saves replace the inode, so re-arm the watchers
+      M.watch()
+    end))
+  end
+
+  for _, path in pairs(M.config_file_paths()) do
+    if vim.fn.filereadable(path) == 1 then
+      local handle = vim.loop.new_fs_event()
+      local ok = handle and handle:start(path, {}, function(err)
+        if not err then
+          schedule_reload()
+        end
+      end)
+
+      if ok then
+        M.watchers[path] = handle
+      elseif handle then
+        handle:close()
+      end
+    end
+  end
+
+  -- The project file is relative to cwd
+  if not M.dir_changed_autocmd then
+    M.dir_changed_autocmd = vim.api.nvim_create_autocmd("DirChanged", {
+      callback = function()
+        if M.options.config_files.watch then
+          M.reload()
+          M.watch()
+        end
+      end,
+      desc = "Reload zeke.nvim project config on cwd change",
+    })
+  end
+end
+
+-- Effective timeout/retry policy for a provider
+function M.get_retry_policy(provider)
+  local base = (M.options.retry or M.defaults.retry)
+  local policy = vim.deepcopy(base)
+  local provider_opts = provider and M.options.providers and M.options.providers[provider]
+
+  if type(provider_opts) == "table" then
+    for _, key in ipairs({ "timeout_ms", "max_retries", "stream_retries", "stream_resume", "max_wait_ms" }) do
+      if provider_opts[key] ~= nil then
+        policy[key] = provider_opts[key]
+      end
+    end
+    if provider_opts.backoff then
+      policy.backoff = vim.tbl_extend("force", policy.backoff, provider_opts.backoff)
+    end
+  end
+
+  return policy
+end
+
+-- Sampling parameters for a task type
+function M.get_parameters(task)
+  local parameters = M.options.parameters or M.defaults.parameters
+  local result = {
+    temperature = M.options.temperature,
+    max_tokens = M.options.max_tokens,
+    top_p = M.options.top_p,
+  }
+  return vim.tbl_extend("force", result, parameters[task or "chat"] or {})
+end
+
+-- Render a prompt template (see zeke.templates), substituting {placeholders}
+function M.render_prompt(name, vars, provider)
+  return require('zeke.templates').render_named(name, vars, provider)
+end
+
 -- Get Ollama host by name
 function M.get_ollama_host(name)
   name = name or M.options.providers.ollama.default_host
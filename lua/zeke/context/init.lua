@@ -3,6 +3,7 @@ local M = {}
 
 local tools = require('zeke.context.tools')
 local logger = require('zeke.logger')
+local utils = require('zeke.utils')
 
 -- Build context from various sources
 function M.build_context(opts)
@@ -57,22 +58,21 @@ function M.format_for_request(context)
   -- Current file
   if context.current_file then
     local file = context.current_file
-    table.insert(parts, string.format("**Current File**: `%s` (%d lines, %s)%s\n```%s\n%s\n```",
+    table.insert(parts, string.format("**Current File**: `%s` (%d lines, %s)%s\n%s",
       file.path,
       file.line_count,
       file.language or "unknown",
       file.is_modified and " [modified]" or "",
-      file.language or "",
-      file.content))
+      utils.format_code_block(file.content, file.language)))
   end
 
   -- Selection
   if context.selection and context.selection.text ~= "" and not context.selection.isEmpty then
-    table.insert(parts, string.format("**Selected Text** (lines %d-%d from `%s`):\n```\n%s\n```",
+    table.insert(parts, string.format("**Selected Text** (lines %d-%d from `%s`):\n%s",
       context.selection.start_line,
       context.selection.end_line,
       context.selection.filePath,
-      context.selection.text))
+      utils.format_code_block(context.selection.text, utils.filetype_of(context.selection.filePath))))
   end
 
   -- Diagnostics
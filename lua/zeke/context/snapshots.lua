@@ -0,0 +1,178 @@
+-- Precomputed context snapshots
+--
+-- Git diffs/status and LSP document symbols are gathered in the background
+-- and cached, so building a request only reads the latest snapshot instead
+-- of blocking on git or the language server. Git snapshots are refreshed
+-- (debounced) after writes, focus changes and directory changes, and are
+-- never trusted past max_age_s; symbol snapshots are keyed by the buffer's
+-- changedtick. A missing or stale snapshot is computed synchronously, once.
+local M = {}
+
+local logger = require('zeke.logger')
+
+M.config = {
+  enabled = true,
+  debounce_ms = 500,
+  max_age_s = 60,
+}
+
+-- Background commands, run in the current working directory
+M.sources = {
+  git_diff = { 'git', 'diff' },
+  git_staged = { 'git', 'diff', '--cached' },
+  git_status = { 'git', 'status', '--short' },
+}
+
+-- [cwd][name] = { content, ok, dirty, updated_at }
+local git = {}
+-- [bufnr] = { tick, symbols }
+local symbols = {}
+local pending_timer = nil
+
+local function cwd_snapshots()
+  local cwd = vim.fn.getcwd()
+  git[cwd] = git[cwd] or {}
+  return git[cwd], cwd
+end
+
+---Refresh a git snapshot in the background
+---@param name string Key of M.sources
+function M.refresh(name)
+  local snaps, cwd = cwd_snapshots()
+  local snap = snaps[name] or {}
+  if snap.running then
+    snap.rerun = true
+    return
+  end
+  snap.running = true
+  snaps[name] = snap
+
+  local output = {}
+  local job = vim.fn.jobstart(M.sources[name], {
+    cwd = cwd,
+    stdout_buffered = true,
+    on_stdout = function(_, data)
+      output = data or {}
+    end,
+    on_exit = function(_, code)
+      snap.running = false
+      snap.ok = code == 0
+      snap.content = table.concat(output, '\n')
+      snap.dirty = false
+      snap.updated_at = os.time()
+      if snap.rerun then
+        snap.rerun = false
+        M.refresh(name)
+      end
+    end,
+  })
+  if job <= 0 then
+    snap.running = false
+    logger.debug('snapshots', 'Could not start ' .. name)
+  end
+end
+
+---Mark git snapshots stale and refresh them after debounce_ms
+function M.invalidate()
+  for _, snap in pairs(cwd_snapshots()) do
+    snap.dirty = true
+  end
+  if pending_timer then
+    pending_timer:stop()
+  else
+    pending_timer = vim.loop.new_timer()
+  end
+  pending_timer:start(M.config.debounce_ms, 0, vim.schedule_wrap(function()
+    for name in pairs(M.sources) do
+      M.refresh(name)
+    end
+  end))
+end
+
+---Latest output of a git source, computed now if there's no fresh snapshot
+---@param name string "git_diff" | "git_staged" | "git_status"
+---@return string content
+---@return boolean ok Command succeeded (false outside a git repository)
+function M.git(name)
+  local snaps = cwd_snapshots()
+  local snap = snaps[name]
+  local fresh = snap and snap.ok ~= nil and not snap.dirty
+    and os.time() - (snap.updated_at or 0) <= M.config.max_age_s
+  if M.config.enabled and fresh then
+    return snap.content, snap.ok
+  end
+
+  local content = vim.fn.system(M.sources[name])
+  local ok = vim.v.shell_error == 0
+  snaps[name] = { content = content, ok = ok, dirty = false, updated_at = os.time() }
+  return content, ok
+end
+
+local function request_symbols(bufnr)
+  local tick = vim.api.nvim_buf_get_changedtick(bufnr)
+  local params = { textDocument = vim.lsp.util.make_text_document_params(bufnr) }
+  vim.lsp.buf_request_all(bufnr, 'textDocument/documentSymbol', params, function(results)
+    for _, res in pairs(results or {}) do
+      if res.result then
+        symbols[bufnr] = { tick = tick, symbols = res.result }
+        return
+      end
+    end
+  end)
+end
+
+---Document symbols of a buffer from the snapshot (nil when stale)
+---@param bufnr number
+---@return table|nil
+function M.symbols(bufnr)
+  local snap = symbols[bufnr]
+  if M.config.enabled and snap and snap.tick == vim.api.nvim_buf_get_changedtick(bufnr) then
+    return snap.symbols
+  end
+  return nil
+end
+
+---Store symbols computed by a caller
+---@param bufnr number
+---@param result table
+function M.store_symbols(bufnr, result)
+  symbols[bufnr] = { tick = vim.api.nvim_buf_get_changedtick(bufnr), symbols = result }
+end
+
+function M.setup(opts)
+  M.config = vim.tbl_extend('force', M.config, opts or {})
+
+  local group = vim.api.nvim_create_augroup('ZekeContextSnapshots', { clear = true })
+  if not M.config.enabled then
+    return
+  end
+
+  vim.api.nvim_create_autocmd({ 'BufWritePost', 'FocusGained', 'DirChanged', 'ShellCmdPost', 'TermLeave' }, {
+    group = group,
+    callback = function()
+      M.invalidate()
+    end,
+  })
+
+  vim.api.nvim_create_autocmd({ 'LspAttach', 'BufWritePost', 'InsertLeave' }, {
+    group = group,
+    callback = function(args)
+      local clients = vim.lsp.get_clients and vim.lsp.get_clients({ bufnr = args.buf })
+        or vim.lsp.get_active_clients({ bufnr = args.buf })
+      if #clients > 0 then
+        request_symbols(args.buf)
+      end
+    end,
+  })
+
+  vim.api.nvim_create_autocmd('BufWipeout', {
+    group = group,
+    callback = function(args)
+      symbols[args.buf] = nil
+    end,
+  })
+
+  M.invalidate()
+end
+
+return M
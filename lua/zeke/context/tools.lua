@@ -4,13 +4,14 @@
 local M = {}
 
 local logger = require('zeke.logger')
+local privacy = require('zeke.privacy')
 
 -- Get current file info
 function M.get_current_file()
   local buf = vim.api.nvim_get_current_buf()
   local path = vim.api.nvim_buf_get_name(buf)
 
-  if not path or path == "" then
+  if not path or path == "" or not privacy.check_path(path) then
     return nil
   end
 
@@ -86,7 +87,7 @@ function M.get_open_editors()
   for _, buf in ipairs(bufs) do
     if vim.api.nvim_buf_is_loaded(buf) then
       local name = vim.api.nvim_buf_get_name(buf)
-      if name ~= "" and vim.bo[buf].buftype == "" then
+      if name ~= "" and vim.bo[buf].buftype == "" and not privacy.is_sensitive(name) then
         table.insert(editors, {
           path = name,
           language = vim.bo[buf].filetype,
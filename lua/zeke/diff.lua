@@ -3,6 +3,8 @@
 local M = {}
 
 local logger = require('zeke.logger')
+local audit = require('zeke.audit')
+local privacy = require('zeke.privacy')
 
 -- Configuration
 M.config = {
@@ -263,11 +265,24 @@ function M.accept_diff(diff_id)
     return false
   end
 
+  local permitted, refused = privacy.check_action('file_write')
+  if not permitted then
+    vim.notify(refused, vim.log.levels.WARN)
+    return false
+  end
+
   -- Copy modified content to original file
   local modified_content = vim.api.nvim_buf_get_lines(diff_state.modified_buf, 0, -1, false)
 
   -- Write to original file
+  local previous = audit.read_file(diff_state.original_file)
   vim.fn.writefile(modified_content, diff_state.original_file)
+  audit.record('file_write', {
+    source = 'diff',
+    path = diff_state.original_file,
+    before = previous,
+    after = modified_content,
+  })
 
   diff_state.accepted = true
 
@@ -513,6 +528,7 @@ function M.accept_hunk(diff_id)
   )
 
   -- Apply to target buffer
+  local replaced = vim.api.nvim_buf_get_lines(target_buf, hunk.start_line - 1, hunk.end_line, false)
   vim.api.nvim_buf_set_lines(
     target_buf,
     hunk.start_line - 1,
@@ -520,6 +536,15 @@ function M.accept_hunk(diff_id)
     false,
     hunk_lines
   )
+  if target_buf == diff_state.original_buf then
+    audit.record('buffer_edit', {
+      source = 'diff_hunk',
+      path = diff_state.original_file,
+      lines = { hunk.start_line, hunk.end_line },
+      before = replaced,
+      after = hunk_lines,
+    })
+  end
 
   logger.info("diff", string.format(
     "Accepted hunk: lines %d-%d (%s)",
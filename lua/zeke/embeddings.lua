@@ -0,0 +1,319 @@
+--[[
+  Embeddings
+
+  Turns text into vectors for semantic search, RAG indexes and other
+  plugins. Requests go straight to the embedding backend's HTTP API (the
+  zeke CLI has no embeddings command), in batches of batch_size with up to
+  concurrency batches in flight:
+
+    require('zeke').embed({ "fn parse(input: &str)", "struct Token" }, function(vectors, err)
+      ...
+    end, { input_type = "document" })
+
+  Built-in backends: openai, ollama, cohere and voyage. API keys come from
+  providers.<name>.api_key or the backend's environment variable, base URLs
+  from providers.<name>.base_url. More backends can be added with
+  M.register_backend(). Privacy mode only allows local backends.
+
+  With response_cache enabled, vectors are cached per text (operation
+  "embeddings"), so only texts that changed since the last run are sent;
+  opts.cache = false skips the cache.
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local config = require('zeke.config')
+local privacy = require('zeke.privacy')
+local secrets = require('zeke.secrets')
+local cache = require('zeke.cache')
+
+M.config = {
+  provider = 'ollama',
+  model = nil,        -- Default: the backend's model
+  batch_size = 64,
+  concurrency = 2,
+  timeout_s = 60,
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_extend('force', M.config, opts or {})
+end
+
+local function data_vectors(response)
+  local data = vim.deepcopy(response.data or {})
+  table.sort(data, function(a, b) return (a.index or 0) < (b.index or 0) end)
+  return vim.tbl_map(function(item) return item.embedding end, data)
+end
+
+-- name -> { model, url, key_env, request(model, texts, opts) -> path, body, parse(response) -> vectors }
+-- opts.input_type is "document" or "query" for backends that embed them differently
+M.backends = {
+  openai = {
+    model = 'text-embedding-3-small',
+    url = 'https://api.openai.com/v1',
+    key_env = 'OPENAI_API_KEY',
+    request = function(model, texts)
+      return '/embeddings', { model = model, input = texts }
+    end,
+    parse = data_vectors,
+  },
+  ollama = {
+    model = 'nomic-embed-text',
+    url = function() return config.get_ollama_host() end,
+    request = function(model, texts)
+      return '/api/embed', { model = model, input = texts }
+    end,
+    parse = function(response) return response.embeddings end,
+  },
+  cohere = {
+    model = 'embed-english-v3.0',
+    url = 'https://api.cohere.com/v2',
+    key_env = 'COHERE_API_KEY',
+    request = function(model, texts, opts)
+      return '/embed', {
+        model = model,
+        texts = texts,
+        input_type = opts.input_type == 'query' and 'search_query' or 'search_document',
+        embedding_types = { 'float' },
+      }
+    end,
+    parse = function(response) return response.embeddings and response.embeddings.float end,
+  },
+  voyage = {
+    model = 'voyage-code-3',
+    url = 'https://api.voyageai.com/v1',
+    key_env = 'VOYAGE_API_KEY',
+    request = function(model, texts, opts)
+      return '/embeddings', { model = model, input = texts, input_type = opts.input_type }
+    end,
+    parse = data_vectors,
+  },
+}
+
+---Add (or replace) an embedding backend
+---@param name string
+---@param spec table {model, url (string or function), key_env, request(model, texts, opts), parse(response)}
+function M.register_backend(name, spec)
+  M.backends[name] = spec
+end
+
+local function provider_settings(name)
+  local providers = (config.options and config.options.providers) or {}
+  return providers[name] or {}
+end
+
+---POST a JSON body (or multipart form fields) and decode the JSON response
+---@param url string
+---@param body table JSON body, or {form = {name = value, ...}} for multipart ({path = ...} values upload files)
+---@param api_key string|nil
+---@param callback function Called with (response|nil, error)
+---@param timeout_s number|nil Default: embeddings.timeout_s
+function M.post_json(url, body, api_key, callback, timeout_s)
+  local cmd = { 'curl', '-sS', '--max-time', tostring(timeout_s or M.config.timeout_s), '-w', '\n%{http_code}',
+    '-X', 'POST' }
+  if body.form then
+    for name, value in pairs(body.form) do
+      if type(value) == 'table' then
+        vim.list_extend(cmd, { '-F', string.format('%s=@"%s"', name, (value.path:gsub('"', '\\"'))) })
+      else
+        vim.list_extend(cmd, { '--form-string', name .. '=' .. tostring(value) })
+      end
+    end
+  else
+    vim.list_extend(cmd, { '-H', 'Content-Type: application/json', '--data-binary', '@-' })
+  end
+
+  -- The key goes through a header file (created 0600) so it doesn't show up
+  -- in the process list
+  local header_file
+  if api_key then
+    header_file = vim.fn.tempname()
+    local fd = vim.loop.fs_open(header_file, 'w', 384)
+    if not fd then
+      callback(nil, 'Could not write the API key header file')
+      return
+    end
+    vim.loop.fs_write(fd, 'Authorization: Bearer ' .. api_key .. '\n')
+    vim.loop.fs_close(fd)
+    vim.list_extend(cmd, { '-H', '@' .. header_file })
+  end
+  table.insert(cmd, url)
+
+  local output = {}
+  local job = vim.fn.jobstart(cmd, {
+    stdout_buffered = true,
+    on_stdout = function(_, data)
+      output = data or {}
+    end,
+    on_exit = function(_, code)
+      if header_file then
+        vim.fn.delete(header_file)
+      end
+      if code ~= 0 then
+        callback(nil, string.format('Request to %s failed (curl exit %d)', url, code))
+        return
+      end
+
+      while #output > 0 and output[#output] == '' do
+        table.remove(output)
+      end
+      local status = tonumber(table.remove(output) or '')
+      local ok, response = pcall(vim.json.decode, table.concat(output, '\n'))
+      if not status or status >= 300 then
+        local message = ok and type(response) == 'table'
+          and (type(response.error) == 'table' and response.error.message or response.error or response.message)
+        callback(nil, string.format('HTTP %s from %s: %s', tostring(status), url,
+          tostring(message or table.concat(output, '\n'):sub(1, 200))))
+      elseif not ok or type(response) ~= 'table' then
+        callback(nil, 'Invalid JSON response from ' .. url)
+      else
+        callback(response, nil)
+      end
+    end,
+  })
+  if job <= 0 then
+    if header_file then
+      vim.fn.delete(header_file)
+    end
+    callback(nil, 'Could not start curl')
+    return
+  end
+  if not body.form then
+    vim.fn.chansend(job, vim.json.encode(body))
+  end
+  vim.fn.chanclose(job, 'stdin')
+end
+
+---Embed one text or a list of texts
+---@param texts string|string[]
+---@param callback function Called with (vector or list of vectors | nil, error)
+---@param opts table|nil {provider, model, input_type = "document" | "query", secret_scan, cache}
+function M.embed(texts, callback, opts)
+  opts = opts or {}
+  local single = type(texts) == 'string'
+  texts = single and { texts } or vim.deepcopy(texts)
+
+  local provider = opts.provider or M.config.provider
+  local backend = M.backends[provider]
+  if not backend then
+    callback(nil, 'Unknown embedding provider: ' .. tostring(provider))
+    return
+  end
+  local allowed, refused = privacy.check_provider(provider)
+  if not allowed then
+    callback(nil, refused)
+    return
+  end
+  for i, text in ipairs(texts) do
+    local checked, blocked = secrets.check(text, opts.secret_scan)
+    if not checked then
+      callback(nil, blocked)
+      return
+    end
+    texts[i] = checked
+  end
+
+  local settings = provider_settings(provider)
+  local base_url = settings.base_url or backend.url
+  if type(base_url) == 'function' then
+    base_url = base_url()
+  end
+  local api_key = settings.api_key or (backend.key_env and vim.env[backend.key_env])
+  if backend.key_env and not api_key then
+    callback(nil, string.format('No API key for %s (set %s or providers.%s.api_key)', provider, backend.key_env, provider))
+    return
+  end
+  local model = opts.model or M.config.model or backend.model
+
+  -- Cached vectors by position; only the rest are requested
+  local use_cache = opts.cache ~= false and cache.enabled_for('embeddings')
+  local keys, vectors, missing = {}, {}, {}
+  for i, text in ipairs(texts) do
+    local cached
+    if use_cache then
+      keys[i] = cache.key('embeddings', { provider = provider, model = model, input_type = opts.input_type, text = text })
+      cached = cache.get(keys[i])
+    end
+    local ok, vector = pcall(vim.json.decode, cached or '')
+    if ok and type(vector) == 'table' then
+      vectors[i] = vector
+    else
+      table.insert(missing, i)
+    end
+  end
+
+  local batches = {}
+  for first = 1, #missing, M.config.batch_size do
+    table.insert(batches, vim.list_slice(missing, first, first + M.config.batch_size - 1))
+  end
+  local function finished()
+    logger.debug('embeddings', string.format('Embedded %d texts with %s/%s (%d cached)', #texts, provider, model,
+      #texts - #missing))
+    callback(single and vectors[1] or vectors, nil)
+  end
+  if #batches == 0 then
+    finished()
+    return
+  end
+
+  -- Keep up to `concurrency` batches in flight; the first failure ends the call
+  local launched, pending, failed = 0, 0, false
+  local function launch()
+    while not failed and launched < #batches and pending < math.max(1, M.config.concurrency) do
+      launched = launched + 1
+      pending = pending + 1
+      local indices = batches[launched]
+      local batch = vim.tbl_map(function(i) return texts[i] end, indices)
+      local path, body = backend.request(model, batch, opts)
+      M.post_json(base_url:gsub('/+$', '') .. path, body, api_key, vim.schedule_wrap(function(response, err)
+        pending = pending - 1
+        if failed then
+          return
+        end
+        local batch_vectors = response and backend.parse(response)
+        if not batch_vectors or #batch_vectors ~= #batch then
+          failed = true
+          err = err or string.format('Expected %d embeddings from %s, got %d', #batch, provider, #(batch_vectors or {}))
+          logger.warn('embeddings', err)
+          callback(nil, err)
+          return
+        end
+        local fresh = {}
+        for n, i in ipairs(indices) do
+          vectors[i] = batch_vectors[n]
+          if use_cache then
+            fresh[keys[i]] = vim.json.encode(batch_vectors[n])
+          end
+        end
+        cache.put_many(fresh, { operation = 'embeddings', model = model, provider = provider })
+        if launched == #batches and pending == 0 then
+          finished()
+        else
+          launch()
+        end
+      end))
+    end
+  end
+  launch()
+end
+
+---Embed and wait for the result
+---@param texts string|string[]
+---@param opts table|nil Same as M.embed
+---@return table|nil vectors
+---@return string|nil error
+function M.embed_sync(texts, opts)
+  local result, err, finished = nil, nil, false
+  M.embed(texts, function(vectors, embed_err)
+    result, err, finished = vectors, embed_err, true
+  end, opts)
+  local batches = type(texts) == 'string' and 1
+    or math.max(1, math.ceil(#texts / M.config.batch_size / math.max(1, M.config.concurrency)))
+  if not vim.wait(M.config.timeout_s * 1000 * batches, function() return finished end, 20) then
+    return nil, 'Timed out waiting for embeddings'
+  end
+  return result, err
+end
+
+return M
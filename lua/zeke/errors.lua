@@ -198,6 +198,33 @@ function M.parse_error(error_msg)
     "https://github.com/ghostkellz/zeke.nvim/issues"
 end
 
+-- Categories for metrics, checked in order; first match wins
+M.categories = {
+  { name = "rate_limit", patterns = { "rate.?limit", "429", "too many requests" } },
+  { name = "auth", patterns = { "unauthorized", "401", "invalid.*api.*key", "authentication" } },
+  { name = "permission", patterns = { "forbidden", "403" } },
+  { name = "timeout", patterns = { "timeout", "timed out", "504" } },
+  { name = "not_found", patterns = { "not found", "404" } },
+  { name = "context_length", patterns = { "context.*length", "maximum.*token" } },
+  { name = "server", patterns = { "500", "502", "503", "overloaded", "service unavailable" } },
+  { name = "network", patterns = { "connection", "network", "unreachable", "refused" } },
+}
+
+---Classify an error message for metrics
+---@param error_msg string|nil
+---@return string category (rate_limit, auth, permission, timeout, not_found, context_length, server, network, other)
+function M.category(error_msg)
+  local error_lower = (error_msg or ""):lower()
+  for _, category in ipairs(M.categories) do
+    for _, pattern in ipairs(category.patterns) do
+      if error_lower:match(pattern) then
+        return category.name
+      end
+    end
+  end
+  return "other"
+end
+
 ---Show helpful error notification
 ---@param error_msg string Raw error message
 ---@param level number|nil Vim log level (default: ERROR)
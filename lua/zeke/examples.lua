@@ -0,0 +1,138 @@
+--[[
+  Few-Shot Examples
+
+  Curated input/output pairs appended to an operation's prompt to steer
+  style ("here's how we write tests in this repo"). Each example set is a
+  Markdown file, <name>.md, in the project's .zeke/examples/ or in the
+  prompt library's examples directory:
+
+    ## Input
+    Write a test for parse_config
+    ## Output
+    ```rust
+    #[test]
+    fn parse_config_reads_defaults() { ... }
+    ```
+
+  Sets are attached to operations in config (usually the project's
+  .zeke/config.json):
+
+    few_shot = { operations = { generate = { "tests" }, edit = { "style" } } }
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+
+M.config = {
+  dir = '~/.config/zeke/prompts/examples',
+  operations = {},
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+---Path of an example set (project first, then the prompt library)
+---@param name string
+---@return string|nil
+function M.path(name)
+  local candidates = {
+    vim.fn.getcwd() .. '/.zeke/examples/' .. name .. '.md',
+    vim.fn.expand(M.config.dir) .. '/' .. name .. '.md',
+  }
+  for _, path in ipairs(candidates) do
+    if vim.fn.filereadable(path) == 1 then
+      return path
+    end
+  end
+  return nil
+end
+
+---Parse "## Input" / "## Output" sections into pairs
+---@param text string
+---@return table[] pairs {input, output}
+function M.parse(text)
+  local pairs_list = {}
+  local current, section, lines = nil, nil, {}
+
+  local function flush()
+    if current and section then
+      current[section] = vim.trim(table.concat(lines, '\n'))
+    end
+    lines = {}
+  end
+
+  for _, line in ipairs(vim.split(text, '\n', { plain = true })) do
+    local heading = line:match('^##%s+(%a+)%s*$')
+    heading = heading and heading:lower()
+    if heading == 'input' then
+      flush()
+      current = { input = '', output = '' }
+      table.insert(pairs_list, current)
+      section = 'input'
+    elseif heading == 'output' and current then
+      flush()
+      section = 'output'
+    elseif section then
+      table.insert(lines, line)
+    end
+  end
+  flush()
+
+  return vim.tbl_filter(function(pair)
+    return pair.input ~= '' and pair.output ~= ''
+  end, pairs_list)
+end
+
+---Load an example set
+---@param name string
+---@return table[] pairs (empty when the set doesn't exist)
+function M.load(name)
+  local path = M.path(name)
+  if not path then
+    logger.warn('examples', 'Example set not found: ' .. name)
+    return {}
+  end
+  return M.parse(table.concat(vim.fn.readfile(path), '\n'))
+end
+
+---Format pairs as a prompt section
+---@param pairs_list table[]
+---@return string
+function M.format(pairs_list)
+  local parts = { 'Follow the style of these examples:' }
+  for i, pair in ipairs(pairs_list) do
+    table.insert(parts, string.format('Example %d input:\n%s\n\nExample %d output:\n%s', i, pair.input, i, pair.output))
+  end
+  return table.concat(parts, '\n\n')
+end
+
+---Example sets for an operation
+---@param operation string
+---@param sets string[]|false|nil Override: list of set names, false for none
+---@return string[]
+function M.sets_for(operation, sets)
+  if sets == false then
+    return {}
+  end
+  return sets or M.config.operations[operation] or {}
+end
+
+---Append an operation's examples to a prompt
+---@param message string
+---@param operation string Operation name (chat, edit, generate, debug, or a prompt template name)
+---@param sets string[]|false|nil Override the configured sets
+---@return string
+function M.apply(message, operation, sets)
+  local examples = {}
+  for _, name in ipairs(M.sets_for(operation, sets)) do
+    vim.list_extend(examples, M.load(name))
+  end
+  if #examples == 0 then
+    return message
+  end
+  return message .. '\n\n' .. M.format(examples)
+end
+
+return M
@@ -30,6 +30,57 @@ local tokens = require('zeke.tokens')
 local backup = require('zeke.backup')
 local safety = require('zeke.safety')
 local statusline = require('zeke.statusline')
+local mcp = require('zeke.mcp')
+local approval = require('zeke.approval')
+local terminal = require('zeke.terminal')
+local testfix = require('zeke.testfix')
+local transcript = require('zeke.transcript')
+local postprocess = require('zeke.postprocess')
+local examples = require('zeke.examples')
+local prompt_library = require('zeke.prompt_library')
+local wire_log = require('zeke.wire_log')
+local otlp = require('zeke.otlp')
+local audit = require('zeke.audit')
+local privacy = require('zeke.privacy')
+local cache = require('zeke.cache')
+local warmup = require('zeke.warmup')
+local snapshots = require('zeke.context.snapshots')
+local secrets = require('zeke.secrets')
+local agent_tools = require('zeke.agent_tools')
+local agent_loop = require('zeke.agent_loop')
+local agent_checkpoints = require('zeke.agent_checkpoints')
+local embeddings = require('zeke.embeddings')
+local rerank = require('zeke.rerank')
+local transcription = require('zeke.transcription')
+local openai_server = require('zeke.openai_server')
+
+-- Start an agent run from a command, reporting progress with notifications
+local function start_agent(goal, plan_first)
+  local opts = {
+    on_step = function(run, step)
+      vim.notify(string.format('Agent #%d step %d: %s %s', run.id, #run.steps, step.tool, step.ok and '✓' or '✗'),
+        vim.log.levels.INFO)
+    end,
+    on_done = function(run)
+      vim.notify(string.format('Agent #%d %s: %s', run.id, run.status, run.answer or ''),
+        run.status == 'done' and vim.log.levels.INFO or vim.log.levels.WARN)
+    end,
+  }
+
+  if plan_first then
+    vim.notify('Agent: writing a plan...', vim.log.levels.INFO)
+    agent_loop.plan(goal, opts, function(ok, err)
+      if not ok then
+        vim.notify('Agent planning failed: ' .. tostring(err), vim.log.levels.ERROR)
+      end
+    end)
+    return
+  end
+
+  local run = agent_loop.run(goal, opts)
+  vim.notify(string.format('Agent #%d started (:ZekeAgent to show steps, :ZekeAgent stop to stop)', run.id),
+    vim.log.levels.INFO)
+end
 
 function M.setup(opts)
   opts = opts or {}
@@ -44,6 +95,79 @@ function M.setup(opts)
   -- Setup diff module
   diff.setup(cfg.diff or {})
 
+  -- Concurrency limits for async requests
+  requests.configure_limits(cfg.concurrency)
+
+  -- Approval prompts for AI-proposed file changes
+  approval.setup(cfg.approval or {})
+
+  -- MCP tools exposed to external agents
+  mcp.setup(cfg.mcp or {})
+
+  -- Run-tests-and-fix loop
+  testfix.setup(cfg.test_fix or {})
+
+  -- Stream transcripts
+  transcript.setup(cfg.transcripts or {})
+
+  -- Request metadata log
+  wire_log.setup(cfg.wire_log or {})
+
+  -- OpenTelemetry span export
+  otlp.setup(cfg.otlp or {})
+
+  -- Audit log of AI-driven changes
+  audit.setup(cfg.audit or {})
+
+  -- Privacy / offline mode
+  privacy.setup(cfg.privacy or {})
+
+  -- Response cache for deterministic operations
+  cache.setup(cfg.response_cache or {})
+
+  -- Local model warm-up and keep-alive
+  warmup.setup(cfg.warmup or {})
+
+  -- Background git/LSP context snapshots
+  snapshots.setup(cfg.context_snapshots or {})
+
+  -- Embedding backends
+  embeddings.setup(cfg.embeddings or {})
+
+  -- Reranking backends
+  rerank.setup(cfg.rerank or {})
+
+  -- Speech to text
+  transcription.setup(cfg.transcription or {})
+
+  -- OpenAI-compatible local server
+  openai_server.setup(cfg.server or {})
+
+  -- Agent loop and its tools
+  agent_tools.setup((cfg.agent or {}).tools or {})
+  agent_loop.setup(cfg.agent or {})
+
+  -- Pricing overrides for cost estimates
+  tokens.setup({ pricing = cfg.pricing })
+
+  -- Outgoing prompt secret scanning
+  secrets.setup(cfg.secret_scan or {})
+
+  -- Response transform stages per task
+  postprocess.setup(cfg.postprocess or {})
+
+  -- Few-shot example sets per operation
+  examples.setup(cfg.few_shot or {})
+
+  -- Saved reusable prompts
+  prompt_library.setup(cfg.prompt_library or {})
+
+  -- Start on the configured model (sent with each request, no CLI round-trip)
+  models.apply_aliases(cfg.aliases)
+  if cfg.default_model then
+    models.set_model(cfg.default_model, { persist = false })
+  end
+
   -- Setup selection tracking if enabled
   if cfg.track_selection ~= false then
     selection.enable(cfg.selection or {})
@@ -67,6 +191,47 @@ function M.setup(opts)
     statusline.setup_autocommands()
   end
 
+  -- Reapply module settings when config files change
+  vim.api.nvim_create_autocmd('User', {
+    group = vim.api.nvim_create_augroup('ZekeConfigReload', { clear = true }),
+    pattern = 'ZekeConfigReloaded',
+    callback = function(ev)
+      local new_cfg = ev.data.options
+      logger.setup(new_cfg.logger or {})
+      diff.setup(new_cfg.diff or {})
+      requests.configure_limits(new_cfg.concurrency)
+      approval.setup(new_cfg.approval or {})
+      mcp.setup(new_cfg.mcp or {})
+      testfix.setup(new_cfg.test_fix or {})
+      transcript.setup(new_cfg.transcripts or {})
+      wire_log.setup(new_cfg.wire_log or {})
+      otlp.setup(new_cfg.otlp or {})
+      audit.setup(new_cfg.audit or {})
+      privacy.setup(new_cfg.privacy or {})
+      cache.setup(new_cfg.response_cache or {})
+      warmup.setup(new_cfg.warmup or {})
+      snapshots.setup(new_cfg.context_snapshots or {})
+      embeddings.setup(new_cfg.embeddings or {})
+      rerank.setup(new_cfg.rerank or {})
+      transcription.setup(new_cfg.transcription or {})
+      openai_server.setup(new_cfg.server or {})
+      agent_tools.setup((new_cfg.agent or {}).tools or {})
+      agent_loop.setup(new_cfg.agent or {})
+      tokens.setup({ pricing = new_cfg.pricing })
+      secrets.setup(new_cfg.secret_scan or {})
+      postprocess.setup(new_cfg.postprocess or {})
+      examples.setup(new_cfg.few_shot or {})
+      prompt_library.setup(new_cfg.prompt_library or {})
+      models.apply_aliases(new_cfg.aliases)
+
+      if new_cfg.default_model ~= ev.data.previous.default_model then
+        models.set_model(new_cfg.default_model, { persist = false })
+      end
+
+      logger.info('init', 'Configuration reloaded')
+    end,
+  })
+
   -- Health check
   local health = cli.health_check()
   if not health.installed then
@@ -79,6 +244,8 @@ function M.setup(opts)
       "Zeke CLI found but not working: " .. (health.error or "unknown error"),
       vim.log.levels.WARN
     )
+  elseif not health.compatible then
+    vim.notify(health.version_message, vim.log.levels.WARN)
   else
     logger.info("init", "Zeke CLI ready: " .. (health.version or "unknown"))
   end
@@ -86,119 +253,151 @@ function M.setup(opts)
   -- Create lock file for Zeke CLI discovery (if enabled)
   if opts.create_lockfile ~= false then
     local lockfile = require('zeke.lockfile')
-    lockfile.create(0)  -- No port needed for CLI mode
+    local port = lockfile.find_free_port(cfg.lockfile_ports)
+    if port then
+      lockfile.create(port)
+      lockfile.schedule_cleanup(cfg.lockfile_cleanup_interval)
+    else
+      logger.warn("init", "No free port for the lock file in the configured range")
+    end
   end
 
   -- =============================================================================
   -- User Commands
   -- =============================================================================
 
+  -- Register a user command under its configured name, unless disabled
+  local function command(name, fn, cmd_opts)
+    local resolved = M.command_name(name)
+    if resolved then
+      vim.api.nvim_create_user_command(resolved, fn, cmd_opts)
+    end
+  end
+
   -- Main agent interface
-  vim.api.nvim_create_user_command('ZekeCode', function()
+  command('ZekeCode', function()
     agent.toggle()
   end, { desc = 'Open ZekeCode AI Agent Interface' })
 
-  vim.api.nvim_create_user_command('ZekeCodeClose', function()
+  command('ZekeCodeClose', function()
     agent.close()
   end, { desc = 'Close ZekeCode interface' })
 
-  vim.api.nvim_create_user_command('ZekeCodeClear', function()
+  command('ZekeCodeClear', function()
     agent.clear_chat()
   end, { desc = 'Clear ZekeCode chat history' })
 
-  vim.api.nvim_create_user_command('ZekeCodeSave', function(args)
+  command('ZekeCodeSave', function(args)
     agent.save_conversation(args.args ~= '' and args.args or nil)
   end, { nargs = '?', desc = 'Save ZekeCode conversation' })
 
   -- Chat panel
-  vim.api.nvim_create_user_command('ZekeChatPanel', function()
+  command('ZekeChatPanel', function()
     chat_panel.toggle()
   end, { desc = 'Toggle Zeke chat panel' })
 
-  vim.api.nvim_create_user_command('ZekeChatOpen', function()
+  command('ZekeChatOpen', function()
     chat_panel.open()
   end, { desc = 'Open Zeke chat panel' })
 
-  vim.api.nvim_create_user_command('ZekeChatClose', function()
+  command('ZekeChatClose', function()
     chat_panel.close()
   end, { desc = 'Close Zeke chat panel' })
 
-  vim.api.nvim_create_user_command('ZekeChatClear', function()
+  command('ZekeChatClear', function()
     chat_panel.clear()
   end, { desc = 'Clear chat history' })
 
   -- Original commands (still available)
-  vim.api.nvim_create_user_command('ZekeChat', function(args)
+  command('ZekeChat', function(args)
     commands.chat(args.args)
   end, { nargs = '?', desc = 'Chat with Zeke AI' })
 
-  vim.api.nvim_create_user_command('ZekeEdit', function(args)
+  command('ZekeTranscribe', function(args)
+    local send = args.bang
+    vim.notify('Transcribing ' .. vim.fn.fnamemodify(args.args, ':t') .. '...', vim.log.levels.INFO)
+    transcription.transcribe(args.args, function(text, err)
+      if not text then
+        vim.notify('Transcription failed: ' .. err, vim.log.levels.ERROR)
+      elseif send then
+        commands.chat(text)
+      else
+        vim.api.nvim_put(vim.split(text, '\n', { plain = true }), 'c', true, true)
+      end
+    end)
+  end, { nargs = 1, bang = true, complete = 'file', desc = 'Transcribe an audio file at the cursor (! sends it to chat)' })
+
+  command('ZekeRace', function(args)
+    commands.race(args.args)
+  end, { nargs = '?', desc = 'Send a prompt to every race target and keep the first answer' })
+
+  command('ZekeEdit', function(args)
     commands.edit_buffer(args.args)
   end, { nargs = '?', desc = 'Edit current buffer with Zeke' })
 
-  vim.api.nvim_create_user_command('ZekeExplain', function()
+  command('ZekeExplain', function()
     commands.explain()
   end, { desc = 'Explain current buffer with Zeke' })
 
-  vim.api.nvim_create_user_command('ZekeCreate', function(args)
+  command('ZekeCreate', function(args)
     commands.create_file(args.args)
   end, { nargs = '?', desc = 'Create file with Zeke' })
 
-  vim.api.nvim_create_user_command('ZekeAnalyze', function(args)
+  command('ZekeAnalyze', function(args)
     local analysis_type = args.args or 'quality'
     commands.analyze(analysis_type)
   end, { nargs = '?', desc = 'Analyze code with Zeke' })
 
   -- Code Actions Menu
-  vim.api.nvim_create_user_command('ZekeActions', function()
+  command('ZekeActions', function()
     actions.show_picker()
   end, { desc = 'Show context-aware code actions menu' })
 
-  vim.api.nvim_create_user_command('ZekeExplainCode', function()
+  command('ZekeExplainCode', function()
     local ctx = actions.gather_context()
     local action = vim.tbl_filter(function(a) return a.id == "explain" end, actions.actions)[1]
     actions.execute_action(action, ctx)
   end, { desc = 'Explain code (smart context)' })
 
-  vim.api.nvim_create_user_command('ZekeFixCode', function()
+  command('ZekeFixCode', function()
     local ctx = actions.gather_context()
     local action = vim.tbl_filter(function(a) return a.id == "fix" end, actions.actions)[1]
     actions.execute_action(action, ctx)
   end, { desc = 'Fix code issues (smart context)' })
 
-  vim.api.nvim_create_user_command('ZekeRefactorCode', function()
+  command('ZekeRefactorCode', function()
     local ctx = actions.gather_context()
     local action = vim.tbl_filter(function(a) return a.id == "refactor" end, actions.actions)[1]
     actions.execute_action(action, ctx)
   end, { desc = 'Refactor code (smart context)' })
 
-  vim.api.nvim_create_user_command('ZekeGenerateTests', function()
+  command('ZekeGenerateTests', function()
     local ctx = actions.gather_context()
     local action = vim.tbl_filter(function(a) return a.id == "test" end, actions.actions)[1]
     actions.execute_action(action, ctx)
   end, { desc = 'Generate tests (smart context)' })
 
   -- LSP integration commands
-  vim.api.nvim_create_user_command('ZekeFix', function()
+  command('ZekeFix', function()
     lsp.context.fix_diagnostic_at_cursor()
   end, { desc = 'Fix diagnostic at cursor with AI' })
 
-  vim.api.nvim_create_user_command('ZekeExplainDiagnostic', function()
+  command('ZekeExplainDiagnostic', function()
     lsp.context.explain_diagnostic_at_cursor()
   end, { desc = 'Explain diagnostic at cursor' })
 
   -- Model management
-  vim.api.nvim_create_user_command('ZekeModels', function()
+  command('ZekeModels', function()
     models.show_picker()
   end, { desc = 'Show model picker' })
 
-  vim.api.nvim_create_user_command('ZekeModelInfo', function()
+  command('ZekeModelInfo', function()
     local current = models.get_current()
     local info = models.model_info(current)
     vim.notify(info, vim.log.levels.INFO)
   end, { desc = 'Show current model info' })
 
-  vim.api.nvim_create_user_command('ZekeModelSet', function(args)
+  command('ZekeModelSet', function(args)
     if args.args == '' then
       models.show_picker()
     else
@@ -206,31 +405,31 @@ function M.setup(opts)
     end
   end, { nargs = '?', desc = 'Set AI model' })
 
-  vim.api.nvim_create_user_command('ZekeModelNext', function()
+  command('ZekeModelNext', function()
     local model = models.cycle_next()
     vim.notify(string.format("%s %s", model.icon, model.name), vim.log.levels.INFO)
   end, { desc = 'Cycle to next model' })
 
-  vim.api.nvim_create_user_command('ZekeModelPrev', function()
+  command('ZekeModelPrev', function()
     local model = models.cycle_prev()
     vim.notify(string.format("%s %s", model.icon, model.name), vim.log.levels.INFO)
   end, { desc = 'Cycle to previous model' })
 
   -- Provider management
-  vim.api.nvim_create_user_command('ZekeProviders', function()
+  command('ZekeProviders', function()
     commands.list_providers()
   end, { desc = 'List available providers' })
 
-  vim.api.nvim_create_user_command('ZekeProviderSet', function(args)
+  command('ZekeProviderSet', function(args)
     commands.set_provider(args.args)
   end, { nargs = '?', desc = 'Set AI provider' })
 
-  vim.api.nvim_create_user_command('ZekeProviderStatus', function()
+  command('ZekeProviderStatus', function()
     commands.provider_status()
   end, { desc = 'Show provider status' })
 
   -- Ollama host management
-  vim.api.nvim_create_user_command('ZekeOllamaHosts', function()
+  command('ZekeOllamaHosts', function()
     local hosts = config.list_ollama_hosts()
     local lines = { "Configured Ollama Hosts:", "" }
     for _, host in ipairs(hosts) do
@@ -240,7 +439,7 @@ function M.setup(opts)
   end, { desc = 'List Ollama hosts' })
 
   -- LiteLLM host management
-  vim.api.nvim_create_user_command('ZekeLiteLLMHosts', function()
+  command('ZekeLiteLLMHosts', function()
     local hosts = config.list_litellm_hosts()
     local lines = { "Configured LiteLLM Hosts:", "" }
     for _, host in ipairs(hosts) do
@@ -250,7 +449,7 @@ function M.setup(opts)
   end, { desc = 'List LiteLLM hosts' })
 
   -- Health check
-  vim.api.nvim_create_user_command('ZekeHealth', function()
+  command('ZekeHealth', function()
     local health = cli.health_check()
     local status_lines = {
       "Zeke CLI Health Check",
@@ -264,6 +463,23 @@ function M.setup(opts)
       if health.version then
         table.insert(status_lines, "Version: " .. health.version)
       end
+      if health.version_message then
+        table.insert(status_lines, "Compatibility: " .. (health.compatible and "? " or "✗ ") .. health.version_message)
+      elseif health.working then
+        table.insert(status_lines, "Compatibility: ✓")
+      end
+    end
+
+    local lock_stats = require('zeke.lockfile').get_stats()
+    if lock_stats.cleanup_runs > 0 then
+      table.insert(status_lines, string.format("Stale lock files removed: %d (%d sweeps)",
+        lock_stats.stale_removed, lock_stats.cleanup_runs))
+    end
+
+    local problems = config.validate(config.user_opts)
+    table.insert(status_lines, "Config: " .. (#problems == 0 and "✓" or (#problems .. " issue(s)")))
+    for _, problem in ipairs(problems) do
+      table.insert(status_lines, "  " .. problem)
     end
 
     if health.error then
@@ -274,47 +490,295 @@ function M.setup(opts)
     vim.notify(table.concat(status_lines, "\n"), vim.log.levels.INFO)
   end, { desc = 'Check Zeke CLI health' })
 
+  -- MCP server (external agents connect through the stdio shim)
+  command('ZekeMcpConfig', function()
+    local entry = { mcpServers = { zeke = mcp.client_config() } }
+    local json = vim.json.encode(entry)
+    vim.fn.setreg('+', json)
+    vim.notify("MCP client config (copied to + register):\n" .. json, vim.log.levels.INFO)
+  end, { desc = 'Show MCP client config for this session' })
+
+  command('ZekeConfigReload', function()
+    config.reload()
+    vim.notify("Zeke configuration reloaded", vim.log.levels.INFO)
+  end, { desc = 'Reload Zeke config files' })
+
   -- Help commands
   local help = require('zeke.help')
 
-  vim.api.nvim_create_user_command('ZekeHelp', function()
+  command('ZekeHelp', function()
     help.show()
   end, { desc = 'Show interactive help' })
 
-  vim.api.nvim_create_user_command('ZekeQuickRef', function()
+  command('ZekeQuickRef', function()
     help.show_quick_reference()
   end, { desc = 'Show quick reference' })
 
   -- Production Polish Commands
-  vim.api.nvim_create_user_command('ZekeRequests', function()
+  command('ZekeTranscript', function()
+    local path = transcript.path()
+    if vim.fn.filereadable(path) == 0 then
+      vim.notify('No transcript for this session (transcripts.enabled = ' .. tostring(transcript.config.enabled) .. ')', vim.log.levels.INFO)
+      return
+    end
+    vim.cmd('edit ' .. vim.fn.fnameescape(path))
+  end, { desc = "Open this session's stream transcript" })
+
+  command('ZekeWireLog', function()
+    local path = wire_log.path()
+    if vim.fn.filereadable(path) == 0 then
+      vim.notify('No wire log yet (wire_log.enabled = ' .. tostring(wire_log.config.enabled) .. ')', vim.log.levels.INFO)
+      return
+    end
+    vim.cmd('edit ' .. vim.fn.fnameescape(path))
+  end, { desc = 'Open the request wire log' })
+
+  command('ZekeAudit', function(args)
+    audit.show(tonumber(args.args))
+  end, { nargs = '?', desc = 'Show recent AI-driven file writes, edits and commands' })
+
+  command('ZekeRequests', function()
     requests.show_inspector()
   end, { desc = 'Show request inspector' })
 
-  vim.api.nvim_create_user_command('ZekeTokens', function()
+  command('ZekeMetrics', function(args)
+    if args.args == 'reset' then
+      requests.reset_metrics()
+      vim.notify('Provider metrics reset', vim.log.levels.INFO)
+      return
+    end
+    vim.notify(requests.format_metrics(), vim.log.levels.INFO)
+  end, {
+    nargs = '?',
+    complete = function() return { 'reset' } end,
+    desc = 'Show per-provider latency and error metrics',
+  })
+
+  command('ZekeWarmup', function(args)
+    local model = args.args ~= '' and args.args or nil
+    local started = warmup.warm(model, function(ok, elapsed_ms)
+      vim.schedule(function()
+        vim.notify(ok and string.format('Model loaded in %dms', elapsed_ms) or 'Warm-up failed (see :ZekeLog)',
+          ok and vim.log.levels.INFO or vim.log.levels.WARN)
+      end)
+    end)
+    if not started then
+      vim.notify('Nothing to warm up: not a local model, or warmup is disabled for it', vim.log.levels.INFO)
+    end
+  end, { nargs = '?', desc = 'Load a local Ollama/llama.cpp model now (default: current model)' })
+
+  command('ZekeAgent', function(args)
+    if args.args == '' then
+      agent_loop.show()
+      return
+    end
+    if args.args == 'stop' then
+      local latest = agent_loop.list()[1]
+      if not (latest and agent_loop.stop(latest.id)) then
+        vim.notify('No agent run in progress', vim.log.levels.INFO)
+      end
+      return
+    end
+
+    start_agent(args.args, agent_loop.config.plan_first)
+  end, { nargs = '?', desc = 'Run the agent on a goal, show the latest run, or stop it' })
+
+  command('ZekeAgentUndo', function(args)
+    local run_id = tonumber(args.args)
+    if not run_id then
+      local latest = agent_checkpoints.list()[1]
+      run_id = latest and latest.run_id
+    end
+    if not run_id then
+      vim.notify('No agent run has changed files', vim.log.levels.INFO)
+      return
+    end
+    local restored, err = agent_checkpoints.revert(run_id, { force = args.bang })
+    if restored then
+      vim.notify(string.format('Reverted agent run #%d (%d files)', run_id, restored), vim.log.levels.INFO)
+    end
+    if err then
+      vim.notify(err, vim.log.levels.WARN)
+    end
+  end, { nargs = '?', bang = true, desc = 'Revert every file change of an agent run (default: latest; ! even if edited since)' })
+
+  command('ZekeAgentPlan', function(args)
+    start_agent(args.args, true)
+  end, { nargs = 1, desc = 'Plan an agent run for review before it starts' })
+
+  command('ZekeServer', function(args)
+    if args.args == 'start' then
+      local ok, err = openai_server.start()
+      if not ok then
+        vim.notify('Could not start the server: ' .. err, vim.log.levels.ERROR)
+        return
+      end
+    elseif args.args == 'stop' then
+      openai_server.stop()
+    end
+    local status = openai_server.status()
+    vim.notify(status.running and string.format('Serving %s (%d requests, %d errors)', status.url, status.requests,
+      status.errors) or 'OpenAI-compatible server is not running', vim.log.levels.INFO)
+  end, {
+    nargs = '?',
+    complete = function() return { 'start', 'stop' } end,
+    desc = 'Start, stop or show the local OpenAI-compatible server',
+  })
+
+  command('ZekeCache', function(args)
+    if args.args == 'clear' then
+      vim.notify(string.format('Removed %d cached responses', cache.clear()), vim.log.levels.INFO)
+      return
+    end
+    vim.notify(cache.format_stats(), vim.log.levels.INFO)
+  end, {
+    nargs = '?',
+    complete = function() return { 'clear' } end,
+    desc = 'Show response cache statistics, or clear the cache',
+  })
+
+  command('ZekeTokens', function()
     vim.notify(tokens.format_usage_stats(), vim.log.levels.INFO)
   end, { desc = 'Show token usage statistics' })
 
-  vim.api.nvim_create_user_command('ZekeTokensReset', function()
+  command('ZekeLog', function(args)
+    logger.show(tonumber(args.args))
+  end, { nargs = '?', desc = 'Show recent errors and the end of the log file' })
+
+  command('ZekePrivacy', function(args)
+    if args.args == '' then
+      vim.notify('Privacy mode: ' .. (privacy.enabled() and 'on' or 'off'), vim.log.levels.INFO)
+      return
+    end
+    if not vim.tbl_contains({ 'on', 'off', 'toggle' }, args.args) then
+      vim.notify('Usage: :ZekePrivacy [on|off|toggle]', vim.log.levels.ERROR)
+      return
+    end
+    M.set_privacy(args.args == 'toggle' and not privacy.enabled() or args.args == 'on')
+  end, {
+    nargs = '?',
+    complete = function() return { 'on', 'off', 'toggle' } end,
+    desc = 'Show or switch privacy (local-only) mode',
+  })
+
+  command('ZekeLogLevel', function(args)
+    if args.args == '' then
+      vim.notify('Log level: ' .. logger.level_name(), vim.log.levels.INFO)
+      return
+    end
+    M.set_log_level(args.args)
+  end, {
+    nargs = '?',
+    complete = function() return { 'DEBUG', 'INFO', 'WARN', 'ERROR' } end,
+    desc = 'Show or set the log level',
+  })
+
+  command('ZekeTokenCount', function(opts)
+    local lines = vim.api.nvim_buf_get_lines(0, opts.line1 - 1, opts.line2, false)
+    local text = table.concat(lines, '\n')
+    vim.notify(string.format('%d tokens (%s tokenizer)', tokens.count(text), tokens.tokenizer_for()),
+      vim.log.levels.INFO)
+  end, { range = '%', desc = 'Count tokens of the buffer or range for the current model' })
+
+  command('ZekeTokensReset', function()
     tokens.reset_usage()
     vim.notify("Token statistics reset", vim.log.levels.INFO)
   end, { desc = 'Reset token statistics' })
 
-  vim.api.nvim_create_user_command('ZekeBackups', function()
+  command('ZekeBackups', function()
     backup.show_backup_picker()
   end, { desc = 'Show backup picker' })
 
-  vim.api.nvim_create_user_command('ZekeBackupStats', function()
+  command('ZekeBackupStats', function()
     backup.show_stats()
   end, { desc = 'Show backup statistics' })
 
-  vim.api.nvim_create_user_command('ZekeBackupCleanup', function()
+  command('ZekeBackupCleanup', function()
     backup.cleanup_all_old_backups()
   end, { desc = 'Cleanup old backups' })
 
-  vim.api.nvim_create_user_command('ZekeSafety', function()
+  command('ZekeSafety', function()
     safety.show_stats()
   end, { desc = 'Show safety statistics' })
 
+  -- Background tasks
+  command('ZekeTasks', function()
+    commands.list_tasks()
+  end, { desc = 'List running and recent tasks' })
+
+  command('ZekeCancelTask', function(args)
+    commands.cancel_task(tonumber(args.args))
+  end, { nargs = 1, desc = 'Cancel a running or queued task' })
+
+  command('ZekeCancelAll', function()
+    commands.cancel_all_tasks()
+  end, { desc = 'Cancel all running and queued tasks' })
+
+  -- Long-running watchers
+  local function watcher_names()
+    return vim.tbl_map(function(w) return w.name end, terminal.get_watchers())
+  end
+
+  command('ZekeWatch', function(args)
+    terminal.watch(args.args)
+  end, { nargs = '+', complete = 'shellcmd', desc = 'Run a long-running watcher task' })
+
+  command('ZekeWatchStop', function(args)
+    terminal.stop_watcher(args.args)
+  end, { nargs = 1, complete = watcher_names, desc = 'Stop a watcher' })
+
+  command('ZekeWatchShow', function(args)
+    terminal.show_watcher(args.args)
+  end, { nargs = 1, complete = watcher_names, desc = "Show a watcher's output" })
+
+  -- Prompt library
+  local function prompt_names()
+    return vim.tbl_map(function(p) return p.name end, prompt_library.list())
+  end
+
+  command('ZekePromptSave', function(args)
+    local ok, err = prompt_library.save_current(args.args)
+    if ok then
+      vim.notify('Saved prompt: ' .. args.args, vim.log.levels.INFO)
+    else
+      vim.notify(err, vim.log.levels.ERROR)
+    end
+  end, { nargs = 1, desc = "Save the chat panel's prompt to the prompt library" })
+
+  command('ZekePrompt', function(args)
+    local opts = { range = args.range > 0 }
+    if args.args == '' then
+      prompt_library.pick(opts)
+    else
+      prompt_library.run(args.args, opts)
+    end
+  end, { nargs = '?', range = true, complete = prompt_names, desc = 'Run a saved prompt on the buffer or selection' })
+
+  command('ZekePromptDelete', function(args)
+    if not prompt_library.delete(args.args) then
+      vim.notify('No such prompt: ' .. args.args, vim.log.levels.WARN)
+    end
+  end, { nargs = 1, complete = prompt_names, desc = 'Delete a saved prompt' })
+
+  command('ZekeTaskQuickfix', function(args)
+    local id = tonumber(args.args)
+    if not id then
+      local history = terminal.get_task_history()
+      id = history[#history] and history[#history].id
+    end
+    local items = id and require('zeke.output_parsers').from_task(id)
+    if not items or #items == 0 then
+      vim.notify('No diagnostics found in task output', vim.log.levels.INFO)
+      return
+    end
+    require('zeke.output_parsers').to_quickfix(items, 'Zeke Task #' .. id)
+    vim.cmd('copen')
+  end, { nargs = '?', desc = 'Load diagnostics from task output into quickfix (default: last task)' })
+
+  command('ZekeTestFix', function(args)
+    testfix.run({ command = args.args ~= '' and args.args or nil })
+  end, { nargs = '?', desc = 'Run the tests and fix failures with AI' })
+
   -- =============================================================================
   -- Keymaps
   -- =============================================================================
@@ -322,82 +786,72 @@ function M.setup(opts)
   if cfg.keymaps.enabled then
     local km = cfg.keymaps
 
-    -- Main commands
-    if km.code then
-      vim.keymap.set('n', km.code, ':ZekeCode<CR>', { desc = 'Open ZekeCode Agent', silent = true })
+    -- Map lhs (skipped when false/nil) to a user command name or a function
+    local function map(mode, lhs, rhs, desc)
+      if not lhs then
+        return
+      end
+      if type(rhs) == 'string' then
+        local name = M.command_name(rhs)
+        if not name then
+          return
+        end
+        rhs = ':' .. name .. '<CR>'
+      end
+      vim.keymap.set(mode, lhs, rhs, { desc = desc, silent = true })
     end
 
-    if km.chat then
-      vim.keymap.set('n', km.chat, function()
-        vim.ui.input({ prompt = 'Chat: ' }, function(input)
-          if input then commands.chat(input) end
-        end)
-      end, { desc = 'Quick chat with Zeke', silent = true })
-    end
+    -- Main commands
+    map('n', km.code, 'ZekeCode', 'Open ZekeCode Agent')
 
-    if km.explain then
-      vim.keymap.set('n', km.explain, ':ZekeExplain<CR>', { desc = 'Explain code', silent = true })
-    end
+    map('n', km.chat, function()
+      vim.ui.input({ prompt = 'Chat: ' }, function(input)
+        if input then commands.chat(input) end
+      end)
+    end, 'Quick chat with Zeke')
+
+    map('n', km.explain, 'ZekeExplain', 'Explain code')
 
     -- Code Actions (new!)
-    if km.actions then
-      vim.keymap.set({'n', 'v'}, km.actions, ':ZekeActions<CR>', { desc = 'Code Actions Menu', silent = true })
-    end
+    map({'n', 'v'}, km.actions, 'ZekeActions', 'Code Actions Menu')
 
     -- Quick actions
-    vim.keymap.set({'n', 'v'}, '<leader>ze', ':ZekeExplainCode<CR>', { desc = 'Explain code', silent = true })
-    vim.keymap.set({'n', 'v'}, '<leader>zf', ':ZekeFixCode<CR>', { desc = 'Fix code', silent = true })
-    vim.keymap.set({'n', 'v'}, '<leader>zr', ':ZekeRefactorCode<CR>', { desc = 'Refactor code', silent = true })
-    vim.keymap.set({'n', 'v'}, '<leader>zt', ':ZekeGenerateTests<CR>', { desc = 'Generate tests', silent = true })
-
-    if km.edit then
-      vim.keymap.set('n', km.edit, function()
-        vim.ui.input({ prompt = 'Edit instruction: ' }, function(input)
-          if input then commands.edit_buffer(input) end
-        end)
-      end, { desc = 'Edit with AI', silent = true })
-    end
+    map({'n', 'v'}, km.explain_code, 'ZekeExplainCode', 'Explain code')
+    map({'n', 'v'}, km.fix_code, 'ZekeFixCode', 'Fix code')
+    map({'n', 'v'}, km.refactor_code, 'ZekeRefactorCode', 'Refactor code')
+    map({'n', 'v'}, km.generate_tests, 'ZekeGenerateTests', 'Generate tests')
 
-    -- Model management
-    if km.model_picker then
-      vim.keymap.set('n', km.model_picker, ':ZekeModels<CR>', { desc = 'Model picker', silent = true })
-    end
-
-    if km.model_next then
-      vim.keymap.set('n', km.model_next, ':ZekeModelNext<CR>', { desc = 'Next model', silent = true })
-    end
+    map('n', km.edit, function()
+      vim.ui.input({ prompt = 'Edit instruction: ' }, function(input)
+        if input then commands.edit_buffer(input) end
+      end)
+    end, 'Edit with AI')
 
-    if km.model_prev then
-      vim.keymap.set('n', km.model_prev, ':ZekeModelPrev<CR>', { desc = 'Previous model', silent = true })
-    end
+    -- Model management
+    map('n', km.model_picker, 'ZekeModels', 'Model picker')
+    map('n', km.model_next, 'ZekeModelNext', 'Next model')
+    map('n', km.model_prev, 'ZekeModelPrev', 'Previous model')
 
     -- Chat panel
-    if km.chat_panel then
-      vim.keymap.set('n', km.chat_panel, ':ZekeChatPanel<CR>', { desc = 'Toggle chat panel', silent = true })
-    end
+    map('n', km.chat_panel, 'ZekeChatPanel', 'Toggle chat panel')
 
     -- LSP integration
-    if km.fix_diagnostic then
-      vim.keymap.set('n', km.fix_diagnostic, ':ZekeFix<CR>', { desc = 'Fix diagnostic with AI', silent = true })
-    end
-
-    if km.explain_diagnostic then
-      vim.keymap.set('n', km.explain_diagnostic, ':ZekeExplainDiagnostic<CR>', { desc = 'Explain diagnostic', silent = true })
-    end
+    map('n', km.fix_diagnostic, 'ZekeFix', 'Fix diagnostic with AI')
+    map('n', km.explain_diagnostic, 'ZekeExplainDiagnostic', 'Explain diagnostic')
 
     -- AI assistance shortcuts (similar to Copilot)
-    vim.keymap.set('n', '<leader>aa', function()
+    map('n', km.ai_ask, function()
       vim.ui.input({ prompt = 'Ask AI: ' }, function(input)
         if input then
           chat_panel.open()
           chat_panel.send_message(input)
         end
       end)
-    end, { desc = 'Ask AI', silent = true })
+    end, 'Ask AI')
 
-    vim.keymap.set('n', '<leader>af', ':ZekeFix<CR>', { desc = 'AI: Fix diagnostic', silent = true })
-    vim.keymap.set('n', '<leader>ae', ':ZekeEdit<CR>', { desc = 'AI: Edit selection', silent = true })
-    vim.keymap.set('v', '<leader>ae', function()
+    map('n', km.ai_fix, 'ZekeFix', 'AI: Fix diagnostic')
+    map('n', km.ai_edit, 'ZekeEdit', 'AI: Edit selection')
+    map('v', km.ai_edit, function()
       -- Get visual selection
       local start_pos = vim.fn.getpos("'<")
       local end_pos = vim.fn.getpos("'>")
@@ -405,46 +859,268 @@ function M.setup(opts)
 
       vim.ui.input({ prompt = 'Edit instruction: ' }, function(input)
         if input then
-          local context = table.concat(lines, '\n')
-          local prompt = string.format("Edit the following code:\n\n```\n%s\n```\n\nInstruction: %s", context, input)
+          local prompt = config.render_prompt('edit_selection', {
+            code = table.concat(lines, '\n'),
+            instruction = input,
+            filetype = vim.bo.filetype,
+          })
           chat_panel.open()
           chat_panel.send_message(prompt)
         end
       end)
-    end, { desc = 'AI: Edit selection', silent = true })
+    end, 'AI: Edit selection')
 
-    vim.keymap.set('n', '<leader>ax', ':ZekeExplain<CR>', { desc = 'AI: Explain code', silent = true })
-    vim.keymap.set('n', '<leader>ac', ':ZekeChatPanel<CR>', { desc = 'AI: Toggle chat', silent = true })
-    vim.keymap.set('n', '<leader>at', function() completion.inline.toggle() end, { desc = 'AI: Toggle completions', silent = true })
+    map('n', km.ai_explain, 'ZekeExplain', 'AI: Explain code')
+    map('n', km.ai_chat, 'ZekeChatPanel', 'AI: Toggle chat')
+    map('n', km.ai_toggle_completions, function() completion.inline.toggle() end, 'AI: Toggle completions')
 
-    if km.model_info then
-      vim.keymap.set('n', km.model_info, ':ZekeModelInfo<CR>', { desc = 'Model info', silent = true })
-    end
+    map('n', km.model_info, 'ZekeModelInfo', 'Model info')
 
     -- Quick switches
-    if km.quick_smart then
-      vim.keymap.set('n', km.quick_smart, function()
-        models.set_model('smart')
-        vim.notify("Model: Smart", vim.log.levels.INFO)
-      end, { desc = 'Switch to Smart', silent = true })
-    end
+    map('n', km.quick_smart, function()
+      models.set_model('smart')
+      vim.notify("Model: Smart", vim.log.levels.INFO)
+    end, 'Switch to Smart')
+
+    map('n', km.quick_fast, function()
+      models.set_model('fast')
+      vim.notify("Model: Fast", vim.log.levels.INFO)
+    end, 'Switch to Fast')
+
+    map('n', km.quick_local, function()
+      models.set_model('qwen2.5-coder:7b')
+      vim.notify("Model: Qwen2.5 Coder (Ollama)", vim.log.levels.INFO)
+    end, 'Switch to Local Ollama')
+  end
 
-    if km.quick_fast then
-      vim.keymap.set('n', km.quick_fast, function()
-        models.set_model('fast')
-        vim.notify("Model: Fast", vim.log.levels.INFO)
-      end, { desc = 'Switch to Fast', silent = true })
-    end
+  logger.info("init", "zeke.nvim initialized successfully")
+end
 
-    if km.quick_local then
-      vim.keymap.set('n', km.quick_local, function()
-        models.set_model('qwen2.5-coder:7b')
-        vim.notify("Model: Qwen2.5 Coder (Ollama)", vim.log.levels.INFO)
-      end, { desc = 'Switch to Local Ollama', silent = true })
-    end
+-- Resolve a built-in command name to its configured name (nil when disabled)
+function M.command_name(name)
+  local cmds = config.options.commands or {}
+  if cmds.enabled == false then
+    return nil
+  end
+  local override = cmds[name]
+  if override == false then
+    return nil
   end
+  return type(override) == 'string' and override or name
+end
 
-  logger.info("init", "zeke.nvim initialized successfully")
+-- CLI state ("unknown", "ready", "missing", "error") and stream activity;
+-- changes fire the ZekeStatusChanged User autocmd
+function M.status()
+  return cli.status()
+end
+
+-- Prompt library: save, list and run reusable prompts
+function M.save_prompt(name, text, meta)
+  return prompt_library.save(name, text, meta)
+end
+
+function M.prompts()
+  return prompt_library.list()
+end
+
+-- opts.range: run on the visual selection; opts.vars: template variables
+function M.run_prompt(name, opts)
+  return prompt_library.run(name, opts)
+end
+
+-- Per-provider request counts, errors by category and latency/TTFT
+-- histograms (ms), for comparing providers
+function M.metrics()
+  return requests.metrics()
+end
+
+-- Load a local model in the background (default: current model);
+-- callback(ok, elapsed_ms). Returns false when there's nothing to warm up
+function M.warmup(model, callback)
+  return warmup.warm(model, callback)
+end
+
+-- Send a prompt to several models at once (opts.targets, default
+-- race.targets); callback(response, exit_code, winner) gets the first
+-- successful response and the other streams are cancelled
+function M.race(message, callback, opts)
+  return cli.race(message, callback, opts)
+end
+
+-- Embed a text (-> vector) or a list of texts (-> list of vectors) with the
+-- embeddings backend; opts: provider, model, input_type ("document" |
+-- "query"). Without a callback, waits and returns vectors, err
+function M.embed(texts, callback, opts)
+  if callback then
+    return embeddings.embed(texts, callback, opts)
+  end
+  return embeddings.embed_sync(texts, opts)
+end
+
+-- Rank documents by relevance to a query with the rerank backend:
+-- {index, score, document}, best first; opts: provider, model, top_n.
+-- Without a callback, waits and returns results, err
+function M.rerank(query, documents, callback, opts)
+  if callback then
+    return rerank.rerank(query, documents, callback, opts)
+  end
+  return rerank.rerank_sync(query, documents, opts)
+end
+
+-- Transcribe an audio file path or audio bytes; callback(text, err).
+-- opts: provider, model, language, prompt, format (extension of raw bytes)
+function M.transcribe(audio, callback, opts)
+  return transcription.transcribe(audio, callback, opts)
+end
+
+-- Local OpenAI-compatible server: {running, url, requests, errors}
+function M.server_status()
+  return openai_server.status()
+end
+
+-- Run the agent on a goal: the model calls registered tools
+-- (require('zeke.agent_tools').register) until it answers. opts: max_steps,
+-- max_tokens, max_cost, max_time_s, tools, on_step(run, step), on_done(run).
+-- Returns the run
+function M.agent_run(goal, opts)
+  return agent_loop.run(goal, opts)
+end
+
+-- Like agent_run, but the model first writes a plan that opens in a buffer;
+-- :w approves the (edited) plan and starts the run. callback(ok, err) runs
+-- once the plan is open or planning failed
+function M.agent_plan(goal, opts, callback)
+  return agent_loop.plan(goal, opts, callback)
+end
+
+function M.agent_stop(id)
+  return agent_loop.stop(id)
+end
+
+-- Agent runs that changed files, newest first: {run_id, goal, created_at,
+-- files, reverted_at}
+function M.agent_checkpoints()
+  return agent_checkpoints.list()
+end
+
+-- Restore every file an agent run changed; returns the number of files or nil, err.
+-- Files edited since the run are only overwritten with opts.force
+function M.agent_undo(run_id, opts)
+  return agent_checkpoints.revert(run_id, opts)
+end
+
+-- Response cache: {hits, misses, writes, expired, evicted, hit_rate,
+-- entries, size_bytes, dir}
+function M.cache_stats()
+  return cache.get_stats()
+end
+
+function M.clear_cache()
+  return cache.clear()
+end
+
+-- Estimated token usage and cost: session totals plus `today`
+-- ({date, requests, tokens, cost}); each ZekeStreamEnd also carries `usage`
+function M.usage()
+  return tokens.get_usage_stats()
+end
+
+-- Log access: file path, last n lines, and this session's recent
+-- warnings/errors as { {time, level, context, message}, ... }
+function M.log_path()
+  return logger.path()
+end
+
+function M.tail_log(n)
+  return logger.tail(n)
+end
+
+function M.recent_errors(n)
+  return logger.recent_errors(n)
+end
+
+-- Privacy mode: local providers only, no sensitive files in prompts, no
+-- AI-driven file writes or commands; fires the ZekePrivacyChanged User autocmd
+function M.set_privacy(enabled)
+  privacy.set(enabled)
+  vim.notify('Privacy mode ' .. (privacy.enabled() and 'on' or 'off'), vim.log.levels.INFO)
+end
+
+-- AI-driven file writes, buffer edits and commands, newest first;
+-- filter = {kind, source, path, provider, since, limit}
+function M.audit(filter)
+  return audit.entries(filter)
+end
+
+-- Change log verbosity at runtime ("DEBUG", "INFO", "WARN", "ERROR");
+-- also applies to the CLI on its next call
+function M.set_log_level(level)
+  if type(level) == 'string' and not logger.levels[level:upper()] then
+    vim.notify('Unknown log level: ' .. level, vim.log.levels.ERROR)
+    return
+  end
+  logger.set_level(level)
+  logger.info('init', 'Log level set to ' .. logger.level_name())
+end
+
+-- Token count of text for a model (default: current model)
+function M.count_tokens(text, model)
+  return tokens.count(text, model)
+end
+
+-- Ask for JSON matching a JSON Schema; returns the decoded value or nil, error
+-- (with a callback, doesn't block and calls callback(value, err) instead)
+function M.generate_structured(prompt, schema, opts, callback)
+  return cli.generate_structured(prompt, schema, opts, callback)
+end
+
+-- Background tasks (terminal module)
+-- Run a command (string for the shell, or an argument list) as a task
+function M.run(cmd, opts)
+  return terminal.run(cmd, opts)
+end
+
+-- Running and queued tasks
+function M.tasks()
+  return terminal.get_active_tasks()
+end
+
+-- Retained output of a task: { lines, spans, first, next, total, dropped }
+function M.task_output(id, offset, limit)
+  return terminal.task_output(id, offset, limit)
+end
+
+function M.cancel_task(id)
+  return terminal.cancel_task(id)
+end
+
+-- Diagnostics { file, line, col, severity, message, source } parsed from a task's output
+function M.task_diagnostics(id, parser)
+  return require('zeke.output_parsers').from_task(id, parser)
+end
+
+-- Long-running watcher (restarted on exit); opts: name, cwd, restart, max_restarts
+function M.watch(cmd, opts)
+  return terminal.watch(cmd, opts)
+end
+
+function M.stop_watcher(name)
+  return terminal.stop_watcher(name)
+end
+
+function M.watchers()
+  return terminal.get_watchers()
+end
+
+-- Run the tests and fix failures until green: { command, max_rounds, cwd }
+function M.test_fix(opts)
+  return testfix.run(opts)
+end
+
+-- Machine-readable description of all config keys, types and defaults
+function M.config_schema()
+  return config.schema()
 end
 
 -- Export submodules for direct access
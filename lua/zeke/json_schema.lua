@@ -0,0 +1,130 @@
+--[[
+  Minimal JSON Schema support for structured model output
+
+  Covers the subset used to describe results: type, properties, required,
+  items, enum, additionalProperties = false, minItems/maxItems.
+--]]
+
+local M = {}
+
+local utils = require('zeke.utils')
+
+local function type_of(value)
+  if value == vim.NIL then
+    return 'null'
+  end
+  local t = type(value)
+  if t == 'table' then
+    if vim.tbl_isempty(value) then
+      -- {} decodes the same for [] and {}; accept either
+      return 'empty'
+    end
+    return vim.tbl_islist(value) and 'array' or 'object'
+  elseif t == 'number' then
+    return value == math.floor(value) and 'integer' or 'number'
+  end
+  return t
+end
+
+local function type_matches(actual, expected)
+  if actual == expected then
+    return true
+  end
+  return (expected == 'number' and actual == 'integer')
+    or (actual == 'empty' and (expected == 'array' or expected == 'object'))
+end
+
+---Validate a decoded value against a schema
+---@param value any
+---@param schema table
+---@param path string|nil Location for messages (default "$")
+---@param errors table|nil Accumulator used for nested values
+---@return string[] errors (empty when valid)
+function M.validate(value, schema, path, errors)
+  path = path or '$'
+  errors = errors or {}
+  if type(schema) ~= 'table' then
+    return errors
+  end
+
+  local actual = type_of(value)
+  if schema.type then
+    local expected = type(schema.type) == 'table' and schema.type or { schema.type }
+    local ok = false
+    for _, t in ipairs(expected) do
+      ok = ok or type_matches(actual, t)
+    end
+    if not ok then
+      table.insert(errors, string.format('%s: expected %s, got %s', path, table.concat(expected, '|'), actual))
+      return errors
+    end
+  end
+
+  if schema.enum and not vim.tbl_contains(schema.enum, value) then
+    table.insert(errors, string.format('%s: must be one of %s', path, vim.json.encode(schema.enum)))
+  end
+
+  if actual == 'object' or (actual == 'empty' and schema.properties) then
+    for _, key in ipairs(schema.required or {}) do
+      if value[key] == nil then
+        table.insert(errors, string.format('%s: missing required property "%s"', path, key))
+      end
+    end
+    for key, item in pairs(value) do
+      local prop = schema.properties and schema.properties[key]
+      if prop then
+        M.validate(item, prop, path .. '.' .. key, errors)
+      elseif schema.additionalProperties == false then
+        table.insert(errors, string.format('%s: unexpected property "%s"', path, key))
+      end
+    end
+  elseif actual == 'array' then
+    if schema.minItems and #value < schema.minItems then
+      table.insert(errors, string.format('%s: expected at least %d items', path, schema.minItems))
+    end
+    if schema.maxItems and #value > schema.maxItems then
+      table.insert(errors, string.format('%s: expected at most %d items', path, schema.maxItems))
+    end
+    if schema.items then
+      for i, item in ipairs(value) do
+        M.validate(item, schema.items, string.format('%s[%d]', path, i), errors)
+      end
+    end
+  end
+
+  return errors
+end
+
+---Pull the JSON document out of a model response (fenced, or surrounded by prose)
+---@param text string
+---@return any|nil value
+---@return string|nil error
+function M.extract(text)
+  local candidates = {}
+  local fenced = utils.first_code_block(text, 'json')
+  if fenced then
+    table.insert(candidates, fenced)
+  end
+  table.insert(candidates, vim.trim(text))
+  -- Outermost object or array in the text
+  local object = text:match('%b{}')
+  if object then
+    table.insert(candidates, object)
+  end
+  local array = text:match('%b[]')
+  if array then
+    table.insert(candidates, array)
+  end
+
+  local last_err = 'no JSON found'
+  for _, candidate in ipairs(candidates) do
+    local ok, value = pcall(vim.json.decode, candidate)
+    if ok then
+      return value, nil
+    end
+    last_err = tostring(value)
+  end
+  return nil, last_err
+end
+
+return M
@@ -71,7 +71,7 @@ return {
     -- Logging (DEBUG, INFO, WARN, ERROR)
     logger = {
       level = "INFO",
-      file = vim.fn.stdpath("cache") .. "/zeke.log",
+      file = "~/.local/state/zeke/zeke.nvim.log",
       show_timestamp = true,
     },
 
@@ -5,17 +5,66 @@ local M = {}
 
 local logger = require('zeke.logger')
 
+M.lock_dir = "~/.zeke/ide"
 M.lock_file_path = nil
 M.port = 7878  -- Default Zeke HTTP API port
+M.write_grace_seconds = 5  -- Unparseable lock files younger than this may still be mid-write
+
+-- Write via a temp file + rename so readers never see a partial lock file
+local function write_atomic(path, content)
+  local tmp = string.format("%s.%d.tmp", path, vim.fn.getpid())
+  if vim.fn.writefile({ content }, tmp) ~= 0 then
+    return false, "Failed to write " .. tmp
+  end
+
+  local ok, err = vim.loop.fs_rename(tmp, path)
+  if not ok then
+    vim.fn.delete(tmp)
+    return false, err
+  end
+  return true
+end
+
+-- Whether nothing is listening on a local TCP port
+local function port_is_free(port)
+  local server = vim.loop.new_tcp()
+  local ok = server:bind("127.0.0.1", port) == 0
+  server:close()
+  return ok
+end
+
+-- Pick a port from [first, last] that no live instance has claimed and
+-- nothing is bound to
+function M.find_free_port(range)
+  range = range or { M.port, M.port + 99 }
+
+  local claimed = {}
+  for _, session in ipairs(M.list()) do
+    if session.alive and session.port then
+      claimed[session.port] = true
+    end
+  end
+
+  for port = range[1], range[2] do
+    if not claimed[port] and port_is_free(port) then
+      return port
+    end
+  end
+
+  return nil
+end
 
 -- Create lock file
 function M.create(port)
   port = port or M.port
 
   -- Create lock directory
-  local lock_dir = vim.fn.expand("~/.zeke/ide")
+  local lock_dir = vim.fn.expand(M.lock_dir)
   vim.fn.mkdir(lock_dir, "p")
 
+  -- Drop leftovers from crashed instances before claiming a file
+  M.cleanup_stale()
+
   -- Lock file path
   M.lock_file_path = lock_dir .. "/" .. port .. ".lock"
 
@@ -26,6 +75,7 @@ function M.create(port)
     protocol = "http",
     base_url = "http://localhost:" .. port,
     editor = "neovim",
+    nvim_server = vim.v.servername,  -- RPC address used by the MCP stdio shim
     version = vim.version(),
     cwd = vim.fn.getcwd(),
     created_at = os.time(),
@@ -33,7 +83,7 @@ function M.create(port)
 
   -- Write lock file
   local content = vim.json.encode(lock_data, { indent = 2 })
-  local ok, err = pcall(vim.fn.writefile, {content}, M.lock_file_path)
+  local ok, err = write_atomic(M.lock_file_path, content)
 
   if ok then
     logger.info('lockfile', 'Created lock file: ' .. M.lock_file_path)
@@ -89,14 +139,105 @@ function M.read()
     return nil
   end
 
-  local decode_ok, data = pcall(vim.json.decode, content[1])
-  if decode_ok then
+  local decode_ok, data = pcall(vim.json.decode, table.concat(content, "\n"))
+  if decode_ok and type(data) == "table" then
     return data
   end
 
   return nil
 end
 
+-- Check that a PID is a live Neovim process without spawning anything.
+-- Signal 0 only probes the PID; /proc (Linux) guards against PID reuse.
+function M.is_process_running(pid)
+  if type(pid) ~= "number" or pid <= 0 then
+    return false
+  end
+
+  local ret, err = vim.loop.kill(pid, 0)
+  if ret ~= 0 and not (err and err:match("EPERM")) then
+    return false
+  end
+
+  local comm = io.open("/proc/" .. pid .. "/comm", "r")
+  if comm then
+    local name = comm:read("*l") or ""
+    comm:close()
+    return name:match("nvim") ~= nil
+  end
+
+  return true
+end
+
+-- List lock files of all instances, with liveness
+function M.list()
+  local sessions = {}
+  local files = vim.fn.glob(vim.fn.expand(M.lock_dir) .. "/*.lock", false, true)
+
+  for _, file in ipairs(files) do
+    local ok, content = pcall(vim.fn.readfile, file)
+    local decode_ok, data = pcall(vim.json.decode, ok and table.concat(content, "\n") or "")
+    if decode_ok and type(data) == "table" then
+      data.path = file
+      data.alive = M.is_process_running(data.pid)
+      table.insert(sessions, data)
+    elseif os.time() - vim.fn.getftime(file) > M.write_grace_seconds then
+      -- Unreadable and not being written right now: move it out of the scan
+      local quarantined = file .. ".corrupt"
+      vim.loop.fs_rename(file, quarantined)
+      logger.warn('lockfile', 'Quarantined corrupted lock file: ' .. quarantined)
+    end
+  end
+
+  return sessions
+end
+
+M.stats = { stale_removed = 0, cleanup_runs = 0, last_cleanup = nil }
+M.cleanup_timer = nil
+
+-- Remove lock files left behind by instances that are no longer running
+function M.cleanup_stale()
+  local removed = 0
+
+  for _, session in ipairs(M.list()) do
+    if not session.alive and session.path ~= M.lock_file_path then
+      if vim.fn.delete(session.path) == 0 then
+        removed = removed + 1
+      end
+    end
+  end
+
+  M.stats.stale_removed = M.stats.stale_removed + removed
+  M.stats.cleanup_runs = M.stats.cleanup_runs + 1
+  M.stats.last_cleanup = os.time()
+
+  if removed > 0 then
+    logger.info('lockfile', string.format('Removed %d stale lock file(s)', removed))
+  end
+  return removed
+end
+
+-- Run cleanup_stale every interval_seconds (0 or nil disables)
+function M.schedule_cleanup(interval_seconds)
+  if M.cleanup_timer then
+    M.cleanup_timer:stop()
+    M.cleanup_timer:close()
+    M.cleanup_timer = nil
+  end
+
+  if not interval_seconds or interval_seconds <= 0 then
+    return
+  end
+
+  local interval_ms = interval_seconds * 1000
+  M.cleanup_timer = vim.loop.new_timer()
+  M.cleanup_timer:start(interval_ms, interval_ms, vim.schedule_wrap(M.cleanup_stale))
+end
+
+function M.get_stats()
+  return vim.deepcopy(M.stats)
+end
+
 -- Update lock file (e.g., if cwd changes)
 function M.update()
   if not M.exists() then
@@ -114,7 +255,7 @@ function M.update()
 
   -- Write back
   local content = vim.json.encode(data, { indent = 2 })
-  local ok, err = pcall(vim.fn.writefile, {content}, M.lock_file_path)
+  local ok, err = write_atomic(M.lock_file_path, content)
 
   if ok then
     logger.debug('lockfile', 'Updated lock file')
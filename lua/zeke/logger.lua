@@ -1,5 +1,6 @@
 -- Logger module for Zeke.nvim
--- Provides structured logging with levels and configurable output
+-- Provides structured logging with levels and configurable output.
+-- The log file rolls over at max_size into <file>.1 ... <file>.<max_files>.
 local M = {}
 
 -- Log levels
@@ -16,19 +17,54 @@ M.state = {
   prefix = "[Zeke]",
   show_timestamp = false,
   file = nil,
+  max_size = 1024 * 1024,
+  max_files = 3,
+  cli_level = nil,  -- Level passed to the zeke CLI (default: same as level)
   callbacks = {},
 }
 
+-- Recent warnings and errors, kept regardless of the file setting
+M.recent = {}
+local max_recent = 50
+
+local function remember(level, context, message)
+  if level < M.levels.WARN then
+    return
+  end
+  table.insert(M.recent, {
+    time = os.time(),
+    level = M.level_name(level),
+    context = context,
+    message = message,
+  })
+  if #M.recent > max_recent then
+    table.remove(M.recent, 1)
+  end
+end
+
+local function to_level(level)
+  if type(level) == "string" then
+    return M.levels[level:upper()] or M.levels.INFO
+  end
+  return level
+end
+
+-- Level as last configured, and the one set at runtime (nil: none). The
+-- runtime level survives config reloads until the configured level changes
+local configured_level = M.state.level
+local runtime_level = nil
+
 -- Setup logger configuration
 function M.setup(opts)
   opts = opts or {}
 
   if opts.level then
-    if type(opts.level) == "string" then
-      M.state.level = M.levels[opts.level:upper()] or M.levels.INFO
-    else
-      M.state.level = opts.level
+    local level = to_level(opts.level)
+    if level ~= configured_level then
+      configured_level = level
+      runtime_level = nil
     end
+    M.state.level = runtime_level or configured_level
   end
 
   if opts.prefix ~= nil then
@@ -43,6 +79,18 @@ function M.setup(opts)
     M.state.file = vim.fn.expand(opts.file)
   end
 
+  if opts.max_size then
+    M.state.max_size = opts.max_size
+  end
+
+  if opts.max_files then
+    M.state.max_files = opts.max_files
+  end
+
+  if opts.cli_level ~= nil then
+    M.state.cli_level = opts.cli_level
+  end
+
   if opts.callbacks then
     M.state.callbacks = opts.callbacks
   end
@@ -78,12 +126,29 @@ local function format_message(level, context, message)
   return table.concat(parts, " ")
 end
 
+-- Shift zeke.log -> zeke.log.1 -> ... once the file reaches max_size
+local function rotate(path)
+  local stat = vim.loop.fs_stat(path)
+  if not stat or stat.size < M.state.max_size then
+    return
+  end
+
+  os.remove(path .. "." .. M.state.max_files)
+  for i = M.state.max_files - 1, 1, -1 do
+    os.rename(path .. "." .. i, path .. "." .. (i + 1))
+  end
+  os.rename(path, path .. ".1")
+end
+
 -- Write to file if configured
 local function write_to_file(message)
   if not M.state.file then
     return
   end
 
+  vim.fn.mkdir(vim.fn.fnamemodify(M.state.file, ":h"), "p")
+  rotate(M.state.file)
+
   local file = io.open(M.state.file, "a")
   if file then
     file:write(message .. "\n")
@@ -98,6 +163,8 @@ local function log(level, context, message)
     return
   end
 
+  remember(level, context, message)
+
   -- Format the message
   local formatted = format_message(level, context, message)
 
@@ -123,6 +190,15 @@ local function log(level, context, message)
   vim.notify(formatted, vim_levels[level])
 end
 
+-- Write to the log file only (no notification), e.g. for CLI stderr
+function M.record(level, context, message)
+  remember(level, context, message)
+  if level < M.state.level then
+    return
+  end
+  write_to_file(format_message(level, context, message))
+end
+
 -- Public logging methods
 function M.debug(context, message)
   log(M.levels.DEBUG, context, message)
@@ -140,13 +216,10 @@ function M.error(context, message)
   log(M.levels.ERROR, context, message)
 end
 
--- Set log level dynamically
+-- Set log level dynamically (kept across config reloads)
 function M.set_level(level)
-  if type(level) == "string" then
-    M.state.level = M.levels[level:upper()] or M.levels.INFO
-  else
-    M.state.level = level
-  end
+  runtime_level = to_level(level)
+  M.state.level = runtime_level
 end
 
 -- Get current log level
@@ -154,6 +227,72 @@ function M.get_level()
   return M.state.level
 end
 
+-- Name of a level (default: the current one), e.g. "INFO"
+function M.level_name(level)
+  for name, value in pairs(M.levels) do
+    if value == (level or M.state.level) then
+      return name
+    end
+  end
+  return "INFO"
+end
+
+-- Verbosity for the zeke CLI's own log
+function M.cli_level()
+  if M.state.cli_level then
+    return M.state.cli_level:lower()
+  end
+  return M.level_name():lower()
+end
+
+-- Path of the log file (nil when file logging is off)
+function M.path()
+  return M.state.file
+end
+
+-- Last n lines of the log file
+function M.tail(n)
+  n = n or 50
+  if not M.state.file or vim.fn.filereadable(M.state.file) == 0 then
+    return {}
+  end
+  return vim.fn.readfile(M.state.file, "", -n)
+end
+
+-- Recent warnings and errors, newest last: { {time, level, context, message}, ... }
+function M.recent_errors(n)
+  return vim.list_slice(M.recent, math.max(1, #M.recent - (n or max_recent) + 1))
+end
+
+-- Show recent errors and the log tail in a split
+function M.show(n)
+  local lines = { "# Recent warnings and errors", "" }
+  local recent = M.recent_errors()
+  if #recent == 0 then
+    table.insert(lines, "None this session.")
+  end
+  for _, entry in ipairs(recent) do
+    local text = string.format("%s [%s] [%s] %s", os.date("%H:%M:%S", entry.time), entry.level,
+      entry.context or "-", entry.message)
+    vim.list_extend(lines, vim.split(text, "\n", { plain = true }))
+  end
+
+  table.insert(lines, "")
+  table.insert(lines, "# " .. (M.state.file or "No log file (logger.file is unset)"))
+  table.insert(lines, "")
+  vim.list_extend(lines, M.tail(n or 100))
+
+  local buf = vim.api.nvim_create_buf(false, true)
+  vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+  vim.bo[buf].buftype = "nofile"
+  vim.bo[buf].bufhidden = "wipe"
+  vim.bo[buf].filetype = "markdown"
+  vim.bo[buf].modifiable = false
+  vim.cmd("botright split")
+  vim.api.nvim_win_set_buf(0, buf)
+  vim.cmd("normal! G")
+end
+
 -- Clear log file
 function M.clear_log_file()
   if M.state.file then
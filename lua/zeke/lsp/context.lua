@@ -6,6 +6,10 @@ local M = {}
 local api = vim.api
 local cli = require('zeke.cli')
 local logger = require('zeke.logger')
+local utils = require('zeke.utils')
+local config = require('zeke.config')
+local privacy = require('zeke.privacy')
+local snapshots = require('zeke.context.snapshots')
 
 -- Get diagnostics for current buffer or line
 function M.get_diagnostics(bufnr, line_num)
@@ -90,6 +94,12 @@ end
 function M.get_symbols(bufnr)
   bufnr = bufnr or api.nvim_get_current_buf()
 
+  -- Symbols gathered in the background since the last change
+  local cached = snapshots.symbols(bufnr)
+  if cached then
+    return cached
+  end
+
   local params = { textDocument = vim.lsp.util.make_text_document_params(bufnr) }
   local result = vim.lsp.buf_request_sync(bufnr, 'textDocument/documentSymbol', params, 1000)
 
@@ -99,6 +109,7 @@ function M.get_symbols(bufnr)
 
   for _, res in pairs(result) do
     if res.result then
+      snapshots.store_symbols(bufnr, res.result)
       return res.result
     end
   end
@@ -178,14 +189,17 @@ function M.get_full_context(opts)
     end
   end
 
+  -- Privacy mode keeps sensitive files out of the prompt
+  local readable = privacy.check_buffer(bufnr)
+
   -- Get buffer content
-  if opts.include_buffer then
+  if opts.include_buffer and readable then
     local lines = api.nvim_buf_get_lines(bufnr, 0, -1, false)
     context.content = table.concat(lines, "\n")
   end
 
   -- Get surrounding context (lines around cursor)
-  if opts.context_lines then
+  if opts.context_lines and readable then
     local start_line = math.max(0, line_num - opts.context_lines - 1)
     local end_line = math.min(api.nvim_buf_line_count(bufnr), line_num + opts.context_lines)
     local lines = api.nvim_buf_get_lines(bufnr, start_line, end_line, false)
@@ -232,16 +246,12 @@ function M.format_context_prompt(context)
 
   if context.surrounding then
     table.insert(parts, "\nCode Context:")
-    table.insert(parts, "```" .. (context.filetype or ""))
-    table.insert(parts, context.surrounding)
-    table.insert(parts, "```")
+    table.insert(parts, utils.format_code_block(context.surrounding, context.filetype))
   end
 
   if context.content then
     table.insert(parts, "\nFull Buffer:")
-    table.insert(parts, "```" .. (context.filetype or ""))
-    table.insert(parts, context.content)
-    table.insert(parts, "```")
+    table.insert(parts, utils.format_code_block(context.content, context.filetype))
   end
 
   return table.concat(parts, "\n")
@@ -269,8 +279,10 @@ function M.fix_diagnostic_at_cursor()
   })
 
   -- Build fix prompt
-  local prompt = M.format_context_prompt(context)
-  prompt = prompt .. "\n\nPlease provide a fix for the diagnostic issue on this line. Return ONLY the corrected code for the affected area."
+  local prompt = config.render_prompt('fix_diagnostic', {
+    code = M.format_context_prompt(context),
+    filetype = context.filetype,
+  })
 
   -- Request fix from Zeke
   vim.notify("Requesting fix from Zeke...", vim.log.levels.INFO)
@@ -288,7 +300,7 @@ function M.fix_diagnostic_at_cursor()
           local current_lines = api.nvim_buf_get_lines(bufnr, 0, -1, false)
 
           -- Extract code from response
-          local code = full_response:match('```[%w]*\n(.-)```') or full_response
+          local code = utils.first_code_block(full_response, context.filetype) or full_response
 
           -- Show diff and ask user to apply
           vim.notify("Fix generated. Review the suggested changes.", vim.log.levels.INFO)
@@ -301,7 +313,8 @@ function M.fix_diagnostic_at_cursor()
           vim.notify("Failed to generate fix", vim.log.levels.ERROR)
         end
       end)
-    end
+    end,
+    { task = 'edit' }
   )
 end
 
@@ -323,8 +336,10 @@ function M.explain_diagnostic_at_cursor()
     context_lines = 5,
   })
 
-  local prompt = M.format_context_prompt(context)
-  prompt = prompt .. "\n\nPlease explain what this diagnostic means and how to fix it."
+  local prompt = config.render_prompt('explain_diagnostic', {
+    code = M.format_context_prompt(context),
+    filetype = context.filetype,
+  })
 
   -- Show explanation in floating window
   vim.notify("Asking Zeke to explain...", vim.log.levels.INFO)
@@ -362,7 +377,8 @@ function M.explain_diagnostic_at_cursor()
           api.nvim_buf_set_keymap(buf, 'n', '<Esc>', '<cmd>close<cr>', { silent = true })
         end
       end)
-    end
+    end,
+    { task = 'analyze' }
   )
 end
 
@@ -0,0 +1,326 @@
+--[[
+  MCP Server
+
+  Exposes the live Neovim session to external agents (Claude Desktop, the Zeke
+  CLI, ...) through the Model Context Protocol. Agents launch the stdio shim
+  (lua/zeke/mcp/stdio.lua), which forwards each JSON-RPC message here over
+  Neovim's RPC socket.
+
+  Tools:
+  - list_buffers, read_buffer, list_diagnostics (read-only; files outside
+    the project that aren't open ask for approval)
+  - apply_edit, run_command (asks for approval in the editor, showing the
+    edit as a diff; "allow for session" covers MCP requests only)
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local tools = require('zeke.context.tools')
+local approval = require('zeke.approval')
+local audit = require('zeke.audit')
+local privacy = require('zeke.privacy')
+
+M.protocol_version = "2024-11-05"
+
+M.config = {
+  allow_edits = true,
+  allow_commands = false,
+  require_approval = true,
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+-- Path of the stdio shim agents should launch
+function M.stdio_script()
+  local source = debug.getinfo(1, 'S').source:sub(2)
+  return vim.fn.fnamemodify(source, ':p:h') .. '/stdio.lua'
+end
+
+-- Server entry for an MCP client config (e.g. claude_desktop_config.json)
+function M.client_config()
+  return {
+    command = "nvim",
+    args = { "--headless", "-l", M.stdio_script() },
+    env = { ZEKE_NVIM_SERVER = vim.v.servername },
+  }
+end
+
+-- Blocking prompt (the shim waits on the RPC reply); honors session
+-- approvals, kept apart from the ones given to Zeke's own proposals
+local function approve(kind, message)
+  kind = "mcp:" .. kind
+  if not M.config.require_approval or approval.is_allowed(kind) then
+    return true
+  end
+
+  local choice = vim.fn.confirm(message, "&Allow once\nAllow for &session\n&Deny", 3)
+  if choice == 2 then
+    approval.allow_session(kind)
+  end
+  return choice == 1 or choice == 2
+end
+
+-- Buffer whose name is exactly `full` (bufnr() would treat it as a pattern)
+local function buffer_named(full)
+  for _, bufnr in ipairs(vim.api.nvim_list_bufs()) do
+    if vim.api.nvim_buf_get_name(bufnr) == full then
+      return bufnr
+    end
+  end
+  return -1
+end
+
+-- Unified diff of an edit for the approval prompt, cut at max_lines
+local function edit_preview(old_lines, new_lines, max_lines)
+  local old = #old_lines > 0 and table.concat(old_lines, '\n') .. '\n' or ''
+  local new = #new_lines > 0 and table.concat(new_lines, '\n') .. '\n' or ''
+  local lines = vim.split(vim.diff(old, new, { result_type = 'unified' }) or '', '\n', { plain = true, trimempty = true })
+  if #lines > max_lines then
+    local more = #lines - max_lines
+    lines = vim.list_slice(lines, 1, max_lines)
+    table.insert(lines, string.format('... (%d more lines)', more))
+  end
+  return table.concat(lines, '\n')
+end
+
+-- Loaded buffers and files under the working directory are readable;
+-- anything else needs approval
+local function find_buffer(path)
+  if not path or path == "" then
+    return vim.api.nvim_get_current_buf()
+  end
+
+  local full = vim.fn.fnamemodify(path, ':p')
+  local bufnr = buffer_named(full)
+  if bufnr ~= -1 and vim.api.nvim_buf_is_loaded(bufnr) then
+    return bufnr
+  end
+
+  local root = vim.fn.getcwd()
+  local real = vim.fn.resolve(full)
+  if real:sub(1, #root + 1) ~= root .. '/' and not approve("read", "MCP agent wants to read " .. full) then
+    error("Reading files outside the project denied by user")
+  end
+  if bufnr == -1 then
+    bufnr = vim.fn.bufadd(full)
+  end
+  vim.fn.bufload(bufnr)
+  return bufnr
+end
+
+-- Tool definitions: description, input schema and handler returning text
+M.tools = {
+  list_buffers = {
+    description = "List files open in the editor",
+    schema = { type = "object", properties = vim.empty_dict() },
+    handler = function()
+      return vim.json.encode(tools.get_open_editors())
+    end,
+  },
+
+  read_buffer = {
+    description = "Read a buffer's current (possibly unsaved) content",
+    schema = {
+      type = "object",
+      properties = {
+        path = { type = "string", description = "File path (defaults to the current buffer)" },
+      },
+    },
+    handler = function(args)
+      local bufnr = find_buffer(args.path)
+      local readable, refused = privacy.check_buffer(bufnr)
+      if not readable then
+        error(refused)
+      end
+      local lines = vim.api.nvim_buf_get_lines(bufnr, 0, -1, false)
+      return table.concat(lines, '\n')
+    end,
+  },
+
+  list_diagnostics = {
+    description = "List LSP diagnostics for a file",
+    schema = {
+      type = "object",
+      properties = {
+        path = { type = "string", description = "File path (defaults to the current buffer)" },
+      },
+    },
+    handler = function(args)
+      local bufnr = find_buffer(args.path)
+      local readable, refused = privacy.check_buffer(bufnr)
+      if not readable then
+        error(refused)
+      end
+      return vim.json.encode(tools.get_diagnostics(bufnr))
+    end,
+  },
+
+  apply_edit = {
+    description = "Replace a line range in a buffer (1-based, inclusive); requires user approval",
+    schema = {
+      type = "object",
+      properties = {
+        path = { type = "string" },
+        start_line = { type = "integer" },
+        end_line = { type = "integer" },
+        new_text = { type = "string" },
+      },
+      required = { "path", "start_line", "end_line", "new_text" },
+    },
+    handler = function(args)
+      if not M.config.allow_edits then
+        error("Edits are disabled (mcp.allow_edits = false)")
+      end
+      local permitted, refused = privacy.check_action('file_write')
+      if not permitted then
+        error(refused)
+      end
+
+      local bufnr = find_buffer(args.path)
+      local new_lines = vim.split(args.new_text, '\n', { plain = true })
+      local replaced = vim.api.nvim_buf_get_lines(bufnr, args.start_line - 1, args.end_line, false)
+      local message = string.format("MCP agent wants to replace lines %d-%d of %s with %d line(s):\n\n%s",
+        args.start_line, args.end_line, args.path, #new_lines, edit_preview(replaced, new_lines, 40))
+
+      if not approve("modify", message) then
+        error("Edit denied by user")
+      end
+
+      vim.api.nvim_buf_set_lines(bufnr, args.start_line - 1, args.end_line, false, new_lines)
+      audit.record('buffer_edit', {
+        source = 'mcp',
+        provider = 'mcp',
+        path = vim.api.nvim_buf_get_name(bufnr),
+        lines = { args.start_line, args.end_line },
+        before = replaced,
+        after = new_lines,
+      })
+      return string.format("Replaced lines %d-%d", args.start_line, args.end_line)
+    end,
+  },
+
+  run_command = {
+    description = "Run an Ex command and return its output; requires user approval",
+    schema = {
+      type = "object",
+      properties = {
+        command = { type = "string" },
+      },
+      required = { "command" },
+    },
+    handler = function(args)
+      if not M.config.allow_commands then
+        error("Commands are disabled (mcp.allow_commands = false)")
+      end
+      local permitted, refused = privacy.check_action('command')
+      if not permitted then
+        error(refused)
+      end
+
+      if not approve("command", "MCP agent wants to run :" .. args.command) then
+        error("Command denied by user")
+      end
+
+      audit.record('command', { source = 'mcp', provider = 'mcp', command = ':' .. args.command })
+      local result = vim.api.nvim_exec2(args.command, { output = true })
+      return result.output
+    end,
+  },
+}
+
+local function list_tools()
+  local list = {}
+  for name, tool in pairs(M.tools) do
+    table.insert(list, {
+      name = name,
+      description = tool.description,
+      inputSchema = tool.schema,
+    })
+  end
+  table.sort(list, function(a, b) return a.name < b.name end)
+  return { tools = list }
+end
+
+local function call_tool(params)
+  local tool = M.tools[params.name]
+  if not tool then
+    return nil, { code = -32602, message = "Unknown tool: " .. tostring(params.name) }
+  end
+
+  logger.info('mcp', 'Tool call: ' .. params.name)
+  local ok, result = pcall(tool.handler, params.arguments or {})
+
+  local text = tostring(result)
+  if not ok then
+    -- Strip the "file:line: " prefix from error messages
+    text = text:gsub('^.-:%d+: ', '')
+  end
+
+  return {
+    content = { { type = "text", text = text } },
+    isError = not ok,
+  }
+end
+
+local methods = {
+  initialize = function()
+    return {
+      protocolVersion = M.protocol_version,
+      capabilities = { tools = vim.empty_dict() },
+      serverInfo = { name = "zeke.nvim", version = "0.1.0" },
+    }
+  end,
+  ping = function()
+    return vim.empty_dict()
+  end,
+  ["tools/list"] = list_tools,
+  ["tools/call"] = call_tool,
+}
+
+---Handle one JSON-RPC message
+---@param message table Decoded request or notification
+---@return table|nil Response (nil for notifications)
+function M.handle(message)
+  -- Notifications (e.g. notifications/initialized) get no response
+  if message.id == nil then
+    return nil
+  end
+
+  local method = methods[message.method]
+  if not method then
+    return {
+      jsonrpc = "2.0",
+      id = message.id,
+      error = { code = -32601, message = "Method not found: " .. tostring(message.method) },
+    }
+  end
+
+  local result, err = method(message.params or {})
+  if err then
+    return { jsonrpc = "2.0", id = message.id, error = err }
+  end
+
+  return { jsonrpc = "2.0", id = message.id, result = result }
+end
+
+---Handle one raw JSON line from the stdio shim
+---@param line string JSON-RPC message
+---@return string|nil Encoded response
+function M.handle_json(line)
+  local ok, message = pcall(vim.json.decode, line)
+  if not ok or type(message) ~= 'table' then
+    return vim.json.encode({
+      jsonrpc = "2.0",
+      id = vim.NIL,
+      error = { code = -32700, message = "Parse error" },
+    })
+  end
+
+  local response = M.handle(message)
+  return response and vim.json.encode(response) or nil
+end
+
+return M
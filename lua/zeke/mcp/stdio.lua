@@ -0,0 +1,83 @@
+--[[
+  MCP stdio shim
+
+  Run by MCP clients as: nvim --headless -l /path/to/zeke/mcp/stdio.lua
+  Reads newline-delimited JSON-RPC from stdin and forwards each message to the
+  running Neovim's zeke.mcp module, writing responses to stdout.
+
+  The target Neovim is $ZEKE_NVIM_SERVER, $NVIM (inside :terminal), or the
+  most recent live ~/.zeke/ide lock file, preferring the current directory.
+--]]
+
+-- Make the plugin's modules requireable (nvim -l does not load plugins)
+local lua_root = vim.fn.fnamemodify(debug.getinfo(1, "S").source:sub(2), ":p:h:h:h")
+package.path = lua_root .. "/?.lua;" .. lua_root .. "/?/init.lua;" .. package.path
+
+local lockfile = require("zeke.lockfile")
+
+local function find_server()
+  local server = os.getenv("ZEKE_NVIM_SERVER") or os.getenv("NVIM")
+  if server and server ~= "" then
+    return server
+  end
+
+  local cwd = vim.fn.getcwd()
+  local best, best_time = nil, -1
+  for _, data in ipairs(lockfile.list()) do
+    if data.alive and data.nvim_server then
+      local time = data.updated_at or data.created_at or 0
+      -- Prefer a session for this project, then the newest one
+      if data.cwd == cwd then
+        time = time + 2 ^ 31
+      end
+      if time > best_time then
+        best, best_time = data.nvim_server, time
+      end
+    end
+  end
+  return best
+end
+
+local server = find_server()
+if not server then
+  io.stderr:write("zeke-mcp: no running Neovim with zeke.nvim found\n")
+  os.exit(1)
+end
+
+local mode = server:match("^[^/\\]+:%d+$") and "tcp" or "pipe"
+local ok, chan = pcall(vim.fn.sockconnect, mode, server, { rpc = true })
+if not ok or chan == 0 then
+  io.stderr:write("zeke-mcp: cannot connect to " .. server .. "\n")
+  os.exit(1)
+end
+
+while true do
+  local line = io.read("*l")
+  if not line then
+    break
+  end
+
+  if line ~= "" then
+    local call_ok, reply = pcall(vim.fn.rpcrequest, chan, "nvim_exec_lua",
+      'return require("zeke.mcp").handle_json(...)', { line })
+
+    if not call_ok then
+      local decoded_ok, message = pcall(vim.json.decode, line)
+      local id = decoded_ok and type(message) == "table" and message.id or nil
+      if id ~= nil then
+        reply = vim.json.encode({
+          jsonrpc = "2.0",
+          id = id,
+          error = { code = -32603, message = tostring(reply) },
+        })
+      else
+        reply = nil
+      end
+    end
+
+    if type(reply) == "string" then
+      io.write(reply, "\n")
+      io.flush()
+    end
+  end
+end
@@ -7,6 +7,7 @@
   - @selection - Include visual selection
   - @diag - Include current diagnostics
   - @git:diff - Include git diff
+  - @watch:name - Include recent output of a watcher task
 
   Usage:
     local mentions = require('zeke.mentions')
@@ -20,6 +21,9 @@ local M = {}
 local api = vim.api
 local lsp_context = require('zeke.lsp.context')
 local logger = require('zeke.logger')
+local utils = require('zeke.utils')
+local privacy = require('zeke.privacy')
+local snapshots = require('zeke.context.snapshots')
 
 -- Pattern matching for @-mentions
 M.patterns = {
@@ -29,6 +33,7 @@ M.patterns = {
   diag = "@diag",                     -- @diag (diagnostics)
   git_diff = "@git:diff",             -- @git:diff
   git_status = "@git:status",         -- @git:status
+  watch = "@watch:([^%s]+)",         -- @watch:cargo-watch
 }
 
 ---Parse text for @-mentions
@@ -107,6 +112,17 @@ function M.parse(text)
     })
   end
 
+  -- Parse @watch:name mentions
+  for pos, match in text:gmatch("()@watch:([^%s]+)") do
+    table.insert(mentions, {
+      type = "watch",
+      value = match,
+      start_pos = pos,
+      end_pos = pos + #match + 7, -- "@watch:" = 7 chars
+      raw = "@watch:" .. match,
+    })
+  end
+
   -- Sort by position
   table.sort(mentions, function(a, b)
     return a.start_pos < b.start_pos
@@ -242,9 +258,7 @@ end
 ---Resolve @git:diff mention to git diff output
 ---@return table {content, success}
 function M.resolve_git_diff()
-  -- Run git diff
-  local output = vim.fn.system('git diff')
-  local success = vim.v.shell_error == 0
+  local output, success = snapshots.git('git_diff')
 
   if not success then
     return {
@@ -267,8 +281,7 @@ end
 ---Resolve @git:status mention to git status output
 ---@return table {content, success}
 function M.resolve_git_status()
-  local output = vim.fn.system('git status --short')
-  local success = vim.v.shell_error == 0
+  local output, success = snapshots.git('git_status')
 
   if not success then
     return {
@@ -288,6 +301,18 @@ function M.resolve_git_status()
   }
 end
 
+---Resolve @watch mention to the watcher's recent output
+---@param name string Watcher name
+---@return table {content, name}
+function M.resolve_watch(name)
+  local lines = require('zeke.terminal').watcher_tail(name)
+  if not lines then
+    logger.warn('mentions', 'Watcher not found: ' .. name)
+    return { content = nil, name = name }
+  end
+  return { content = table.concat(lines, "\n"), name = name }
+end
+
 ---Resolve all mentions in a list
 ---@param mentions table List of mention objects from parse()
 ---@return table List of resolved contexts {type, raw, resolved_content, metadata}
@@ -309,11 +334,21 @@ function M.resolve(mentions)
       result = M.resolve_git_diff()
     elseif mention.type == "git_status" then
       result = M.resolve_git_status()
+    elseif mention.type == "watch" then
+      result = M.resolve_watch(mention.value)
     else
       logger.warn('mentions', 'Unknown mention type: ' .. mention.type)
       goto continue
     end
 
+    -- Privacy mode keeps sensitive files out of the prompt
+    if mention.type == "file" or mention.type == "buffer" or mention.type == "selection" then
+      local readable, refused = privacy.check_path(result.path or result.name or api.nvim_buf_get_name(0))
+      if not readable then
+        result = { error = refused }
+      end
+    end
+
     table.insert(resolved, {
       type = mention.type,
       raw = mention.raw,
@@ -341,21 +376,9 @@ function M.format_context(resolved)
     if ctx.content then
       local header = string.format("\n[%s: %s]", ctx.type:upper(), ctx.raw)
       table.insert(parts, header)
-      table.insert(parts, "\n```")
-
-      -- Add filetype for syntax highlighting
-      if ctx.metadata and ctx.metadata.filetype then
-        table.insert(parts, ctx.metadata.filetype)
-      elseif ctx.metadata and ctx.metadata.path then
-        -- Guess filetype from extension
-        local ext = ctx.metadata.path:match("%.([^.]+)$")
-        if ext then
-          table.insert(parts, ext)
-        end
-      end
-
-      table.insert(parts, "\n" .. ctx.content)
-      table.insert(parts, "\n```\n")
+      -- Tag the fence for syntax highlighting
+      local filetype = ctx.metadata and (ctx.metadata.filetype or utils.filetype_of(ctx.metadata.path))
+      table.insert(parts, "\n" .. utils.format_code_block(ctx.content, filetype) .. "\n")
     end
   end
 
@@ -410,6 +433,9 @@ function M.get_chips(mentions)
     elseif mention.type == "git_status" then
       icon = "📊"
       label = "Git Status"
+    elseif mention.type == "watch" then
+      icon = "👀"
+      label = "Watch: " .. mention.value
     end
 
     table.insert(chips, {
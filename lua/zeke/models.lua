@@ -12,6 +12,7 @@
 local M = {}
 local cli = require('zeke.cli')
 local logger = require('zeke.logger')
+local warmup = require('zeke.warmup')
 
 -- Model registry with metadata
 M.models = {
@@ -315,6 +316,7 @@ function M.cycle_next()
 
   -- Set the model via CLI
   cli.model_set(model.id)
+  warmup.model_selected(model.id)
 
   return model
 end
@@ -330,12 +332,15 @@ function M.cycle_prev()
   logger.info("models", "Cycling to: " .. model.name)
 
   cli.model_set(model.id)
+  warmup.model_selected(model.id)
 
   return model
 end
 
 -- Set current model by ID
-function M.set_model(id)
+-- opts.persist = false only updates the plugin's selection (no CLI call)
+function M.set_model(id, opts)
+  opts = opts or {}
   local model = M.get_model(id)
   if not model then
     logger.error("models", "Model not found: " .. id)
@@ -351,11 +356,66 @@ function M.set_model(id)
   end
 
   logger.info("models", "Setting model: " .. model.name)
-  cli.model_set(id)
+  if opts.persist ~= false then
+    cli.model_set(id)
+  end
+  warmup.model_selected(id)
 
   return model
 end
 
+-- Resolve an ID or alias to a concrete model ID and its provider.
+-- "provider/model" targets (e.g. "groq/llama-3.1-70b") name the provider explicitly.
+function M.resolve(id)
+  local seen = {}
+  local model = M.get_model(id)
+
+  while model and model.source == "alias" and model.resolves_to do
+    if seen[model.id] then
+      logger.warn("models", "Alias cycle detected at: " .. model.id)
+      break
+    end
+    seen[model.id] = true
+    id = model.resolves_to
+    model = M.get_model(id)
+  end
+
+  local provider, name = id:match("^([%w_%-]+)/(.+)$")
+  if provider then
+    return name, provider
+  end
+
+  if model and model.provider ~= "auto" then
+    return id, model.provider
+  end
+
+  return id, nil
+end
+
+-- Register user-configured aliases (config.aliases), overriding built-ins
+function M.apply_aliases(aliases)
+  for alias, target in pairs(aliases or {}) do
+    local existing = M.get_model(alias)
+
+    if existing and existing.source ~= "alias" then
+      logger.warn("models", string.format("Alias '%s' shadows a model ID, ignoring", alias))
+    elseif existing then
+      existing.resolves_to = target
+      existing.description = "Alias for " .. target
+    else
+      table.insert(M.models, {
+        id = alias,
+        name = alias,
+        provider = "auto",
+        source = "alias",
+        icon = "󰚩",
+        description = "Alias for " .. target,
+        resolves_to = target,
+      })
+    end
+  end
+end
+
 -- Get current model
 function M.get_current()
   return M.models[M.current_index]
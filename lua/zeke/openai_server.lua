@@ -0,0 +1,387 @@
+--[[
+  OpenAI-compatible Local Server
+
+  Serves zeke's configured providers on localhost with the OpenAI chat API,
+  so curl scripts and other editors reuse the same credentials, model
+  routing, failover and cost tracking:
+
+    server = { enabled = true, port = 8790 }
+
+    curl http://127.0.0.1:8790/v1/chat/completions -H 'Content-Type: application/json' \
+      -d '{"model": "qwen2.5-coder:7b", "messages": [{"role": "user", "content": "hi"}]}'
+
+  Endpoints: POST /v1/chat/completions (with "stream": true for SSE) and
+  GET /v1/models. "model" may be any model ID or alias zeke knows, or
+  "zeke" for the current model. Requests run through the CLI like chat
+  messages, so privacy mode and secret scanning apply. Set token to require
+  "Authorization: Bearer <token>". Without a token, requests from browsers
+  (an Origin header) or for another Host are refused, so web pages can't
+  reach the server through the browser or DNS rebinding.
+--]]
+
+local M = {}
+
+local cli = require('zeke.cli')
+local logger = require('zeke.logger')
+local tokens = require('zeke.tokens')
+
+M.config = {
+  enabled = false,
+  host = '127.0.0.1',
+  port = 8790,
+  token = nil,
+  max_body_kb = 4096,
+}
+
+M.server = nil
+M.stats = { requests = 0, errors = 0 }
+
+local reasons = {
+  [200] = 'OK', [400] = 'Bad Request', [401] = 'Unauthorized', [403] = 'Forbidden', [404] = 'Not Found',
+  [405] = 'Method Not Allowed', [413] = 'Payload Too Large', [415] = 'Unsupported Media Type',
+  [502] = 'Bad Gateway',
+}
+
+local function close(client)
+  if not client:is_closing() then
+    client:shutdown(function()
+      client:close()
+    end)
+  end
+end
+
+local function head(status, content_type, length)
+  local lines = {
+    string.format('HTTP/1.1 %d %s', status, reasons[status] or ''),
+    'Content-Type: ' .. content_type,
+    'Cache-Control: no-cache',
+    'Connection: close',
+  }
+  if length then
+    table.insert(lines, 'Content-Length: ' .. length)
+  end
+  return table.concat(lines, '\r\n') .. '\r\n\r\n'
+end
+
+local function respond(client, status, body)
+  local json = vim.json.encode(body)
+  client:write(head(status, 'application/json', #json) .. json)
+  close(client)
+end
+
+local function fail(client, status, message, kind)
+  M.stats.errors = M.stats.errors + 1
+  respond(client, status, { error = { message = message, type = kind or 'invalid_request_error' } })
+end
+
+local function content_text(content)
+  if type(content) == 'string' then
+    return content
+  end
+  local parts = {}
+  for _, part in ipairs(type(content) == 'table' and content or {}) do
+    if part.type == 'text' and part.text then
+      table.insert(parts, part.text)
+    end
+  end
+  return table.concat(parts, '\n')
+end
+
+-- The CLI takes one message: a lone user message is sent as is, a
+-- conversation as a role-labelled transcript
+local function to_prompt(messages)
+  if #messages == 1 and messages[1].role == 'user' then
+    return content_text(messages[1].content)
+  end
+  local blocks = {}
+  for _, message in ipairs(messages) do
+    local role = tostring(message.role or 'user')
+    table.insert(blocks, role:sub(1, 1):upper() .. role:sub(2) .. ': ' .. content_text(message.content))
+  end
+  return table.concat(blocks, '\n\n')
+end
+
+local function model_list(client)
+  local models = require('zeke.models')
+  local data = { { id = 'zeke', object = 'model', created = 0, owned_by = 'zeke' } }
+  for _, model in ipairs(models.filter_models({})) do
+    table.insert(data, { id = model.id, object = 'model', created = 0, owned_by = model.provider or 'zeke' })
+  end
+  respond(client, 200, { object = 'list', data = data })
+end
+
+local function chat_completions(client, body)
+  local ok, request = pcall(vim.json.decode, body, { luanil = { object = true, array = true } })
+  if not ok or type(request) ~= 'table' or type(request.messages) ~= 'table' or #request.messages == 0 then
+    fail(client, 400, 'Expected a JSON body with a non-empty "messages" array')
+    return
+  end
+
+  local prompt = to_prompt(request.messages)
+  local model = type(request.model) == 'string' and request.model ~= 'zeke' and request.model or nil
+  local id = string.format('chatcmpl-zeke-%d-%d', os.time(), M.stats.requests)
+  local created = os.time()
+  local opts = {
+    task = 'chat',
+    model = model,
+    stream = request.stream == true,
+    parameters = {
+      temperature = request.temperature,
+      max_tokens = request.max_completion_tokens or request.max_tokens,
+      top_p = request.top_p,
+    },
+  }
+
+  local function chunk(delta, finish_reason)
+    return 'data: ' .. vim.json.encode({
+      id = id, object = 'chat.completion.chunk', created = created, model = request.model or 'zeke',
+      choices = { { index = 0, delta = delta, finish_reason = finish_reason or vim.NIL } },
+    }) .. '\n\n'
+  end
+
+  if not request.stream then
+    local stream_id, answered = nil, false
+    stream_id = cli.stream_chat(prompt, nil, function(response, exit_code)
+      answered = true
+      if exit_code ~= 0 then
+        fail(client, 502, tostring(response), 'provider_error')
+        return
+      end
+      local prompt_tokens, completion_tokens = tokens.count(prompt, model), tokens.count(response, model)
+      respond(client, 200, {
+        id = id, object = 'chat.completion', created = created, model = request.model or 'zeke',
+        choices = { { index = 0, message = { role = 'assistant', content = response }, finish_reason = 'stop' } },
+        usage = { prompt_tokens = prompt_tokens, completion_tokens = completion_tokens,
+          total_tokens = prompt_tokens + completion_tokens },
+      })
+    end, opts)
+
+    -- The client closing the connection before the answer stops generating
+    client:read_start(function(err, data)
+      if err or not data then
+        vim.schedule(function()
+          if stream_id and not answered then
+            cli.cancel_stream(stream_id)
+          end
+        end)
+      end
+    end)
+    return
+  end
+
+  local stream_id
+  local function send(data)
+    client:write(data, function(err)
+      -- The client went away: stop generating
+      if err and stream_id then
+        vim.schedule(function() cli.cancel_stream(stream_id) end)
+      end
+    end)
+  end
+
+  client:write(head(200, 'text/event-stream'))
+  send(chunk({ role = 'assistant', content = '' }))
+  stream_id = cli.stream_chat(prompt, function(text)
+    send(chunk({ content = text }))
+  end, function(response, exit_code)
+    if exit_code ~= 0 then
+      M.stats.errors = M.stats.errors + 1
+      send('data: ' .. vim.json.encode({ error = { message = tostring(response), type = 'provider_error' } }) .. '\n\n')
+    else
+      send(chunk({}, 'stop'))
+    end
+    send('data: [DONE]\n\n')
+    close(client)
+  end, opts)
+end
+
+-- Compare in constant time, so response timing doesn't reveal the token
+local function token_matches(header)
+  local expected = 'Bearer ' .. M.config.token
+  if type(header) ~= 'string' or #header ~= #expected then
+    return false
+  end
+  local diff = 0
+  for i = 1, #expected do
+    diff = bit.bor(diff, bit.bxor(header:byte(i), expected:byte(i)))
+  end
+  return diff == 0
+end
+
+-- Without a token only local, non-browser clients are served
+local function local_client(request)
+  if request.headers['origin'] then
+    return false
+  end
+  local host = request.headers['host'] or ''
+  local port = tostring(M.config.port)
+  return host == '127.0.0.1:' .. port or host == 'localhost:' .. port or host == '[::1]:' .. port
+end
+
+---Check a request's token, or without one that it's local and not from a browser
+---@param request table {headers = {lowercase name -> value}}
+---@return number|nil status HTTP status when refused
+---@return string|nil message
+---@return string|nil kind OpenAI error type
+function M.authorize(request)
+  if M.config.token and not token_matches(request.headers['authorization']) then
+    return 401, 'Invalid or missing bearer token', 'authentication_error'
+  end
+  if not M.config.token and not local_client(request) then
+    return 403, 'Cross-origin or non-local requests need a token (server.token)', 'permission_error'
+  end
+  return nil
+end
+
+local function handle(client, request)
+  M.stats.requests = M.stats.requests + 1
+  logger.debug('server', request.method .. ' ' .. request.path)
+
+  local status, message, kind = M.authorize(request)
+  if status then
+    fail(client, status, message, kind)
+    return
+  end
+
+  local path = request.path:gsub('%?.*$', '')
+  if path == '/v1/chat/completions' then
+    if request.method ~= 'POST' then
+      fail(client, 405, 'Use POST')
+      return
+    end
+    if not (request.headers['content-type'] or ''):lower():match('^application/json') then
+      fail(client, 415, 'Content-Type must be application/json')
+      return
+    end
+    chat_completions(client, request.body)
+  elseif path == '/v1/models' then
+    model_list(client)
+  else
+    fail(client, 404, 'Unknown endpoint: ' .. path)
+  end
+end
+
+-- Read one request (headers plus Content-Length body) from a connection
+local function accept(client)
+  local buffer, request = '', nil
+  client:read_start(function(err, data)
+    if err or not data then
+      close(client)
+      return
+    end
+    buffer = buffer .. data
+
+    if not request then
+      local header_end = buffer:find('\r\n\r\n', 1, true)
+      if not header_end then
+        if #buffer > 64 * 1024 then
+          client:read_stop()
+          vim.schedule(function() fail(client, 413, 'Headers too large') end)
+        end
+        return
+      end
+      local lines = vim.split(buffer:sub(1, header_end - 1), '\r\n', { plain = true })
+      local method, path = lines[1]:match('^(%u+)%s+(%S+)')
+      request = { method = method or '', path = path or '/', headers = {} }
+      for i = 2, #lines do
+        local name, value = lines[i]:match('^([^:]+):%s*(.*)$')
+        if name then
+          request.headers[name:lower()] = value
+        end
+      end
+      request.length = tonumber(request.headers['content-length']) or 0
+      buffer = buffer:sub(header_end + 4)
+      if request.length > M.config.max_body_kb * 1024 then
+        client:read_stop()
+        vim.schedule(function() fail(client, 413, 'Request body too large') end)
+        return
+      end
+    end
+
+    if #buffer >= request.length then
+      client:read_stop()
+      request.body = buffer:sub(1, request.length)
+      vim.schedule(function() handle(client, request) end)
+    end
+  end)
+end
+
+---Start listening
+---@return boolean ok
+---@return string|nil error
+function M.start()
+  if M.server then
+    return true
+  end
+  if not M.config.token and M.config.host ~= '127.0.0.1' and M.config.host ~= 'localhost' and M.config.host ~= '::1' then
+    logger.warn('server', 'Listening on ' .. M.config.host .. ' without a token: anyone who can reach it can use your providers')
+  end
+
+  local server = vim.loop.new_tcp()
+  local ok, err = server:bind(M.config.host, M.config.port)
+  if ok then
+    ok, err = server:listen(64, function(listen_err)
+      if listen_err then
+        return
+      end
+      local client = vim.loop.new_tcp()
+      if server:accept(client) then
+        accept(client)
+      else
+        client:close()
+      end
+    end)
+  end
+  if ok ~= 0 then
+    server:close()
+    logger.error('server', string.format('Could not listen on %s:%d: %s', M.config.host, M.config.port, tostring(err)))
+    return false, tostring(err)
+  end
+
+  M.server = server
+  logger.info('server', string.format('OpenAI-compatible server on http://%s:%d/v1', M.config.host, M.config.port))
+  return true
+end
+
+---Stop listening
+function M.stop()
+  if M.server then
+    M.server:close()
+    M.server = nil
+    logger.info('server', 'Server stopped')
+  end
+end
+
+---@return table {running, url, requests, errors}
+function M.status()
+  return {
+    running = M.server ~= nil,
+    url = string.format('http://%s:%d/v1', M.config.host, M.config.port),
+    requests = M.stats.requests,
+    errors = M.stats.errors,
+  }
+end
+
+function M.setup(opts)
+  local previous = vim.deepcopy(M.config)
+  M.config = vim.tbl_extend('force', M.config, opts or {})
+
+  -- Rebind when the address changes
+  if M.server and (previous.host ~= M.config.host or previous.port ~= M.config.port) then
+    M.stop()
+  end
+  if M.config.enabled then
+    M.start()
+  elseif previous.enabled then
+    M.stop()
+  end
+
+  vim.api.nvim_create_autocmd('VimLeavePre', {
+    group = vim.api.nvim_create_augroup('ZekeOpenAIServer', { clear = true }),
+    callback = function()
+      M.stop()
+    end,
+  })
+end
+
+return M
@@ -0,0 +1,182 @@
+--[[
+  OpenTelemetry Export
+
+  Sends a span per CLI request or stream to an OTLP/HTTP collector (JSON
+  encoding), so AI usage shows up next to the rest of a self-hosted
+  observability stack. Spans are batched and posted with curl in the
+  background; attributes follow the gen_ai semantic conventions:
+
+    otlp = {
+      enabled = true,
+      endpoint = "http://localhost:4318/v1/traces",
+      headers = { ["Authorization"] = "Bearer ..." },
+    }
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local errors = require('zeke.errors')
+
+M.config = {
+  enabled = false,
+  endpoint = 'http://localhost:4318/v1/traces',
+  headers = {},
+  service_name = 'zeke.nvim',
+  flush_interval_ms = 5000,
+  max_batch = 50,
+}
+
+local pending = {}
+local timer = nil
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+
+  -- Send what's left before Neovim exits (background jobs die with it)
+  vim.api.nvim_create_autocmd('VimLeavePre', {
+    group = vim.api.nvim_create_augroup('ZekeOtlp', { clear = true }),
+    callback = function()
+      M.flush({ sync = true })
+    end,
+  })
+end
+
+local function random_hex(bytes)
+  local out = {}
+  for i = 1, bytes do
+    out[i] = string.format('%02x', math.random(0, 255))
+  end
+  return table.concat(out)
+end
+
+-- Nanoseconds since the epoch as a decimal string (too large for a double)
+local function ns_string(sec, usec)
+  return string.format('%d%06d000', sec, math.floor(usec))
+end
+
+local function attribute(key, value)
+  local typed
+  if type(value) == 'number' then
+    typed = value == math.floor(value) and { intValue = tostring(value) } or { doubleValue = value }
+  elseif type(value) == 'boolean' then
+    typed = { boolValue = value }
+  else
+    typed = { stringValue = tostring(value) }
+  end
+  return { key = key, value = typed }
+end
+
+---Post the pending spans
+---@param opts table|nil {sync = true to wait for the upload}
+function M.flush(opts)
+  if #pending == 0 then
+    return
+  end
+  local spans = pending
+  pending = {}
+
+  local body = vim.json.encode({
+    resourceSpans = { {
+      resource = { attributes = { attribute('service.name', M.config.service_name) } },
+      scopeSpans = { {
+        scope = { name = 'zeke.nvim' },
+        spans = spans,
+      } },
+    } },
+  })
+
+  local cmd = { 'curl', '-sS', '--max-time', '5', '-o', '/dev/null', '-w', '%{http_code}', '-X', 'POST',
+    '-H', 'Content-Type: application/json', '--data-binary', '@-' }
+  for name, value in pairs(M.config.headers or {}) do
+    vim.list_extend(cmd, { '-H', name .. ': ' .. value })
+  end
+  table.insert(cmd, M.config.endpoint)
+
+  if opts and opts.sync then
+    vim.fn.system(cmd, body)
+    return
+  end
+
+  local job = vim.fn.jobstart(cmd, {
+    stdout_buffered = true,
+    on_stdout = function(_, data)
+      local status = tonumber(data and data[1] or '')
+      if not status or status >= 300 then
+        logger.record(logger.levels.WARN, 'otlp', string.format('Export of %d spans failed (HTTP %s)',
+          #spans, data and data[1] or '?'))
+      end
+    end,
+  })
+  if job > 0 then
+    vim.fn.chansend(job, body)
+    vim.fn.chanclose(job, 'stdin')
+  else
+    logger.record(logger.levels.WARN, 'otlp', 'Could not start curl for span export')
+  end
+end
+
+local function schedule_flush()
+  if #pending >= M.config.max_batch then
+    M.flush()
+    return
+  end
+  if timer then
+    return
+  end
+  timer = vim.defer_fn(function()
+    timer = nil
+    M.flush()
+  end, M.config.flush_interval_ms)
+end
+
+---Queue a span for a finished request
+---@param entry table {kind, task, provider, model, status, duration_ms, attempts, exit_code, tokens, cost, error}
+function M.export(entry)
+  if not M.config.enabled then
+    return
+  end
+
+  local end_sec, end_usec = vim.loop.gettimeofday()
+  local start_us = end_sec * 1e6 + end_usec - (entry.duration_ms or 0) * 1000
+  local start_sec = math.floor(start_us / 1e6)
+
+  local attributes = {
+    attribute('gen_ai.operation.name', entry.task or 'chat'),
+    attribute('zeke.request.kind', entry.kind or 'request'),
+    attribute('zeke.request.attempts', entry.attempts or 1),
+  }
+  if entry.provider then
+    table.insert(attributes, attribute('gen_ai.system', entry.provider))
+  end
+  if entry.model then
+    table.insert(attributes, attribute('gen_ai.request.model', entry.model))
+  end
+  if entry.tokens then
+    table.insert(attributes, attribute('gen_ai.usage.input_tokens', entry.tokens.prompt))
+    table.insert(attributes, attribute('gen_ai.usage.output_tokens', entry.tokens.response))
+  end
+  if entry.cost and entry.cost > 0 then
+    table.insert(attributes, attribute('zeke.cost.usd', entry.cost))
+  end
+  if entry.exit_code then
+    table.insert(attributes, attribute('process.exit.code', entry.exit_code))
+  end
+  if entry.error then
+    table.insert(attributes, attribute('error.type', errors.category(entry.error)))
+  end
+
+  table.insert(pending, {
+    traceId = random_hex(16),
+    spanId = random_hex(8),
+    name = string.format('zeke %s', entry.task or 'chat'),
+    kind = 3,  -- SPAN_KIND_CLIENT
+    startTimeUnixNano = ns_string(start_sec, start_us - start_sec * 1e6),
+    endTimeUnixNano = ns_string(end_sec, end_usec),
+    attributes = attributes,
+    status = entry.status == 'ok' and { code = 1 } or { code = 2, message = entry.status },
+  })
+  schedule_flush()
+end
+
+return M
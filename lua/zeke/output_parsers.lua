@@ -0,0 +1,255 @@
+--[[
+  Output parsers: task output -> diagnostics
+
+  Turns compiler, linter and test output into structured diagnostics
+  { file, line, col, severity, message, source } for the quickfix list or as
+  context for fix requests. Parsers are pluggable:
+
+    require('zeke.output_parsers').register('mytool', {
+      efm = '%f:%l:%c: %m',        -- a Vim 'errorformat'
+    })
+    register('other', function(lines) return { ... } end)
+--]]
+
+local M = {}
+
+local terminal = require('zeke.terminal')
+
+local severities = {
+  error = 'error', ['fatal error'] = 'error',
+  warning = 'warning', warn = 'warning',
+  note = 'info', info = 'info', help = 'hint', hint = 'hint',
+}
+
+local function severity(word)
+  return severities[(word or ''):lower()] or 'error'
+end
+
+-- Built-in parsers, tried in order by M.parse() when no name is given
+M.order = { 'rust', 'gcc', 'tsc', 'eslint', 'python', 'generic' }
+
+M.parsers = {
+  -- error[E0308]: mismatched types
+  --   --> src/main.rs:4:18
+  -- thread 'tests::adds' panicked at src/lib.rs:10:5:
+  rust = function(lines)
+    local items = {}
+    local header = nil
+    for _, text in ipairs(lines) do
+      local level, message = text:match('^(%a+)%[?[%w]*%]?: (.+)$')
+      local thread, file, lnum, col = text:match("^thread '(.-)' panicked at (.-):(%d+):(%d+)")
+      if thread then
+        table.insert(items, {
+          file = file, line = tonumber(lnum), col = tonumber(col),
+          severity = 'error', message = string.format("'%s' panicked", thread),
+        })
+      elseif level == 'error' or level == 'warning' then
+        header = { severity = severity(level), message = message }
+      elseif header then
+        local file, lnum, col = text:match('^%s*%-%-> (.-):(%d+):(%d+)')
+        if file then
+          table.insert(items, {
+            file = file, line = tonumber(lnum), col = tonumber(col),
+            severity = header.severity, message = header.message,
+          })
+          header = nil
+        end
+      end
+    end
+    return items
+  end,
+
+  -- src/main.c:12:5: error: expected ';'
+  gcc = function(lines)
+    local items = {}
+    for _, text in ipairs(lines) do
+      local file, lnum, col, level, message = text:match('^([^:%s]+):(%d+):(%d+): (%a[%a ]-): (.+)$')
+      if file and severities[level] then
+        table.insert(items, {
+          file = file, line = tonumber(lnum), col = tonumber(col),
+          severity = severity(level), message = message,
+        })
+      end
+    end
+    return items
+  end,
+
+  -- src/app.ts(10,5): error TS2322: Type 'string' is not assignable ...
+  tsc = function(lines)
+    local items = {}
+    for _, text in ipairs(lines) do
+      local file, lnum, col, level, message = text:match('^(.-)%((%d+),(%d+)%): (%a+) (.+)$')
+      if file and severities[level] then
+        table.insert(items, {
+          file = file, line = tonumber(lnum), col = tonumber(col),
+          severity = severity(level), message = message,
+        })
+      end
+    end
+    return items
+  end,
+
+  -- ESLint "stylish" output: a file path line, then indented entries
+  --   10:5  error  'x' is not defined  no-undef
+  eslint = function(lines)
+    local items = {}
+    local file = nil
+    for _, text in ipairs(lines) do
+      local lnum, col, level, message = text:match('^%s+(%d+):(%d+)%s+(%a+)%s+(.-)%s*$')
+      if file and lnum and severities[level] then
+        table.insert(items, {
+          file = file, line = tonumber(lnum), col = tonumber(col),
+          severity = severity(level), message = message,
+        })
+      elseif text:match('^/.*%.%w+$') or text:match('^%a:\\.*%.%w+$') then
+        file = text
+      end
+    end
+    return items
+  end,
+
+  -- Traceback: the innermost 'File "x", line N' gets the exception message
+  python = function(lines)
+    local items = {}
+    local location = nil
+    for _, text in ipairs(lines) do
+      local file, lnum = text:match('^%s*File "(.-)", line (%d+)')
+      if file then
+        location = { file = file, line = tonumber(lnum) }
+      elseif location then
+        local exception, message = text:match('^([%w_%.]+Error):? ?(.*)$')
+        if not exception then
+          exception, message = text:match('^([%w_%.]+Exception):? ?(.*)$')
+        end
+        if exception then
+          table.insert(items, {
+            file = location.file, line = location.line,
+            severity = 'error', message = exception .. ': ' .. message,
+          })
+          location = nil
+        end
+      end
+    end
+    return items
+  end,
+
+  -- file.ext:line[:col]: message (go, eslint -f unix, many others)
+  generic = function(lines)
+    local items = {}
+    for _, text in ipairs(lines) do
+      local file, lnum, col, message = text:match('^([^:%s]+%.%w+):(%d+):(%d*):? ?(.+)$')
+      if file then
+        table.insert(items, {
+          file = file, line = tonumber(lnum), col = tonumber(col),
+          severity = severity(message:match('^(%a+):')), message = message,
+        })
+      end
+    end
+    return items
+  end,
+}
+
+---Register an output parser
+---@param name string
+---@param spec function|table function(lines) -> diagnostics, or { efm = "errorformat" }
+function M.register(name, spec)
+  if type(spec) == 'table' and spec.efm then
+    local efm = spec.efm
+    local kinds = { E = 'error', W = 'warning', I = 'info', N = 'hint' }
+    spec = function(lines)
+      local items = {}
+      local qf = vim.fn.getqflist({ lines = lines, efm = efm }).items or {}
+      for _, entry in ipairs(qf) do
+        if entry.valid == 1 then
+          table.insert(items, {
+            file = entry.bufnr > 0 and vim.api.nvim_buf_get_name(entry.bufnr) or nil,
+            line = entry.lnum, col = entry.col > 0 and entry.col or nil,
+            severity = kinds[entry.type] or 'error', message = entry.text,
+          })
+        end
+      end
+      return items
+    end
+  end
+
+  M.parsers[name] = spec
+  if not vim.tbl_contains(M.order, name) then
+    -- Custom parsers run before the catch-all generic one
+    table.insert(M.order, #M.order, name)
+  end
+end
+
+---Parse output lines into diagnostics
+---@param lines string[]
+---@param name string|nil Parser name (default: every parser, deduplicated)
+---@return table[] { file, line, col, severity, message, source }
+function M.parse(lines, name)
+  local names = name and { name } or M.order
+  local items, seen = {}, {}
+
+  for _, parser_name in ipairs(names) do
+    local parser = M.parsers[parser_name]
+    for _, item in ipairs(parser and parser(lines) or {}) do
+      local key = string.format('%s:%s:%s', item.file, item.line, item.col or '')
+      -- generic also matches the lines specific parsers already handled
+      if item.file and not seen[key] then
+        seen[key] = true
+        item.source = parser_name
+        table.insert(items, item)
+      end
+    end
+  end
+  return items
+end
+
+---Diagnostics from a task's retained output; relative paths resolve against its cwd
+---@param task_id number
+---@param name string|nil Parser name
+---@return table[]|nil
+function M.from_task(task_id, name)
+  local output = terminal.task_output(task_id)
+  if not output then
+    return nil
+  end
+
+  local task = terminal.get_task(task_id) or {}
+  local items = M.parse(output.lines, name)
+  for _, item in ipairs(items) do
+    if task.cwd and not item.file:match('^/') and not item.file:match('^%a:[\\/]') then
+      item.file = task.cwd .. '/' .. item.file
+    end
+  end
+  return items
+end
+
+---Fill the quickfix list with diagnostics
+---@param items table[]
+---@param title string|nil
+function M.to_quickfix(items, title)
+  local types = { error = 'E', warning = 'W', info = 'I', hint = 'N' }
+  local qf = {}
+  for _, item in ipairs(items) do
+    table.insert(qf, {
+      filename = item.file,
+      lnum = item.line or 1,
+      col = item.col or 1,
+      type = types[item.severity] or 'E',
+      text = item.message,
+    })
+  end
+  vim.fn.setqflist({}, ' ', { title = title or 'Zeke', items = qf })
+end
+
+---Diagnostics as plain text for a prompt
+---@param items table[]
+---@return string
+function M.format(items)
+  local lines = {}
+  for _, item in ipairs(items) do
+    table.insert(lines, string.format('%s:%s:%s: %s: %s',
+      item.file, item.line or '?', item.col or '?', item.severity, item.message))
+  end
+  return table.concat(lines, '\n')
+end
+
+return M
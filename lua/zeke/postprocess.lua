@@ -0,0 +1,144 @@
+--[[
+  Response post-processing
+
+  Line-based transform stages applied to CLI output before it reaches
+  callers, configured per task under `postprocess`:
+
+    postprocess = {
+      completion = { "strip_fences" },
+      edit = { "strip_fences", "trim_leading_blank", { "stop", "<|end|>" } },
+    }
+
+  Stages see one line at a time, so they work the same for streamed and
+  complete responses.
+--]]
+
+local M = {}
+
+M.config = {
+  completion = { "strip_fences" },
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_extend('force', M.config, opts or {})
+end
+
+-- Stage constructors: function(args) -> function(line, state) returning the
+-- line to emit (nil to drop it) and true to end the response after it
+M.stages = {
+  -- Drop ``` / ~~~ fence lines, keeping the code between them
+  strip_fences = function()
+    return function(line)
+      if line:match('^%s*```[%w_+%-]*%s*$') or line:match('^%s*~~~[%w_+%-]*%s*$') then
+        return nil
+      end
+      return line
+    end
+  end,
+
+  -- Collapse runs of blank lines into one
+  collapse_blank_lines = function()
+    return function(line, state)
+      local blank = line:match('^%s*$') ~= nil
+      if blank and state.last_blank then
+        return nil
+      end
+      state.last_blank = blank
+      return line
+    end
+  end,
+
+  -- Drop blank lines before the first non-blank one
+  trim_leading_blank = function()
+    return function(line, state)
+      if not state.started and line:match('^%s*$') then
+        return nil
+      end
+      state.started = true
+      return line
+    end
+  end,
+
+  trim_trailing_whitespace = function()
+    return function(line)
+      return (line:gsub('%s+$', ''))
+    end
+  end,
+
+  -- End the response at the first stop sequence (plain text match)
+  stop = function(args)
+    return function(line)
+      for _, sequence in ipairs(args) do
+        local at = line:find(sequence, 1, true)
+        if at then
+          local head = line:sub(1, at - 1)
+          return head ~= '' and head or nil, true
+        end
+      end
+      return line
+    end
+  end,
+}
+
+---Build a processor for a task's pipeline
+---@param task string|nil Task type (chat, edit, completion, ...)
+---@param stages table|false|nil Stage list overriding the configured one
+---@return function|nil process(line) -> line|nil, stop; nil when there are no stages
+function M.pipeline(task, stages)
+  if stages == false then
+    return nil
+  end
+  stages = stages or M.config[task or 'chat']
+  if not stages or #stages == 0 then
+    return nil
+  end
+
+  local steps = {}
+  for _, spec in ipairs(stages) do
+    local name = type(spec) == 'table' and spec[1] or spec
+    local make = M.stages[name]
+    if make then
+      local args = type(spec) == 'table' and vim.list_slice(spec, 2) or {}
+      table.insert(steps, { fn = make(args), state = {} })
+    end
+  end
+
+  return function(line)
+    local stop = false
+    for _, step in ipairs(steps) do
+      local ended
+      line, ended = step.fn(line, step.state)
+      stop = stop or ended == true
+      if line == nil then
+        break
+      end
+    end
+    return line, stop
+  end
+end
+
+---Run a complete response through a task's pipeline
+---@param text string
+---@param task string|nil
+---@param stages table|false|nil
+---@return string
+function M.apply(text, task, stages)
+  local process = M.pipeline(task, stages)
+  if not process then
+    return text
+  end
+
+  local out = {}
+  for _, line in ipairs(vim.split(text, '\n', { plain = true })) do
+    local result, stop = process(line)
+    if result then
+      table.insert(out, result)
+    end
+    if stop then
+      break
+    end
+  end
+  return table.concat(out, '\n')
+end
+
+return M
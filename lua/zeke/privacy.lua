@@ -0,0 +1,153 @@
+--[[
+  Privacy / Offline Mode
+
+  When enabled:
+  - requests may only go to local providers (Ollama, LM Studio, llama.cpp);
+    anything else is refused before the CLI is started
+  - files matching `sensitive_paths` are never read into a prompt
+  - AI-driven file writes and commands are refused outright
+
+  Switch it at runtime with :ZekePrivacy [on|off] or
+  require('zeke').set_privacy(true); changes fire the ZekePrivacyChanged
+  User autocmd and show up in require('zeke').status().privacy. A runtime
+  switch survives config reloads until `privacy.enabled` itself is changed
+  in the config.
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+
+M.config = {
+  enabled = false,
+  local_providers = { 'ollama', 'lmstudio', 'llamacpp', 'whispercpp' },
+  -- Glob patterns matched against the full path and the file name
+  sensitive_paths = {
+    '.env', '.env.*', '*.pem', '*.key', '*.p12', '*.pfx', 'id_rsa*', 'id_ed25519*',
+    '*/.ssh/*', '*/.aws/*', '*/.gnupg/*', '*/secrets/*', '.netrc', '*.kdbx',
+  },
+}
+
+-- `enabled` as last configured, and the runtime switch (nil: none) that
+-- overrides it
+local configured = M.config.enabled
+local runtime = nil
+
+local function emit_changed()
+  vim.api.nvim_exec_autocmds('User', { pattern = 'ZekePrivacyChanged', data = { enabled = M.enabled() }, modeline = false })
+end
+
+function M.setup(opts)
+  opts = opts or {}
+  local enabled = M.config.enabled
+  if opts.enabled ~= nil and opts.enabled ~= configured then
+    configured = opts.enabled
+    runtime = nil
+  end
+  -- Lists replace the defaults instead of merging index by index
+  M.config = vim.tbl_extend('force', M.config, opts)
+  if runtime ~= nil then
+    M.config.enabled = runtime
+  else
+    M.config.enabled = configured
+  end
+  if M.config.enabled ~= enabled then
+    emit_changed()
+  end
+end
+
+---Whether privacy mode is on
+---@return boolean
+function M.enabled()
+  return M.config.enabled == true
+end
+
+---Turn privacy mode on or off
+---@param enabled boolean
+function M.set(enabled)
+  enabled = enabled == true
+  runtime = enabled
+  if M.config.enabled == enabled then
+    return
+  end
+  M.config.enabled = enabled
+  logger.info('privacy', 'Privacy mode ' .. (enabled and 'enabled' or 'disabled'))
+  emit_changed()
+end
+
+---Check that a request may go to a provider
+---@param provider string|nil
+---@return boolean ok
+---@return string|nil error
+function M.check_provider(provider)
+  if not M.enabled() or vim.tbl_contains(M.config.local_providers, provider) then
+    return true, nil
+  end
+  local err = string.format('Privacy mode: provider %s is not local (allowed: %s)',
+    provider or 'unknown', table.concat(M.config.local_providers, ', '))
+  logger.warn('privacy', err)
+  return false, err
+end
+
+---Whether a path matches the sensitive path patterns
+---@param path string|nil
+---@return boolean
+function M.is_sensitive(path)
+  if not path or path == '' then
+    return false
+  end
+  local full = vim.fn.fnamemodify(path, ':p')
+  local name = vim.fn.fnamemodify(path, ':t')
+  for _, pattern in ipairs(M.config.sensitive_paths) do
+    local regex = vim.fn.glob2regpat(pattern)
+    if vim.fn.match(name, regex) >= 0 or vim.fn.match(full, regex) >= 0 then
+      return true
+    end
+  end
+  return false
+end
+
+---Check that a file's contents may be sent to the model
+---@param path string|nil
+---@return boolean ok
+---@return string|nil error
+function M.check_path(path)
+  if not M.enabled() or not M.is_sensitive(path) then
+    return true, nil
+  end
+  local err = 'Privacy mode: not sending sensitive file ' .. vim.fn.fnamemodify(path, ':~:.')
+  logger.warn('privacy', err)
+  return false, err
+end
+
+---Check that a buffer's contents may be sent to the model
+---@param bufnr number|nil Default: current buffer
+---@return boolean ok
+---@return string|nil error
+function M.check_buffer(bufnr)
+  return M.check_path(vim.api.nvim_buf_get_name(bufnr or 0))
+end
+
+---Check that an AI-driven action may run
+---@param kind string "file_write" | "command"
+---@return boolean ok
+---@return string|nil error
+function M.check_action(kind)
+  if not M.enabled() then
+    return true, nil
+  end
+  local err = string.format('Privacy mode: %s actions are disabled', kind == 'command' and 'command' or 'file write')
+  logger.warn('privacy', err)
+  return false, err
+end
+
+---Summary for the status API
+---@return table {enabled, local_providers}
+function M.status()
+  return {
+    enabled = M.enabled(),
+    local_providers = vim.deepcopy(M.config.local_providers),
+  }
+end
+
+return M
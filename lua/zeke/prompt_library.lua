@@ -0,0 +1,212 @@
+--[[
+  Prompt Library
+
+  Reusable prompts saved as Markdown files under ~/.config/zeke/prompts,
+  one per prompt, with a small front matter block:
+
+    ---
+    description: Review for error handling
+    created: 2026-01-01
+    ---
+    Review the error handling in this {language} code:
+
+    {selection}
+
+  Prompt bodies use the template syntax of zeke.templates. A prompt without
+  {code} or {selection} gets the selection (or the whole buffer) appended.
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local templates = require('zeke.templates')
+local utils = require('zeke.utils')
+
+M.config = {
+  dir = '~/.config/zeke/prompts',
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+local function dir()
+  return vim.fn.expand(M.config.dir)
+end
+
+---File of a named prompt
+---@param name string
+---@return string
+function M.path(name)
+  return dir() .. '/' .. name .. '.md'
+end
+
+---Split front matter from the body
+---@param text string
+---@return table meta
+---@return string body
+function M.parse(text)
+  local meta = {}
+  local header, body = text:match('^%-%-%-\n(.-)\n%-%-%-\n?(.*)$')
+  if not header then
+    return meta, text
+  end
+  for _, line in ipairs(vim.split(header, '\n', { plain = true })) do
+    local key, value = line:match('^([%w_]+):%s*(.-)%s*$')
+    if key then
+      meta[key] = value
+    end
+  end
+  return meta, body
+end
+
+---Save a prompt (overwrites an existing one of the same name)
+---@param name string
+---@param text string Prompt body
+---@param meta table|nil {description}
+---@return boolean ok
+---@return string|nil error
+function M.save(name, text, meta)
+  if not name:match('^[%w_%-]+$') then
+    return false, 'Prompt names may only use letters, digits, "-" and "_"'
+  end
+  meta = vim.tbl_extend('keep', meta or {}, {
+    description = vim.split(text, '\n', { plain = true })[1]:sub(1, 60),
+    created = os.date('%Y-%m-%d'),
+  })
+
+  local lines = { '---' }
+  for _, key in ipairs({ 'description', 'created' }) do
+    table.insert(lines, string.format('%s: %s', key, meta[key]))
+  end
+  table.insert(lines, '---')
+  vim.list_extend(lines, vim.split(text, '\n', { plain = true }))
+
+  vim.fn.mkdir(dir(), 'p')
+  if vim.fn.writefile(lines, M.path(name)) ~= 0 then
+    return false, 'Could not write ' .. M.path(name)
+  end
+  logger.info('prompt_library', 'Saved prompt: ' .. name)
+  return true, nil
+end
+
+---Save the chat panel's pending input, or else its last sent message
+---@param name string
+---@param meta table|nil
+---@return boolean ok
+---@return string|nil error
+function M.save_current(name, meta)
+  local chat_panel = require('zeke.chat.panel')
+  local state = chat_panel.state
+  local text = ''
+  if state.input_buf and vim.api.nvim_buf_is_valid(state.input_buf) then
+    local lines = vim.api.nvim_buf_get_lines(state.input_buf, 0, -1, false)
+    -- Drop the prompt buffer's "> " prefix
+    text = vim.trim((table.concat(lines, '\n'):gsub('^' .. vim.pesc(vim.fn.prompt_getprompt(state.input_buf)), '')))
+  end
+  if text == '' then
+    for i = #state.history, 1, -1 do
+      if state.history[i].role == 'user' then
+        text = state.history[i].content
+        break
+      end
+    end
+  end
+  if text == '' then
+    return false, 'No prompt to save'
+  end
+  return M.save(name, text, meta)
+end
+
+---Load a prompt
+---@param name string
+---@return table|nil prompt {name, path, description, created, body}
+function M.get(name)
+  local path = M.path(name)
+  if vim.fn.filereadable(path) == 0 then
+    return nil
+  end
+  local meta, body = M.parse(table.concat(vim.fn.readfile(path), '\n'))
+  return vim.tbl_extend('force', meta, { name = name, path = path, body = body })
+end
+
+---Saved prompts with their metadata, sorted by name
+---@return table[]
+function M.list()
+  local prompts = {}
+  for _, path in ipairs(vim.fn.glob(dir() .. '/*.md', false, true)) do
+    local prompt = M.get(vim.fn.fnamemodify(path, ':t:r'))
+    if prompt then
+      table.insert(prompts, prompt)
+    end
+  end
+  table.sort(prompts, function(a, b) return a.name < b.name end)
+  return prompts
+end
+
+---Delete a prompt
+---@param name string
+---@return boolean
+function M.delete(name)
+  return vim.fn.delete(M.path(name)) == 0
+end
+
+---Render a prompt against the current buffer or selection
+---@param name string
+---@param opts table|nil {range = true when run on the visual selection, vars}
+---@return string|nil prompt
+---@return string|nil error
+function M.render(name, opts)
+  opts = opts or {}
+  local prompt = M.get(name)
+  if not prompt then
+    return nil, 'Unknown prompt: ' .. name
+  end
+
+  local vars = vim.deepcopy(opts.vars or {})
+  local text = templates.render(prompt.body, vars)
+
+  if not prompt.body:find('{code}', 1, true) and not prompt.body:find('{selection}', 1, true) then
+    local code = opts.range and templates.value(vars, 'selection') or templates.value(vars, 'code')
+    if code and code ~= '' then
+      text = text .. '\n\n' .. utils.format_code_block(code, templates.value(vars, 'filetype'))
+    end
+  end
+  return text, nil
+end
+
+---Run a prompt in the chat panel
+---@param name string
+---@param opts table|nil See M.render
+function M.run(name, opts)
+  local text, err = M.render(name, opts)
+  if not text then
+    vim.notify(err, vim.log.levels.ERROR)
+    return
+  end
+  local chat_panel = require('zeke.chat.panel')
+  chat_panel.open()
+  chat_panel.send_message(text)
+end
+
+---Pick a prompt and run it
+---@param opts table|nil See M.render
+function M.pick(opts)
+  local prompts = M.list()
+  if #prompts == 0 then
+    vim.notify('No saved prompts in ' .. dir(), vim.log.levels.INFO)
+    return
+  end
+  vim.ui.select(prompts, {
+    prompt = 'Run prompt:',
+    format_item = function(prompt)
+      return string.format('%-20s %s', prompt.name, prompt.description or '')
+    end,
+  }, function(prompt)
+    if prompt then
+      M.run(prompt.name, opts)
+    end
+  end)
+end
+
+return M
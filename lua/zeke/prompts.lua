@@ -3,7 +3,15 @@ local M = {}
 
 local http = require('zeke.http_client')
 local tools = require('zeke.context.tools')
+local snapshots = require('zeke.context.snapshots')
 local logger = require('zeke.logger')
+local utils = require('zeke.utils')
+
+-- "this Rust code" / "this code"
+local function this_code(opts)
+  local name = utils.language_name(opts.language)
+  return name ~= "" and ("this " .. name .. " code") or "this code"
+end
 
 -- Prompt templates
 M.templates = {
@@ -11,10 +19,9 @@ M.templates = {
     description = "Explain selected code or buffer",
     build_prompt = function(code, opts)
       return string.format(
-        "Explain this %s code in detail. Break down what it does, how it works, and any important concepts:\n\n```%s\n%s\n```",
-        opts.language or "",
-        opts.language or "",
-        code
+        "Explain %s in detail. Break down what it does, how it works, and any important concepts:\n\n%s",
+        this_code(opts),
+        utils.format_code_block(code, opts.language)
       )
     end,
   },
@@ -34,10 +41,10 @@ M.templates = {
       end
 
       return string.format(
-        "Fix the issues in this code. Return the corrected version with explanations of what was wrong and how you fixed it.%s\n\n```%s\n%s\n```",
+        "Fix the issues in %s. Return the corrected version with explanations of what was wrong and how you fixed it.%s\n\n%s",
+        this_code(opts),
         diag_text,
-        opts.language or "",
-        code
+        utils.format_code_block(code, opts.language)
       )
     end,
   },
@@ -46,9 +53,9 @@ M.templates = {
     description = "Optimize code for performance",
     build_prompt = function(code, opts)
       return string.format(
-        "Optimize this code for better performance, readability, and maintainability. Explain each optimization:\n\n```%s\n%s\n```",
-        opts.language or "",
-        code
+        "Optimize %s for better performance, readability, and maintainability. Explain each optimization:\n\n%s",
+        this_code(opts),
+        utils.format_code_block(code, opts.language)
       )
     end,
   },
@@ -57,9 +64,9 @@ M.templates = {
     description = "Add documentation comments",
     build_prompt = function(code, opts)
       return string.format(
-        "Add comprehensive documentation comments to this code. Include function descriptions, parameter explanations, return values, and examples where appropriate:\n\n```%s\n%s\n```",
-        opts.language or "",
-        code
+        "Add comprehensive documentation comments to %s. Include function descriptions, parameter explanations, return values, and examples where appropriate:\n\n%s",
+        this_code(opts),
+        utils.format_code_block(code, opts.language)
       )
     end,
   },
@@ -68,9 +75,9 @@ M.templates = {
     description = "Generate unit tests",
     build_prompt = function(code, opts)
       return string.format(
-        "Generate comprehensive unit tests for this code. Include edge cases, error cases, and normal cases:\n\n```%s\n%s\n```",
-        opts.language or "",
-        code
+        "Generate comprehensive unit tests for %s. Include edge cases, error cases, and normal cases:\n\n%s",
+        this_code(opts),
+        utils.format_code_block(code, opts.language)
       )
     end,
   },
@@ -89,9 +96,9 @@ M.templates = {
     description = "Refactor code",
     build_prompt = function(code, opts)
       return string.format(
-        "Refactor this code following best practices and design patterns. Explain the improvements and why they matter:\n\n```%s\n%s\n```",
-        opts.language or "",
-        code
+        "Refactor %s following best practices and design patterns. Explain the improvements and why they matter:\n\n%s",
+        this_code(opts),
+        utils.format_code_block(code, opts.language)
       )
     end,
   },
@@ -100,9 +107,8 @@ M.templates = {
     description = "Code review",
     build_prompt = function(code, opts)
       return string.format(
-        "Perform a thorough code review. Check for:\n- Bugs and potential issues\n- Code quality and style\n- Performance concerns\n- Security vulnerabilities\n- Best practices\n\n```%s\n%s\n```",
-        opts.language or "",
-        code
+        "Perform a thorough code review. Check for:\n- Bugs and potential issues\n- Code quality and style\n- Performance concerns\n- Security vulnerabilities\n- Best practices\n\n%s",
+        utils.format_code_block(code, opts.language)
       )
     end,
   },
@@ -123,8 +129,9 @@ function M.execute(template_name, code, opts)
   if not code then
     if template_name == 'commit' then
       -- For commit, use staged git diff
-      code = vim.fn.system("git diff --cached")
-      if vim.v.shell_error ~= 0 or code == "" then
+      local ok
+      code, ok = snapshots.git('git_staged')
+      if not ok or code == "" then
         vim.notify('No staged changes found. Stage changes with: git add <files>', vim.log.levels.WARN)
         return
       end
@@ -12,6 +12,7 @@
 local M = {}
 
 local logger = require('zeke.logger')
+local errors = require('zeke.errors')
 
 -- Request states
 M.State = {
@@ -28,6 +29,107 @@ M.active_requests = {}
 M.request_history = {}
 M.next_id = 1
 
+-- Concurrency limiter for async (streaming) requests
+M.limits = {
+  max_requests = 4,
+  max_per_provider = 2,
+  queue_size = 16,
+  overflow = "queue",
+}
+M.running = { total = 0, by_provider = {} }
+M.queue = {}
+M.queue_metrics = { peak = 0, dropped = 0, rejected = 0 }
+
+-- Timing of recent streams: { ttft_ms, duration_ms, chunks, tokens, tokens_per_sec }
+M.stream_metrics = {}
+local max_stream_metrics = 50
+
+---Configure concurrency limits
+---@param opts table {max_requests, max_per_provider, queue_size, overflow}
+function M.configure_limits(opts)
+  M.limits = vim.tbl_extend('force', M.limits, opts or {})
+end
+
+local function has_slot(provider)
+  local key = provider or "default"
+  return M.running.total < M.limits.max_requests
+    and (M.running.by_provider[key] or 0) < M.limits.max_per_provider
+end
+
+local function take_slot(provider)
+  local key = provider or "default"
+  M.running.total = M.running.total + 1
+  M.running.by_provider[key] = (M.running.by_provider[key] or 0) + 1
+end
+
+---Start a request now if a slot is free, otherwise queue or reject it
+---@param provider string|nil Provider the request goes to
+---@param start function Called once the request may run
+---@param on_drop function|nil Called with a reason if the queued request is dropped
+---@return boolean Accepted (started or queued)
+---@return string|nil Reason when rejected
+function M.acquire(provider, start, on_drop)
+  if has_slot(provider) then
+    take_slot(provider)
+    start()
+    return true
+  end
+
+  if M.limits.overflow == "reject" then
+    M.queue_metrics.rejected = M.queue_metrics.rejected + 1
+    return false, "Concurrency limit reached"
+  end
+
+  if #M.queue >= M.limits.queue_size then
+    if M.limits.overflow ~= "drop_oldest" or #M.queue == 0 then
+      M.queue_metrics.rejected = M.queue_metrics.rejected + 1
+      return false, string.format("Request queue full (%d waiting)", #M.queue)
+    end
+
+    -- Make room by dropping the request that has waited longest
+    local oldest = table.remove(M.queue, 1)
+    M.queue_metrics.dropped = M.queue_metrics.dropped + 1
+    logger.warn('requests', 'Queue full, dropped oldest queued request')
+    if oldest.on_drop then
+      oldest.on_drop("Dropped from full request queue")
+    end
+  end
+
+  table.insert(M.queue, { provider = provider, start = start, on_drop = on_drop })
+  M.queue_metrics.peak = math.max(M.queue_metrics.peak, #M.queue)
+  logger.debug('requests', string.format('Queued request (%d waiting)', #M.queue))
+  return true
+end
+
+---Release a slot and start the next queued request that fits
+---@param provider string|nil Provider the finished request went to
+function M.release(provider)
+  local key = provider or "default"
+  M.running.total = math.max(0, M.running.total - 1)
+  M.running.by_provider[key] = math.max(0, (M.running.by_provider[key] or 0) - 1)
+
+  for i, entry in ipairs(M.queue) do
+    if has_slot(entry.provider) then
+      table.remove(M.queue, i)
+      take_slot(entry.provider)
+      entry.start()
+      return
+    end
+  end
+end
+
+---Remove a queued request before it starts
+---@param start function The start function passed to acquire()
+function M.dequeue(start)
+  for i, entry in ipairs(M.queue) do
+    if entry.start == start then
+      table.remove(M.queue, i)
+      return true
+    end
+  end
+  return false
+end
+
 ---Generate unique request ID
 ---@return string Request ID
 function M.generate_id()
@@ -37,7 +139,7 @@ function M.generate_id()
 end
 
 ---Create new tracked request
----@param opts table Options {prompt, model, provider, max_retries, timeout}
+---@param opts table Options {prompt, model, provider, max_retries, timeout, backoff}
 ---@return table Request object
 function M.create(opts)
   opts = opts or {}
@@ -54,6 +156,7 @@ function M.create(opts)
     attempts = 0,
     max_retries = opts.max_retries or 3,
     timeout = opts.timeout or 120000, -- 2 minutes default
+    backoff = opts.backoff or {},
     errors = {},
     response = nil,
     metadata = opts.metadata or {},
@@ -170,7 +273,7 @@ function M.should_retry(request, error)
     "authentication",
   }
 
-  for _, pattern in ipairs(non_retryable) then
+  for _, pattern in ipairs(non_retryable) do
     if error_lower:match(pattern) then
       return false, "Non-retryable error: " .. pattern
     end
@@ -221,7 +324,11 @@ function M.execute_with_retry(request, execute_fn, on_success, on_failure)
         if should_retry then
           M.update_state(request.id, M.State.RETRYING)
 
-          local delay = M.calculate_backoff(request.attempts)
+          local delay = M.calculate_backoff(
+            request.attempts,
+            request.backoff.base_ms,
+            request.backoff.max_ms
+          )
           logger.info('requests', string.format(
             'Retrying request %s in %dms',
             request.id,
@@ -304,10 +411,169 @@ function M.get_active()
   return active
 end
 
+---Record the timing of a finished stream
+---@param metrics table {ttft_ms, duration_ms, chunks, tokens, tokens_per_sec}
+function M.record_stream(metrics)
+  table.insert(M.stream_metrics, metrics)
+  if #M.stream_metrics > max_stream_metrics then
+    table.remove(M.stream_metrics, 1)
+  end
+end
+
+-- Upper bounds (ms) of the latency and TTFT histogram buckets; larger
+-- values land in a final overflow bucket
+M.latency_buckets = { 100, 250, 500, 1000, 2500, 5000, 10000, 30000, 60000 }
+
+-- Per-provider outcomes: requests, errors by category, latency and TTFT histograms
+M.provider_metrics = {}
+
+local function new_histogram()
+  local counts = {}
+  for i = 1, #M.latency_buckets + 1 do
+    counts[i] = 0
+  end
+  return { counts = counts, count = 0, sum = 0, min = nil, max = nil }
+end
+
+local function observe(histogram, value)
+  local bucket = #M.latency_buckets + 1
+  for i, bound in ipairs(M.latency_buckets) do
+    if value <= bound then
+      bucket = i
+      break
+    end
+  end
+  histogram.counts[bucket] = histogram.counts[bucket] + 1
+  histogram.count = histogram.count + 1
+  histogram.sum = histogram.sum + value
+  histogram.min = math.min(histogram.min or value, value)
+  histogram.max = math.max(histogram.max or value, value)
+end
+
+-- Upper bound of the bucket holding quantile q (the max for the overflow bucket)
+local function quantile(histogram, q)
+  if histogram.count == 0 then
+    return nil
+  end
+  local target, seen = math.ceil(histogram.count * q), 0
+  for i, count in ipairs(histogram.counts) do
+    seen = seen + count
+    if seen >= target then
+      return math.min(M.latency_buckets[i] or histogram.max, histogram.max)
+    end
+  end
+  return histogram.max
+end
+
+local function summarize(histogram)
+  local buckets = {}
+  for i, count in ipairs(histogram.counts) do
+    table.insert(buckets, { le = M.latency_buckets[i] or math.huge, count = count })
+  end
+  return {
+    count = histogram.count,
+    avg = histogram.count > 0 and math.floor(histogram.sum / histogram.count) or nil,
+    min = histogram.min,
+    max = histogram.max,
+    p50 = quantile(histogram, 0.5),
+    p95 = quantile(histogram, 0.95),
+    buckets = buckets,
+  }
+end
+
+---Record the outcome of a request or stream for its provider
+---@param provider string|nil
+---@param outcome table {latency_ms, ttft_ms, error (message when failed)}
+function M.record_outcome(provider, outcome)
+  provider = provider or 'default'
+  local metrics = M.provider_metrics[provider]
+  if not metrics then
+    metrics = { requests = 0, errors = 0, by_category = {}, latency = new_histogram(), ttft = new_histogram() }
+    M.provider_metrics[provider] = metrics
+  end
+
+  metrics.requests = metrics.requests + 1
+  if outcome.latency_ms then
+    observe(metrics.latency, outcome.latency_ms)
+  end
+  if outcome.ttft_ms then
+    observe(metrics.ttft, outcome.ttft_ms)
+  end
+  if outcome.error then
+    local category = errors.category(outcome.error)
+    metrics.errors = metrics.errors + 1
+    metrics.by_category[category] = (metrics.by_category[category] or 0) + 1
+  end
+end
+
+---Per-provider metrics
+---@return table {[provider] = {requests, errors, error_rate, errors_by_category, latency, ttft}}
+---  latency/ttft: {count, avg, min, max, p50, p95, buckets = {{le, count}, ...}} in ms
+function M.metrics()
+  local out = {}
+  for provider, metrics in pairs(M.provider_metrics) do
+    out[provider] = {
+      requests = metrics.requests,
+      errors = metrics.errors,
+      error_rate = metrics.requests > 0 and metrics.errors / metrics.requests or 0,
+      errors_by_category = vim.deepcopy(metrics.by_category),
+      latency = summarize(metrics.latency),
+      ttft = summarize(metrics.ttft),
+    }
+  end
+  return out
+end
+
+---Format per-provider metrics for display
+---@return string
+function M.format_metrics()
+  local metrics = M.metrics()
+  local providers = vim.tbl_keys(metrics)
+  if #providers == 0 then
+    return 'No requests recorded yet'
+  end
+  table.sort(providers)
+
+  local lines = { '=== Provider Metrics ===' }
+  for _, provider in ipairs(providers) do
+    local m = metrics[provider]
+    table.insert(lines, '')
+    table.insert(lines, string.format('%s: %d requests, %d errors (%.0f%%)',
+      provider, m.requests, m.errors, m.error_rate * 100))
+    if m.latency.count > 0 then
+      table.insert(lines, string.format('  Latency: avg %dms, p50 ≤%dms, p95 ≤%dms, max %dms',
+        m.latency.avg, m.latency.p50, m.latency.p95, m.latency.max))
+    end
+    if m.ttft.count > 0 then
+      table.insert(lines, string.format('  TTFT: avg %dms, p50 ≤%dms, p95 ≤%dms',
+        m.ttft.avg, m.ttft.p50, m.ttft.p95))
+    end
+    if m.errors > 0 then
+      local parts = {}
+      for category, count in pairs(m.errors_by_category) do
+        table.insert(parts, category .. '=' .. count)
+      end
+      table.sort(parts)
+      table.insert(lines, '  Errors: ' .. table.concat(parts, ', '))
+    end
+  end
+  return table.concat(lines, '\n')
+end
+
+---Clear per-provider metrics
+function M.reset_metrics()
+  M.provider_metrics = {}
+end
+
 ---Get request statistics
 ---@return table Statistics
 function M.get_stats()
   local stats = {
+    running = M.running.total,
+    queued = #M.queue,
+    queue_peak = M.queue_metrics.peak,
+    queue_dropped = M.queue_metrics.dropped,
+    queue_rejected = M.queue_metrics.rejected,
     active = 0,
     completed = 0,
     failed = 0,
@@ -337,6 +603,19 @@ function M.get_stats()
     stats.avg_attempts = total_attempts / #M.request_history
   end
 
+  -- Averages over recent streams that produced output
+  local ttft_sum, rate_sum, timed = 0, 0, 0
+  for _, metrics in ipairs(M.stream_metrics) do
+    if metrics.ttft_ms then
+      ttft_sum = ttft_sum + metrics.ttft_ms
+      rate_sum = rate_sum + metrics.tokens_per_sec
+      timed = timed + 1
+    end
+  end
+  stats.streams_timed = timed
+  stats.avg_ttft_ms = timed > 0 and math.floor(ttft_sum / timed) or nil
+  stats.avg_tokens_per_sec = timed > 0 and rate_sum / timed or nil
+
   return stats
 end
 
@@ -435,6 +714,12 @@ function M.show_inspector()
   table.insert(items, string.format("Failed: %d", stats.failed))
   table.insert(items, string.format("Cancelled: %d", stats.cancelled))
   table.insert(items, string.format("Avg Attempts: %.2f", stats.avg_attempts))
+  table.insert(items, string.format("Streams: %d running, %d queued (peak %d, dropped %d, rejected %d)",
+    stats.running, stats.queued, stats.queue_peak, stats.queue_dropped, stats.queue_rejected))
+  if stats.avg_ttft_ms then
+    table.insert(items, string.format("Stream Speed: %dms to first token, %.1f tokens/s (last %d)",
+      stats.avg_ttft_ms, stats.avg_tokens_per_sec, stats.streams_timed))
+  end
 
   vim.notify(table.concat(items, "\n"), vim.log.levels.INFO)
 end
@@ -0,0 +1,190 @@
+--[[
+  Reranking
+
+  Orders retrieved documents by relevance to a query with a reranking
+  model (a cross-encoder), which judges relevance far better than
+  embedding similarity alone. Retrieval code asks for more candidates than
+  it can fit, reranks them, and keeps the best until the context budget is
+  spent:
+
+    require('zeke').rerank("where are tokens counted?", chunks, function(results, err)
+      -- results: { { index = 3, score = 0.92, document = chunks[3] }, ... }, best first
+    end, { top_n = 5 })
+
+  Backends: cohere (needs COHERE_API_KEY or providers.cohere.api_key) and
+  llamacpp, a local server with a /v1/rerank endpoint (llama.cpp started
+  with --reranking; vLLM and Infinity serve the same API, set rerank.url).
+  More backends can be added with M.register_backend().
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local config = require('zeke.config')
+local privacy = require('zeke.privacy')
+local secrets = require('zeke.secrets')
+local embeddings = require('zeke.embeddings')
+local tokens = require('zeke.tokens')
+
+M.config = {
+  provider = 'llamacpp',
+  model = nil,   -- Default: the backend's model
+  url = nil,     -- Base URL of the rerank server (default: providers.<name>.base_url)
+  timeout_s = 30,
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_extend('force', M.config, opts or {})
+end
+
+-- Both backends return { results = { { index (0-based), relevance_score } } }
+local function parse_results(response)
+  return vim.tbl_map(function(item)
+    return { index = item.index + 1, score = item.relevance_score }
+  end, response.results or {})
+end
+
+-- name -> { model, url, key_env, request(model, query, documents, opts) -> path, body, parse(response) -> {index, score}[] }
+M.backends = {
+  cohere = {
+    model = 'rerank-v3.5',
+    url = 'https://api.cohere.com/v2',
+    key_env = 'COHERE_API_KEY',
+    request = function(model, query, documents, opts)
+      return '/rerank', { model = model, query = query, documents = documents, top_n = opts.top_n }
+    end,
+    parse = parse_results,
+  },
+  llamacpp = {
+    model = 'bge-reranker-v2-m3',
+    url = 'http://localhost:8080',
+    request = function(model, query, documents, opts)
+      return '/v1/rerank', { model = model, query = query, documents = documents, top_n = opts.top_n }
+    end,
+    parse = parse_results,
+  },
+}
+
+---Add (or replace) a rerank backend
+---@param name string
+---@param spec table {model, url, key_env, request(model, query, documents, opts), parse(response)}
+function M.register_backend(name, spec)
+  M.backends[name] = spec
+end
+
+---Rank documents by relevance to a query
+---@param query string
+---@param documents string[]
+---@param callback function Called with ({index, score, document}[] best first | nil, error)
+---@param opts table|nil {provider, model, top_n, secret_scan}
+function M.rerank(query, documents, callback, opts)
+  opts = opts or {}
+  if #documents == 0 then
+    callback({}, nil)
+    return
+  end
+
+  local provider = opts.provider or M.config.provider
+  local backend = M.backends[provider]
+  if not backend then
+    callback(nil, 'Unknown rerank provider: ' .. tostring(provider))
+    return
+  end
+  local allowed, refused = privacy.check_provider(provider)
+  if not allowed then
+    callback(nil, refused)
+    return
+  end
+
+  local texts = { query }
+  vim.list_extend(texts, documents)
+  for i, text in ipairs(texts) do
+    local checked, blocked = secrets.check(text, opts.secret_scan)
+    if not checked then
+      callback(nil, blocked)
+      return
+    end
+    texts[i] = checked
+  end
+
+  local providers = (config.options and config.options.providers) or {}
+  local settings = providers[provider] or {}
+  local base_url = M.config.url or settings.base_url or backend.url
+  local api_key = settings.api_key or (backend.key_env and vim.env[backend.key_env])
+  if backend.key_env and not api_key then
+    callback(nil, string.format('No API key for %s (set %s or providers.%s.api_key)', provider, backend.key_env, provider))
+    return
+  end
+  local model = opts.model or M.config.model or backend.model
+
+  local path, body = backend.request(model, texts[1], vim.list_slice(texts, 2), opts)
+  embeddings.post_json(base_url:gsub('/+$', '') .. path, body, api_key, vim.schedule_wrap(function(response, err)
+    if not response then
+      logger.warn('rerank', err)
+      callback(nil, err)
+      return
+    end
+
+    local results = {}
+    for _, result in ipairs(backend.parse(response)) do
+      if documents[result.index] then
+        result.document = documents[result.index]
+        table.insert(results, result)
+      end
+    end
+    table.sort(results, function(a, b) return a.score > b.score end)
+    if opts.top_n then
+      results = vim.list_slice(results, 1, opts.top_n)
+    end
+    logger.debug('rerank', string.format('Reranked %d documents with %s/%s', #documents, provider, model))
+    callback(results, nil)
+  end), M.config.timeout_s)
+end
+
+---Rerank and wait for the result
+---@param query string
+---@param documents string[]
+---@param opts table|nil Same as M.rerank
+---@return table|nil results
+---@return string|nil error
+function M.rerank_sync(query, documents, opts)
+  local results, err, finished = nil, nil, false
+  M.rerank(query, documents, function(ranked, rerank_err)
+    results, err, finished = ranked, rerank_err, true
+  end, opts)
+  if not vim.wait(M.config.timeout_s * 1000, function() return finished end, 20) then
+    return nil, 'Timed out waiting for reranking'
+  end
+  return results, err
+end
+
+---Rerank documents and keep the most relevant ones that fit a token budget
+---@param query string
+---@param documents string[]
+---@param max_tokens number
+---@param callback function Called with (documents best first, error); on rerank errors the input order is kept
+---@param opts table|nil Same as M.rerank, plus count_model (whose tokenizer measures the budget)
+function M.select(query, documents, max_tokens, callback, opts)
+  opts = opts or {}
+  local function fill(ordered)
+    local selected, used = {}, 0
+    for _, document in ipairs(ordered) do
+      local count = tokens.count(document, opts.count_model)
+      if used + count <= max_tokens then
+        table.insert(selected, document)
+        used = used + count
+      end
+    end
+    return selected
+  end
+
+  M.rerank(query, documents, function(results, err)
+    if not results then
+      callback(fill(documents), err)
+      return
+    end
+    callback(fill(vim.tbl_map(function(result) return result.document end, results)), nil)
+  end, opts)
+end
+
+return M
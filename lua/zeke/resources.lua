@@ -5,6 +5,8 @@ local M = {}
 
 local tools = require('zeke.context.tools')
 local logger = require('zeke.logger')
+local utils = require('zeke.utils')
+local privacy = require('zeke.privacy')
 
 -- Parse resources from a message
 -- Returns: resources table, cleaned message
@@ -143,21 +145,19 @@ function M.inject_into_message(message, resources)
   for _, res in ipairs(resources) do
     if res.type == "file" or res.type == "buffer" then
       local type_label = res.type == "file" and "File" or "Current Buffer"
-      local lang_str = res.language and res.language or ""
       table.insert(context_parts, string.format(
-        "**%s**: `%s`\n```%s\n%s\n```",
+        "**%s**: `%s`\n%s",
         type_label,
         res.path,
-        lang_str,
-        res.content
+        utils.format_code_block(res.content, res.language)
       ))
     elseif res.type == "selection" then
       table.insert(context_parts, string.format(
-        "**Selected Text** (lines %d-%d from `%s`):\n```\n%s\n```",
+        "**Selected Text** (lines %d-%d from `%s`):\n%s",
         res.start_line,
         res.end_line,
         res.file_path,
-        res.text
+        utils.format_code_block(res.text, res.language or utils.filetype_of(res.file_path))
       ))
     elseif res.type == "diagnostics" then
       local diag_lines = {}
@@ -204,6 +204,10 @@ function M.get_file_content(path)
     return nil
   end
 
+  if not privacy.check_path(path) then
+    return nil
+  end
+
   -- Try to read file
   local ok, lines = pcall(vim.fn.readfile, path)
   if not ok or not lines then
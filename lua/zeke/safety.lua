@@ -52,7 +52,7 @@ function M.setup(opts)
 end
 
 ---Check if operation is safe
----@param opts table Options {prompt, bufnr, operation_type}
+---@param opts table Options {prompt, bufnr, operation_type, model}
 ---@return boolean Is safe
 ---@return table|nil Warnings/errors
 function M.check_safety(opts)
@@ -62,7 +62,7 @@ function M.check_safety(opts)
 
   -- Check prompt size
   if opts.prompt then
-    local token_count = tokens.estimate_tokens(opts.prompt)
+    local token_count = tokens.count(opts.prompt, opts.model)
 
     if token_count >= M.config.max_tokens then
       table.insert(errors, {
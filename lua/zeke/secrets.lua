@@ -0,0 +1,201 @@
+--[[
+  Outgoing Prompt Secret Scanner
+
+  Checks prompts for likely credentials before they leave the editor:
+  cloud keys, provider API keys, private key blocks, bearer tokens, JWTs
+  and credential assignments (PASSWORD=..., "api_key": "..."). Depending
+  on `secret_scan.mode` findings are redacted ("redact"), reported while
+  the prompt is sent as-is ("warn"), or stop the request ("block").
+  Transcripts and the wire log redact with the same detectors.
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+
+M.config = {
+  mode = 'redact',  -- "redact", "warn", "block" or "off"
+  allow = {},       -- Lua patterns of values that are never flagged (test fixtures, ...)
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+local function run(class, n)
+  return string.rep(class, n)
+end
+
+-- Single-line detectors: { name, pattern }
+M.patterns = {
+  { name = 'aws_access_key', pattern = '%f[%w]A[KS]IA' .. run('[%u%d]', 16) .. '%f[^%w]' },
+  { name = 'aws_secret_key', pattern = '[Aa][Ww][Ss]_?[Ss][Ee][Cc][Rr][Ee][Tt]_?[%w_]*%s*["\']?%s*[:=]%s*["\']?' .. run('[%w/+]', 40) .. '%f[^%w/+]' },
+  -- OpenAI, Anthropic and compatible provider keys
+  { name = 'api_key', pattern = '%f[%w]sk%-[%w%-_]' .. run('[%w%-_]', 19) .. '[%w%-_]*' },
+  { name = 'github_token', pattern = '%f[%w]gh[pousr]_' .. run('%w', 20) .. '%w*' },
+  { name = 'slack_token', pattern = 'xox[abpr]%-[%w%-]+' },
+  { name = 'google_api_key', pattern = 'AIza' .. run('[%w%-_]', 35) },
+  { name = 'jwt', pattern = 'eyJ[%w%-_]+%.eyJ[%w%-_]+%.[%w%-_]+' },
+  { name = 'bearer_token', pattern = '[Bb]earer%s+[%w%-_%.=]' .. run('[%w%-_%.=]', 15) .. '[%w%-_%.=]*' },
+}
+
+-- NAME=value / name: value lines whose name marks a credential
+local env_words = { 'SECRET', 'TOKEN', 'PASSWORD', 'PASSWD', 'API_KEY', 'APIKEY', 'PRIVATE_KEY', 'ACCESS_KEY' }
+
+local private_key_block = '%-%-%-%-%-BEGIN [%u ]*PRIVATE KEY%-%-%-%-%-.-%-%-%-%-%-END [%u ]*PRIVATE KEY%-%-%-%-%-'
+
+local function allowed(value)
+  for _, pattern in ipairs(M.config.allow or {}) do
+    if value:match(pattern) then
+      return true
+    end
+  end
+  return false
+end
+
+-- Whether an assigned value is a credential literal rather than a number,
+-- a setting, or a lookup (MAX_TOKENS = 4096, API_KEY = os.environ["API_KEY"])
+local function credential_literal(value)
+  local quoted = value:match('^"(.*)"[,;]?$') or value:match("^'(.*)'[,;]?$")
+  local literal = quoted or value:gsub('[,;]$', '')
+  if #literal < 8 or literal:find('%s') or literal:find('%$') then
+    return false
+  end
+  -- Numbers, versions, sizes, and URLs without user:password@
+  if literal:match('^[%d%.%-_:]+%a?%a?$') or (literal:match('^%a[%w+.-]*://') and not literal:find('@')) then
+    return false
+  end
+  -- Unquoted calls, indexing and attribute references are code, not values
+  if not quoted and (literal:find('[%(%)%[%]{}]') or literal:match('^[%a_][%w_]*%.[%a_][%w_%.]*$')) then
+    return false
+  end
+
+  -- Secrets mix character classes; plain words and snake_case names don't
+  local classes = 0
+  for _, class in ipairs({ '%l', '%u', '%d', '[^%w_]' }) do
+    if literal:find(class) then
+      classes = classes + 1
+    end
+  end
+  return classes >= 2
+end
+
+-- Value of a credential assignment (NAME=value, "name": "value"), with its position
+local function env_assignment(line)
+  local name, start, value = line:match('^%s*export%s+([%w_]+)%s*=%s*()(.-)%s*$')
+  if not name then
+    name, start, value = line:match('^%s*["\']?([%w_%-]+)["\']?%s*[:=]%s*()(.-)%s*$')
+  end
+  if not name or not credential_literal(value) then
+    return nil
+  end
+  name = name:upper():gsub('%-', '_')
+  for _, word in ipairs(env_words) do
+    if name:find(word, 1, true) then
+      return start, start + #value - 1
+    end
+  end
+  return nil
+end
+
+---Find likely secrets
+---@param text string
+---@return table[] findings { {name, line}, ... }
+function M.scan(text)
+  local findings = {}
+
+  local lnum = 1
+  for line in (text .. '\n'):gmatch('(.-)\n') do
+    for _, detector in ipairs(M.patterns) do
+      for match in line:gmatch(detector.pattern) do
+        if not allowed(match) then
+          table.insert(findings, { name = detector.name, line = lnum })
+        end
+      end
+    end
+    local first, last = env_assignment(line)
+    if first and not allowed(line:sub(first, last)) then
+      table.insert(findings, { name = 'env_assignment', line = lnum })
+    end
+    lnum = lnum + 1
+  end
+
+  local start = text:find(private_key_block)
+  if start then
+    local _, newlines = text:sub(1, start):gsub('\n', '')
+    table.insert(findings, { name = 'private_key', line = newlines + 1 })
+  end
+
+  return findings
+end
+
+---Replace likely secrets with [REDACTED:<kind>]
+---@param text string
+---@return string
+function M.redact(text)
+  text = text:gsub(private_key_block, '[REDACTED:private_key]')
+
+  local lines = vim.split(text, '\n', { plain = true })
+  for i, line in ipairs(lines) do
+    for _, detector in ipairs(M.patterns) do
+      line = line:gsub(detector.pattern, function(match)
+        if allowed(match) then
+          return match
+        end
+        return '[REDACTED:' .. detector.name .. ']'
+      end)
+    end
+    local first, last = env_assignment(line)
+    if first and not allowed(line:sub(first, last)) then
+      line = line:sub(1, first - 1) .. '[REDACTED:env_assignment]' .. line:sub(last + 1)
+    end
+    lines[i] = line
+  end
+  return table.concat(lines, '\n')
+end
+
+local function summary(findings)
+  local counts, names = {}, {}
+  for _, finding in ipairs(findings) do
+    if not counts[finding.name] then
+      table.insert(names, finding.name)
+    end
+    counts[finding.name] = (counts[finding.name] or 0) + 1
+  end
+  local parts = {}
+  for _, name in ipairs(names) do
+    table.insert(parts, counts[name] > 1 and string.format('%s ×%d', name, counts[name]) or name)
+  end
+  return table.concat(parts, ', ')
+end
+
+---Apply the configured policy to an outgoing prompt
+---@param prompt string
+---@param mode string|nil Override of secret_scan.mode
+---@return string|nil prompt The prompt to send (nil when blocked)
+---@return string|nil error Why it was blocked
+function M.check(prompt, mode)
+  mode = mode or M.config.mode
+  if mode == 'off' or type(prompt) ~= 'string' then
+    return prompt, nil
+  end
+
+  local findings = M.scan(prompt)
+  if #findings == 0 then
+    return prompt, nil
+  end
+
+  local found = summary(findings)
+  if mode == 'block' then
+    logger.warn('secrets', 'Request blocked, prompt contains likely secrets: ' .. found)
+    return nil, 'Prompt contains likely secrets (' .. found .. '); request blocked'
+  elseif mode == 'warn' then
+    logger.warn('secrets', 'Prompt contains likely secrets: ' .. found)
+    return prompt, nil
+  end
+
+  logger.warn('secrets', 'Redacted likely secrets from the prompt: ' .. found)
+  return M.redact(prompt), nil
+end
+
+return M
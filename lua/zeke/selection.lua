@@ -4,6 +4,7 @@ local M = {}
 
 local logger = require('zeke.logger')
 local terminal = require('zeke.terminal')
+local utils = require('zeke.utils')
 
 -- State management
 M.state = {
@@ -227,8 +228,8 @@ function M.send_selection_to_zeke(selection)
       selection.start_line + 1,
       selection.end_line + 1) or ""
 
-  local message = string.format("Selected text%s:\n```\n%s\n```",
-    file_info, selection.text)
+  local message = string.format("Selected text%s:\n%s",
+    file_info, utils.format_code_block(selection.text, utils.filetype_of(selection.file_path)))
 
   -- Send to terminal/AI
   terminal.send_to_ai({
@@ -6,6 +6,7 @@
   - Token usage statistics
   - Request progress indicators
   - Rate limiting status
+  - Privacy mode indicator
   - Lualine component support
 --]]
 
@@ -15,6 +16,7 @@ local models = require('zeke.models')
 local tokens = require('zeke.tokens')
 local requests = require('zeke.requests')
 local safety = require('zeke.safety')
+local privacy = require('zeke.privacy')
 
 -- Configuration
 M.config = {
@@ -30,6 +32,7 @@ M.config = {
     rate_limit_ok = '🟢',
     rate_limit_warn = '🟡',
     rate_limit_critical = '🔴',
+    privacy = '🔒',
   },
 }
 
@@ -143,6 +146,10 @@ function M.get_statusline()
 
   local parts = {}
 
+  if privacy.enabled() then
+    table.insert(parts, M.config.icons.privacy)
+  end
+
   local model = M.get_model()
   if model ~= '' then
     table.insert(parts, model)
@@ -277,6 +284,14 @@ function M.setup_autocommands()
     end,
   })
 
+  vim.api.nvim_create_autocmd({ 'User' }, {
+    group = group,
+    pattern = 'ZekeStatusChanged',
+    callback = function()
+      M.update()
+    end,
+  })
+
   vim.api.nvim_create_autocmd({ 'User' }, {
     group = group,
     pattern = 'ZekeModelChanged',
@@ -0,0 +1,86 @@
+--[[
+  Stream-to-buffer helper
+
+  Turns streamed text into line operations: complete lines are written once,
+  and the trailing partial line is updated in place as more text arrives.
+  Each chunk costs one nvim_buf_set_lines call on the affected rows instead
+  of re-rendering the whole buffer.
+
+    local writer = require('zeke.stream_buffer').writer(buf, { prefix = '│ ', footer = 2 })
+    cli.stream_chat(prompt, function(chunk)
+      vim.schedule(function() writer.write(chunk) end)
+    end, ...)
+--]]
+
+local M = {}
+
+local api = vim.api
+
+---Split a chunked text stream into lines
+---@return function feed(chunk) -> complete_lines (string[]), partial (string)
+function M.splitter()
+  local pending = ''
+  return function(chunk)
+    pending = pending .. chunk
+    local lines = vim.split(pending, '\n', { plain = true })
+    pending = table.remove(lines)
+    return lines, pending
+  end
+end
+
+---Write streamed text into a buffer
+---@param buf number
+---@param opts table|nil {prefix = string added to each line, footer = lines kept below the text, on_write = function(last_row)}
+---@return table writer {write(chunk), finish()}
+function M.writer(buf, opts)
+  opts = opts or {}
+  local prefix = opts.prefix or ''
+  local footer = opts.footer or 0
+  local split = M.splitter()
+  -- 0-based row showing the current partial line
+  local partial_row = nil
+
+  local writer = {}
+
+  function writer.write(chunk)
+    if not api.nvim_buf_is_valid(buf) then
+      return
+    end
+
+    local complete, partial = split(chunk)
+    local lines = {}
+    for _, line in ipairs(complete) do
+      table.insert(lines, prefix .. line)
+    end
+    if partial ~= '' then
+      table.insert(lines, prefix .. partial)
+    end
+    if #lines == 0 and not partial_row then
+      return
+    end
+
+    -- Replace the previous partial row (or insert above the footer)
+    local row = partial_row or (api.nvim_buf_line_count(buf) - footer)
+    local end_row = partial_row and (partial_row + 1) or row
+
+    local modifiable = vim.bo[buf].modifiable
+    vim.bo[buf].modifiable = true
+    api.nvim_buf_set_lines(buf, row, end_row, false, lines)
+    vim.bo[buf].modifiable = modifiable
+
+    partial_row = partial ~= '' and (row + #lines - 1) or nil
+    if opts.on_write then
+      opts.on_write(row + #lines - 1)
+    end
+  end
+
+  -- Keep the last partial line as written; later text starts a new line
+  function writer.finish()
+    partial_row = nil
+    split = M.splitter()
+  end
+
+  return writer
+end
+
+return M
@@ -0,0 +1,187 @@
+--[[
+  Prompt Templates
+
+  Prompts are rendered from templates looked up in this order:
+    1. <templates_dir>/<name>.<provider>.md  (per-provider file)
+    2. prompts.providers.<provider>.<name>    (per-provider config)
+    3. <templates_dir>/<name>.md             (user file)
+    4. prompts.<name>                         (config / defaults)
+
+  Syntax:
+    {var}              replaced by the variable (left as-is when unknown)
+    {?var}...{/var}    kept only when var is set and non-empty
+
+  {code}, {filetype}, {selection} and {diagnostics} default to the current
+  buffer when the caller doesn't pass them; {instruction} must be passed.
+  {language} ("Rust", "TypeScript") and {fence} (the code fence tag) are
+  derived from {filetype}.
+--]]
+
+local M = {}
+
+local config = require('zeke.config')
+local utils = require('zeke.utils')
+local examples = require('zeke.examples')
+local privacy = require('zeke.privacy')
+
+local function templates_dir()
+  return vim.fn.expand((config.options and config.options.templates_dir) or '~/.config/zeke/templates')
+end
+
+local function read_file(path)
+  if vim.fn.filereadable(path) == 0 then
+    return nil
+  end
+  return table.concat(vim.fn.readfile(path), '\n')
+end
+
+-- Provider of the current model
+local function current_provider()
+  local ok, models = pcall(require, 'zeke.models')
+  local model = ok and models.get_current()
+  return model and model.provider or (config.options and config.options.default_provider)
+end
+
+---Find a template's text
+---@param name string
+---@param provider string|nil Default: provider of the current model
+---@return string|nil template
+---@return string|nil source Where it came from (file path or "config")
+function M.find(name, provider)
+  provider = provider or current_provider()
+  local dir = templates_dir()
+  local prompts = (config.options and config.options.prompts) or config.defaults.prompts
+  local overrides = prompts.providers and provider and prompts.providers[provider]
+
+  if provider then
+    local path = string.format('%s/%s.%s.md', dir, name, provider)
+    local text = read_file(path)
+    if text then
+      return text, path
+    end
+    if type(overrides) == 'table' and overrides[name] then
+      return overrides[name], 'config'
+    end
+  end
+
+  local path = string.format('%s/%s.md', dir, name)
+  local text = read_file(path)
+  if text then
+    return text, path
+  end
+
+  local template = prompts[name] or config.defaults.prompts[name]
+  if type(template) == 'string' then
+    return template, 'config'
+  end
+  return nil, nil
+end
+
+-- Defaults for the standard variables, taken from the current buffer.
+-- Each receives the variables resolved so far.
+M.defaults = {
+  filetype = function()
+    return vim.bo.filetype
+  end,
+  language = function(vars)
+    return utils.language_name(M.value(vars, 'filetype'))
+  end,
+  fence = function(vars)
+    return utils.fence_language(M.value(vars, 'filetype'))
+  end,
+  code = function()
+    if not privacy.check_buffer(0) then
+      return ''
+    end
+    return table.concat(vim.api.nvim_buf_get_lines(0, 0, -1, false), '\n')
+  end,
+  selection = function()
+    local start_pos, end_pos = vim.fn.getpos("'<"), vim.fn.getpos("'>")
+    if start_pos[2] == 0 or not privacy.check_buffer(0) then
+      return ''
+    end
+    return table.concat(vim.api.nvim_buf_get_lines(0, start_pos[2] - 1, end_pos[2], false), '\n')
+  end,
+  diagnostics = function()
+    local lines = {}
+    for _, diag in ipairs(vim.diagnostic.get(0)) do
+      local severity = vim.diagnostic.severity[diag.severity] or 'INFO'
+      table.insert(lines, string.format('Line %d: [%s] %s', diag.lnum + 1, severity, diag.message))
+    end
+    return table.concat(lines, '\n')
+  end,
+}
+
+---Resolve a variable, filling in its default
+---@param vars table
+---@param key string
+---@return any
+function M.value(vars, key)
+  if vars[key] == nil and M.defaults[key] then
+    vars[key] = M.defaults[key](vars)
+  end
+  return vars[key]
+end
+
+---Render template text
+---@param template string
+---@param vars table|nil
+---@return string
+function M.render(template, vars)
+  vars = vars or {}
+
+  local function value(key)
+    return M.value(vars, key)
+  end
+
+  -- Conditional sections first, so their bodies can use variables
+  local text = template:gsub('{%?([%w_]+)}(.-){/%1}', function(key, body)
+    local v = value(key)
+    if v == nil or v == false or v == '' then
+      return ''
+    end
+    return body
+  end)
+
+  return (text:gsub('{([%w_]+)}', function(key)
+    local v = value(key)
+    if v ~= nil then
+      return tostring(v)
+    end
+  end))
+end
+
+---Render a named template
+---@param name string
+---@param vars table|nil
+---@param provider string|nil
+---@return string
+function M.render_named(name, vars, provider)
+  local template = M.find(name, provider)
+  if not template then
+    error('Unknown prompt template: ' .. tostring(name))
+  end
+  return examples.apply(M.render(template, vars), name)
+end
+
+---Template names available from config and the templates directory
+---@return string[]
+function M.list()
+  local names = {}
+  local prompts = (config.options and config.options.prompts) or config.defaults.prompts
+  for name, template in pairs(prompts) do
+    if type(template) == 'string' then
+      names[name] = true
+    end
+  end
+  for _, path in ipairs(vim.fn.glob(templates_dir() .. '/*.md', false, true)) do
+    -- <name>.md or <name>.<provider>.md
+    local name = vim.fn.fnamemodify(path, ':t'):match('^([^.]+)')
+    names[name] = true
+  end
+  local list = vim.tbl_keys(names)
+  table.sort(list)
+  return list
+end
+
+return M
@@ -3,11 +3,18 @@ local M = {}
 local uv = vim.loop
 local config = require('zeke.config')
 local logger = require('zeke.logger')
+local ansi = require('zeke.ansi')
 
 -- Active background tasks
 local active_tasks = {}
 local task_counter = 0
 
+local is_windows = vim.fn.has('win32') == 1
+
+-- Finished tasks, most recent last
+local task_history = {}
+local max_history = 50
+
 -- Terminal state
 M.state = {
   buf = nil,
@@ -40,7 +47,7 @@ end
 
 -- Get the zeke binary path
 local function get_zeke_binary()
-  local config_opts = config.get()
+  local config_opts = config.options or {}
   return config_opts.binary_path or './zig-out/bin/zeke_nvim'
 end
 
@@ -69,121 +76,627 @@ function M.run_command(cmd, opts)
   return buf, win
 end
 
--- Execute command asynchronously with callbacks
-function M.execute_command(cmd, opts)
-  opts = opts or {}
-  
-  local full_cmd = get_zeke_binary() .. ' ' .. cmd
-  local task_id = task_counter + 1
-  task_counter = task_id
-  
-  -- Create a unique buffer for this task's output
-  local buf = vim.api.nvim_create_buf(false, true)
-  vim.api.nvim_buf_set_name(buf, 'Zeke Task #' .. task_id)
-  vim.api.nvim_buf_set_option(buf, 'buftype', 'nofile')
-  vim.api.nvim_buf_set_option(buf, 'filetype', 'json')
-  
+-- Fire a User autocmd with a data payload. Task lifecycle events:
+-- ZekeTaskStarted, ZekeTaskOutput, ZekeTaskCompleted, ZekeTaskFailed,
+-- ZekeTaskCancelled (plus ZekeTaskTimeout before the failure)
+local function emit(event, data)
+  vim.schedule(function()
+    vim.api.nvim_exec_autocmds('User', { pattern = event, data = data, modeline = false })
+  end)
+end
+
+-- Task settings from config (timeout_ms, ...)
+local function task_config()
+  return (config.options and config.options.tasks) or {}
+end
+
+-- Arguments that make each shell run a command string
+local shell_flags = {
+  sh = { '-c' },
+  bash = { '-c' },
+  zsh = { '-c' },
+  fish = { '-c' },
+  pwsh = { '-NoProfile', '-Command' },
+  powershell = { '-NoProfile', '-Command' },
+  cmd = { '/d', '/s', '/c' },
+}
+
+-- Shell for task commands: tasks.shell, else cmd on Windows and sh elsewhere
+local function default_shell()
+  if task_config().shell then
+    return task_config().shell
+  end
+  return is_windows and 'cmd' or 'sh'
+end
+
+-- Program and arguments to spawn. A string runs through the shell; a list
+-- ({ 'cargo', 'test' }) is executed directly without one.
+local function spawn_args(command)
+  if type(command) == 'table' then
+    return command[1], vim.list_slice(command, 2)
+  end
+
+  local shell = default_shell()
+  local name = vim.fn.fnamemodify(shell, ':t'):gsub('%.exe$', '')
+  local args = vim.list_extend(vim.deepcopy(shell_flags[name] or { '-c' }), { command })
+  return shell, args
+end
+
+-- Ask a task to stop, escalating to SIGKILL after grace_ms. On Unix tasks run
+-- in their own process group, so the shell's children are signalled too.
+local function terminate(handle, pid, grace_ms)
+  local function signal(sig)
+    if handle:is_closing() then
+      return
+    end
+    if is_windows or not pid then
+      handle:kill(sig)
+    else
+      uv.kill(-pid, sig)
+    end
+  end
+
+  signal('sigterm')
+  vim.defer_fn(function()
+    signal('sigkill')
+  end, grace_ms or 2000)
+end
+
+-- Split incoming data into complete lines, keeping the trailing partial line.
+-- Call the returned function with nil at EOF to flush what is left.
+local function line_splitter(on_line)
+  local partial = ''
+  return function(data)
+    if not data then
+      if partial ~= '' then
+        on_line(partial)
+        partial = ''
+      end
+      return
+    end
+
+    partial = partial .. data
+    while true do
+      local nl = partial:find('\n', 1, true)
+      if not nl then
+        break
+      end
+      on_line((partial:sub(1, nl - 1):gsub('\r$', '')))
+      partial = partial:sub(nl + 1)
+    end
+  end
+end
+
+-- Decide whether a command may run under tasks.policy (Lua patterns).
+-- Returns "deny", "confirm" or "allow", plus the pattern that decided it.
+function M.check_policy(command)
+  local policy = task_config().policy or {}
+  local text = type(command) == 'table' and table.concat(command, ' ') or command
+
+  for _, pattern in ipairs(policy.deny or {}) do
+    if text:match(pattern) then
+      return 'deny', pattern
+    end
+  end
+
+  if policy.allow and #policy.allow > 0 then
+    local allowed = false
+    for _, pattern in ipairs(policy.allow) do
+      if text:match(pattern) then
+        allowed = true
+        break
+      end
+    end
+    if not allowed then
+      return 'deny', 'not in allow list'
+    end
+  end
+
+  for _, pattern in ipairs(policy.confirm or {}) do
+    if text:match(pattern) then
+      return 'confirm', pattern
+    end
+  end
+
+  return 'allow'
+end
+
+-- Built-in rules for classifying suggested commands before approval.
+-- destructive: patterns matched against the whole command line;
+-- read_only: programs (or "git <subcommand>") that don't change anything;
+-- read_only_flags: programs that only read when every argument is one of
+-- the listed flags.
+M.command_rules = {
+  destructive = {
+    { pattern = "^%s*rm%s", reason = "deletes files" },
+    { pattern = "^%s*rmdir%s", reason = "deletes directories" },
+    { pattern = "^%s*shred%s", reason = "destroys file contents" },
+    { pattern = "^%s*dd%s", reason = "writes raw data" },
+    { pattern = "^%s*mkfs", reason = "formats a filesystem" },
+    { pattern = "^%s*sudo%s", reason = "runs as root" },
+    { pattern = "^%s*kill", reason = "kills processes" },
+    { pattern = "^%s*truncate%s", reason = "truncates files" },
+    { pattern = "%-delete", reason = "find -delete removes files" },
+    { pattern = "^%s*chmod%s+%-R", reason = "changes permissions recursively" },
+    { pattern = "^%s*chown%s+%-R", reason = "changes ownership recursively" },
+    { pattern = "git%s+reset%s+%-%-hard", reason = "discards local changes" },
+    { pattern = "git%s+clean%s", reason = "deletes untracked files" },
+    { pattern = "git%s+push.*%-%-force", reason = "rewrites remote history" },
+    { pattern = "git%s+push.*%s%-f", reason = "rewrites remote history" },
+    { pattern = "git%s+checkout%s+%-%-%s", reason = "discards local changes" },
+    { pattern = "curl.*|%s*s?u?d?o?%s*[bz]?a?sh", reason = "pipes a download into a shell" },
+    { pattern = "wget.*|%s*s?u?d?o?%s*[bz]?a?sh", reason = "pipes a download into a shell" },
+  },
+  read_only = {
+    "ls", "ll", "la", "cat", "head", "tail", "less", "more", "grep", "rg", "ag", "fd",
+    "find", "pwd", "echo", "printf", "wc", "which", "type", "file", "stat", "tree",
+    "du", "df", "diff", "printenv", "date", "whoami", "uname", "id", "ps",
+    "sort", "uniq", "cut", "tr", "jq", "true", "false", "test", "basename", "dirname",
+    "realpath", "readlink", "man", "cd",
+    "git status", "git log", "git diff", "git show", "git blame",
+    "git rev-parse", "git ls-files", "git grep",
+  },
+  read_only_flags = {
+    ["git branch"] = { "-a", "-r", "-v", "-vv", "--all", "--remotes", "--list", "--show-current" },
+    ["git remote"] = { "-v", "--verbose" },
+  },
+}
+
+local severity_rank = { ['read-only'] = 1, mutating = 2, destructive = 3 }
+
+-- Classify one pipeline segment (no && / || / ; / | / & / newline)
+local function classify_segment(segment)
+  -- Output redirection writes files (except to /dev/null or another fd)
+  local stripped = segment:gsub('%d?>>?%s*/dev/null', ''):gsub('%d?>&%d', '')
+  if stripped:match('>') then
+    return 'mutating', 'redirects output to a file'
+  end
+
+  -- Skip leading VAR=value assignments
+  local words = {}
+  for word in segment:gmatch('%S+') do
+    if #words > 0 or not word:match('^[%w_]+=') then
+      table.insert(words, word)
+    end
+  end
+  if #words == 0 then
+    return 'read-only'
+  end
+
+  local program = vim.fn.fnamemodify(words[1], ':t')
+  local read_only = {}
+  for _, name in ipairs(M.command_rules.read_only) do
+    read_only[name] = true
+  end
+
+  if program == 'sed' and segment:match('%s%-i') then
+    return 'mutating', 'edits files in place'
+  end
+  if program == 'sort' then
+    for i = 2, #words do
+      if words[i]:match('^%-%a*o') or words[i]:match('^%-%-output') then
+        return 'mutating', 'sort -o writes a file'
+      end
+    end
+  end
+  local flags = words[2] and M.command_rules.read_only_flags[program .. ' ' .. words[2]]
+  if flags then
+    for i = 3, #words do
+      if not vim.tbl_contains(flags, words[i]) then
+        return 'mutating', program .. ' ' .. words[2] .. ' ' .. words[i] .. ' may change state'
+      end
+    end
+    return 'read-only'
+  end
+  if program == 'find' then
+    local actions = { ['-exec'] = true, ['-execdir'] = true, ['-ok'] = true, ['-okdir'] = true, ['-delete'] = true }
+    for i = 2, #words do
+      if actions[words[i]] then
+        return 'mutating', 'find ' .. words[i] .. ' runs commands or deletes files'
+      end
+    end
+  end
+  if read_only[program] or (words[2] and read_only[program .. ' ' .. words[2]]) then
+    return 'read-only'
+  end
+  return 'mutating', program .. ' may change files or state'
+end
+
+---Classify a command as "read-only", "mutating" or "destructive" (dry run: nothing is executed)
+---@param command string|table Shell string or argument list
+---@return string level
+---@return string[] reasons
+function M.classify(command)
+  local text = type(command) == 'table' and table.concat(command, ' ') or command
+  local level, reasons = 'read-only', {}
+
+  local function raise(new_level, reason)
+    if severity_rank[new_level] > severity_rank[level] then
+      level = new_level
+    end
+    if reason and not vim.tbl_contains(reasons, reason) then
+      table.insert(reasons, reason)
+    end
+  end
+
+  -- Destructive rules see the whole line so they can match across pipes.
+  -- Segments split on every command separator, including newlines and a
+  -- lone & (background); fd duplication like 2>&1 is not a separator
+  local separated = text:gsub('%d?>&%d', ''):gsub('&>', '>'):gsub('&&', ';'):gsub('||', ';')
+    :gsub('[|&\n]', ';')
+  local segments = vim.split(separated, ';', { plain = true })
+  for _, rule in ipairs(M.command_rules.destructive) do
+    if text:match(rule.pattern) then
+      raise('destructive', rule.reason)
+    else
+      for _, segment in ipairs(segments) do
+        if segment:match(rule.pattern) then
+          raise('destructive', rule.reason)
+          break
+        end
+      end
+    end
+  end
+
+  for _, segment in ipairs(segments) do
+    local segment_level, reason = classify_segment(segment)
+    raise(segment_level, reason)
+  end
+
+  -- Command and process substitution run commands the segment checks
+  -- don't see, so they always need approval
+  if text:find('$(', 1, true) or text:find('`', 1, true) or text:match('[<>]%(') then
+    raise('mutating', 'runs a substituted command')
+  end
+
+  return level, reasons
+end
+
+-- Allocate the next task id
+local function next_task_id()
+  task_counter = task_counter + 1
+  return task_counter
+end
+
+-- Tasks waiting for a free slot (tasks.max_concurrent), oldest first
+local task_queue = {}
+
+local start_next_queued
+
+-- Spawn a task process and track it in active_tasks.
+-- opts.cwd, opts.timeout_ms, opts.ansi, opts.on_line(line, stream), opts.on_stream(data)
+-- opts.on_done(task, stdout, stderr) runs on the main loop once the process has
+-- exited and its output is drained; opts.on_error(msg) reports spawn/read errors.
+local function spawn_task(task_id, command, label, buf, opts)
+  local program, args = spawn_args(command)
   local stdout_chunks = {}
   local stderr_chunks = {}
-  
-  local handle, pid
-  handle, pid = uv.spawn('sh', {
-    args = { '-c', full_cmd },
-    stdio = { nil, uv.new_pipe(false), uv.new_pipe(false) }
-  }, function(code, signal)
-    -- Process completion
+  local max_lines = opts.max_output_lines or task_config().max_output_lines or 5000
+  -- Ring of the most recent output lines; indexes are absolute line numbers.
+  -- spans holds highlight spans per line when escape codes are preserved.
+  local output = { lines = {}, spans = {}, first = 1, last = 0 }
+  local ansi_mode = opts.ansi or task_config().ansi or 'strip'
+
+  local stdout = uv.new_pipe(false)
+  local stderr = uv.new_pipe(false)
+  local exit_code = nil
+  local timer = nil
+  local timeout_ms = opts.timeout_ms or task_config().timeout_ms or 0
+
+  -- Process exit and both pipes closing can arrive in any order
+  local pending = 3
+  local function finalize()
+    pending = pending - 1
+    if pending > 0 then
+      return
+    end
+
+    -- Record the outcome and move the task to history
+    local task = active_tasks[task_id]
+    active_tasks[task_id] = nil
+    task.exit_code = exit_code
+    task.finished_at = os.time()
+    task.duration_ms = math.floor((uv.hrtime() - task.started_ns) / 1e6)
+    if task.status == 'running' then
+      task.status = exit_code == 0 and 'completed' or 'failed'
+    end
+    local events = { completed = 'ZekeTaskCompleted', failed = 'ZekeTaskFailed', cancelled = 'ZekeTaskCancelled' }
+    emit(events[task.status], {
+      id = task_id,
+      cmd = label,
+      exit_code = exit_code,
+      reason = task.reason,
+      duration_ms = task.duration_ms,
+    })
+    task.handle = nil
+    table.insert(task_history, task)
+    if #task_history > max_history then
+      table.remove(task_history, 1)
+    end
+
+    if timer and not timer:is_closing() then
+      timer:stop()
+      timer:close()
+    end
+
     local stdout_data = table.concat(stdout_chunks, '')
     local stderr_data = table.concat(stderr_chunks, '')
-    
-    -- Clean up the task
-    active_tasks[task_id] = nil
-    
     vim.schedule(function()
-      local success, response = M.parse_response(stdout_data)
-      
-      if success and response then
-        if opts.on_success then
-          opts.on_success(response.content)
-        else
-          M.show_response(response.content, buf)
-        end
-      else
-        local error_msg = response and response.error or stderr_data or 'Unknown error'
-        if opts.on_error then
-          opts.on_error(error_msg)
-        else
-          M.show_error(error_msg, buf)
-        end
-      end
-      
-      if opts.on_exit then
-        opts.on_exit(code)
+      if opts.on_done then
+        opts.on_done(task, stdout_data, stderr_data)
       end
+      start_next_queued()
     end)
+  end
+
+  local handle, pid
+  handle, pid = uv.spawn(program, {
+    args = args,
+    cwd = opts.cwd,
+    detached = not is_windows,
+    stdio = { nil, stdout, stderr }
+  }, function(code, signal)
+    exit_code = code
+    handle:close()
+    finalize()
   end)
-  
+
   if not handle then
+    stdout:close()
+    stderr:close()
     vim.schedule(function()
-      local error_msg = 'Failed to start zeke process'
-      if opts.on_error then
-        opts.on_error(error_msg)
-      else
-        M.show_error(error_msg, buf)
-      end
+      opts.on_error('Failed to start process: ' .. tostring(pid))
+      start_next_queued()
     end)
-    return
+    return nil
   end
-  
+
+  -- Tasks run in their own process group, so stop them when Neovim exits
+  if not M.exit_autocmd then
+    M.exit_autocmd = vim.api.nvim_create_autocmd('VimLeavePre', {
+      callback = function()
+        for _, task in pairs(active_tasks) do
+          if task.handle then
+            terminate(task.handle, task.pid, 0)
+          end
+        end
+      end,
+      desc = 'Stop running Zeke tasks',
+    })
+  end
+
   -- Store task info
   active_tasks[task_id] = {
+    id = task_id,
     handle = handle,
     pid = pid,
-    cmd = cmd,
+    cmd = label,
+    cwd = opts.cwd,
+    sandbox = opts.sandbox,
+    watcher = opts.watcher,
     buf = buf,
-    start_time = vim.fn.localtime()
+    output = output,
+    status = 'running',
+    started_at = os.time(),
+    started_ns = uv.hrtime(),
   }
-  
-  -- Read stdout
-  if handle.stdout then
-    uv.read_start(handle.stdout, function(err, data)
+
+  emit('ZekeTaskStarted', { id = task_id, cmd = label, cwd = opts.cwd, pid = pid })
+
+  if timeout_ms > 0 then
+    timer = uv.new_timer()
+    timer:start(timeout_ms, 0, function()
+      local task = active_tasks[task_id]
+      if not task then
+        return
+      end
+      task.status = 'failed'
+      task.reason = 'timeout'
+      terminate(handle, pid)
+      emit('ZekeTaskTimeout', { id = task_id, cmd = label, timeout_ms = timeout_ms })
+    end)
+  end
+
+  -- Keep each complete line (bounded), mirror it into the task buffer and
+  -- hand it to on_line
+  local function on_line(line, stream)
+    local spans
+    if ansi_mode == 'strip' then
+      line = ansi.strip(line)
+    elseif ansi_mode == 'preserve' then
+      line, spans = ansi.parse(line)
+    end
+
+    output.last = output.last + 1
+    output.lines[output.last] = line
+    output.spans[output.last] = spans
+    if output.last - output.first + 1 > max_lines then
+      output.lines[output.first] = nil
+      output.spans[output.first] = nil
+      output.first = output.first + 1
+    end
+
+    emit('ZekeTaskOutput', { id = task_id, line = line, stream = stream, line_number = output.last })
+
+    vim.schedule(function()
+      if buf and vim.api.nvim_buf_is_valid(buf) then
+        vim.api.nvim_buf_set_lines(buf, -1, -1, false, { line })
+        if spans then
+          ansi.highlight(buf, vim.api.nvim_buf_line_count(buf) - 1, spans)
+        end
+        local excess = vim.api.nvim_buf_line_count(buf) - max_lines
+        if excess > 0 then
+          vim.api.nvim_buf_set_lines(buf, 0, excess, false, {})
+        end
+      end
+      if opts.on_line then
+        opts.on_line(line, stream)
+      end
+    end)
+  end
+
+  local function read_pipe(pipe, chunks, stream)
+    local split = line_splitter(function(line)
+      on_line(line, stream)
+    end)
+
+    pipe:read_start(function(err, data)
       if err then
         vim.schedule(function()
-          local error_msg = 'Error reading stdout: ' .. err
-          if opts.on_error then
-            opts.on_error(error_msg)
-          else
-            M.show_error(error_msg, buf)
-          end
+          opts.on_error('Error reading ' .. stream .. ': ' .. err)
         end)
       elseif data then
-        table.insert(stdout_chunks, data)
-        
+        -- Raw output is only kept for callers that parse it afterwards
+        if opts.collect_output then
+          table.insert(chunks, data)
+        end
+        split(data)
+
         -- Stream partial updates if enabled
-        if opts.on_stream then
+        if opts.on_stream and stream == 'stdout' then
           vim.schedule(function()
             opts.on_stream(data)
           end)
         end
+      else
+        split(nil)
+        pipe:close()
+        finalize()
       end
     end)
   end
+
+  read_pipe(stdout, stdout_chunks, 'stdout')
+  read_pipe(stderr, stderr_chunks, 'stderr')
+
+  return task_id
+end
+
+-- Start the oldest queued task if a slot is free
+-- Running one-shot tasks (watchers don't take a max_concurrent slot)
+local function running_count()
+  local count = 0
+  for _, task in pairs(active_tasks) do
+    if not task.watcher then
+      count = count + 1
+    end
+  end
+  return count
+end
+
+start_next_queued = function()
+  local limit = task_config().max_concurrent or 0
+  if #task_queue == 0 or (limit > 0 and running_count() >= limit) then
+    return
+  end
+
+  local entry = table.remove(task_queue, 1)
+  spawn_task(entry.id, entry.command, entry.label, entry.buf, entry.opts)
+end
+
+-- Check the command policy, then spawn the task now or queue it when
+-- tasks.max_concurrent tasks are already running. Returns the task id.
+local function start_task(task_id, command, label, buf, opts)
+  -- Enforce the command policy before anything is spawned
+  local verdict, rule = M.check_policy(label)
+  if verdict == 'deny' then
+    logger.warn('terminal', string.format('Blocked by policy (%s): %s', rule, label))
+    vim.schedule(function()
+      opts.on_error('Command blocked by policy: ' .. rule)
+    end)
+    return nil
+  elseif verdict == 'confirm' and not opts.approved then
+    local choice = vim.fn.confirm(string.format('Run this command?\n  %s', label), '&Run\n&Cancel', 2)
+    if choice ~= 1 then
+      vim.schedule(function()
+        opts.on_error('Command cancelled by user')
+      end)
+      return nil
+    end
+  end
+
+  local limit = task_config().max_concurrent or 0
+  if limit > 0 and not opts.watcher and running_count() >= limit then
+    table.insert(task_queue, {
+      id = task_id,
+      command = command,
+      label = label,
+      buf = buf,
+      opts = opts,
+      queued_at = os.time(),
+    })
+    logger.info('terminal', string.format('Task #%d queued (position %d)', task_id, #task_queue))
+    return task_id
+  end
+
+  return spawn_task(task_id, command, label, buf, opts)
+end
+
+-- Create a scratch buffer collecting a task's output
+local function create_task_buffer(task_id, filetype)
+  local buf = vim.api.nvim_create_buf(false, true)
+  vim.api.nvim_buf_set_name(buf, 'Zeke Task #' .. task_id)
+  vim.api.nvim_buf_set_option(buf, 'buftype', 'nofile')
+  vim.api.nvim_buf_set_option(buf, 'filetype', filetype)
+  return buf
+end
+
+-- Execute command asynchronously with callbacks
+-- opts.on_line(line, stream) is called for every line of stdout/stderr as it arrives
+-- opts.timeout_ms kills the process when exceeded (default: tasks.timeout_ms, 0 = none)
+-- cmd may be an argument list to run the binary without a shell
+function M.execute_command(cmd, opts)
+  opts = opts or {}
   
-  -- Read stderr
-  if handle.stderr then
-    uv.read_start(handle.stderr, function(err, data)
-      if err then
-        vim.schedule(function()
-          local error_msg = 'Error reading stderr: ' .. err
-          if opts.on_error then
-            opts.on_error(error_msg)
-          else
-            M.show_error(error_msg, buf)
-          end
-        end)
-      elseif data then
-        table.insert(stderr_chunks, data)
+  local full_cmd
+  if type(cmd) == 'table' then
+    full_cmd = vim.list_extend({ get_zeke_binary() }, cmd)
+    cmd = table.concat(cmd, ' ')
+  else
+    full_cmd = get_zeke_binary() .. ' ' .. cmd
+  end
+  local task_id = next_task_id()
+  
+  -- Create a unique buffer for this task's output
+  local buf = create_task_buffer(task_id, 'json')
+
+  local function report_error(error_msg)
+    if opts.on_error then
+      opts.on_error(error_msg)
+    else
+      M.show_error(error_msg, buf)
+    end
+  end
+
+  local started = start_task(task_id, full_cmd, cmd, buf, {
+    collect_output = true,
+    timeout_ms = opts.timeout_ms,
+    on_line = opts.on_line,
+    on_stream = opts.on_stream,
+    on_error = report_error,
+    on_done = function(task, stdout_data, stderr_data)
+      local success, response = M.parse_response(stdout_data)
+      
+      if task.reason == 'timeout' then
+        report_error(string.format('Task timed out after %dms', opts.timeout_ms or task_config().timeout_ms))
+      elseif success and response then
+        if opts.on_success then
+          opts.on_success(response.content)
+        else
+          M.show_response(response.content, buf)
+        end
+      else
+        report_error(response and response.error or stderr_data or 'Unknown error')
       end
-    end)
+      
+      if opts.on_exit then
+        opts.on_exit(task.exit_code)
+      end
+    end,
+  })
+
+  if not started then
+    return
   end
   
   -- Show task started notification
@@ -194,6 +707,373 @@ function M.execute_command(cmd, opts)
   return task_id
 end
 
+-- Sandbox backends, tried in order when tasks.sandbox.backend is not set
+local sandbox_backends = { 'bwrap', 'firejail', 'sandbox-exec' }
+
+-- Wrap a command in the configured sandbox. Writes are limited to cwd (and a
+-- private /tmp); network is cut unless tasks.sandbox.network is true.
+-- Returns the argv to spawn and a profile description, or nil and an error.
+function M.sandbox_wrap(command, cwd)
+  local sandbox = task_config().sandbox or {}
+  local backend = sandbox.backend
+  if not backend then
+    for _, name in ipairs(sandbox_backends) do
+      if vim.fn.executable(name) == 1 then
+        backend = name
+        break
+      end
+    end
+  end
+  if not backend or vim.fn.executable(backend) ~= 1 then
+    return nil, 'No sandbox available (install bubblewrap or firejail)'
+  end
+
+  local program, args = spawn_args(command)
+  local inner = vim.list_extend({ program }, args)
+  local network = sandbox.network == true
+  local argv
+
+  if backend == 'bwrap' then
+    argv = { 'bwrap', '--ro-bind', '/', '/', '--dev', '/dev', '--proc', '/proc',
+      '--tmpfs', '/tmp', '--bind', cwd, cwd, '--chdir', cwd, '--die-with-parent' }
+    if not network then
+      table.insert(argv, '--unshare-net')
+    end
+    table.insert(argv, '--')
+  elseif backend == 'firejail' then
+    argv = { 'firejail', '--quiet', '--read-only=/', '--read-write=' .. cwd, '--private-tmp' }
+    if not network then
+      table.insert(argv, '--net=none')
+    end
+    table.insert(argv, '--')
+  elseif backend == 'sandbox-exec' then
+    local profile = string.format(
+      '(version 1)(allow default)(deny file-write*)(allow file-write* (subpath "%s") (subpath "/private/tmp"))%s',
+      cwd, network and '' or '(deny network*)')
+    argv = { 'sandbox-exec', '-p', profile }
+  else
+    return nil, 'Unknown sandbox backend: ' .. backend
+  end
+
+  local description = string.format('%s (write: %s, network: %s)', backend, cwd, network and 'on' or 'off')
+  return vim.list_extend(argv, inner), description
+end
+
+-- Run an arbitrary command (string for the shell, or an argument list) as a task.
+-- opts: cwd, timeout_ms, sandbox, ansi, buf, on_line(line, stream), on_exit(exit_code, task, error)
+-- (task is nil, with the error, when the command never started)
+-- Output is collected in the task's buffer (opts.buf to reuse one). Commands
+-- suggested by the model must go through M.propose() instead.
+function M.run(command, opts)
+  opts = opts or {}
+  local task_id = opts.task_id or next_task_id()
+  local label = type(command) == 'table' and table.concat(command, ' ') or command
+  local buf = opts.buf or create_task_buffer(task_id, 'log')
+
+  local profile = nil
+  if opts.sandbox then
+    local wrapped, info = M.sandbox_wrap(command, opts.cwd or vim.fn.getcwd())
+    if not wrapped then
+      logger.error('terminal', info)
+      if opts.on_exit then
+        vim.schedule(function() opts.on_exit(-1, nil, info) end)
+      end
+      return nil
+    end
+    command, profile = wrapped, info
+  end
+
+  -- The policy sees the original command; the sandboxed argv is what runs
+  return start_task(task_id, command, label, buf, {
+    approved = opts.approved,
+    sandbox = profile,
+    cwd = opts.cwd,
+    timeout_ms = opts.timeout_ms,
+    ansi = opts.ansi,
+    watcher = opts.watcher,
+    on_line = opts.on_line,
+    on_error = function(error_msg)
+      logger.error('terminal', error_msg)
+      if opts.on_exit then
+        opts.on_exit(-1, nil, error_msg)
+      end
+    end,
+    on_done = function(task)
+      if opts.on_exit then
+        opts.on_exit(task.exit_code, task)
+      end
+    end,
+  })
+end
+
+-- Commands suggested by the model, waiting for the user's decision
+local pending_tasks = {}
+
+-- Queue a model-suggested command for approval. It never runs until the user
+-- approves it (prompted now, or later via M.approve_task). Returns the task id.
+-- opts are passed to M.run(); opts.on_denied() is called if it is rejected.
+function M.propose(command, opts)
+  opts = opts or {}
+  local task_id = next_task_id()
+  local label = type(command) == 'table' and table.concat(command, ' ') or command
+
+  -- Privacy mode refuses model-suggested commands without asking
+  local permitted, refused = require('zeke.privacy').check_action('command')
+  if not permitted then
+    vim.notify(refused, vim.log.levels.WARN)
+    if opts.on_denied then
+      vim.schedule(opts.on_denied)
+    end
+    return task_id
+  end
+
+  local level, reasons = M.classify(command)
+
+  pending_tasks[task_id] = {
+    id = task_id,
+    command = command,
+    cmd = label,
+    cwd = opts.cwd or vim.fn.getcwd(),
+    opts = opts,
+    status = 'pending',
+    classification = level,
+    reasons = reasons,
+    created_at = os.time(),
+  }
+
+  if opts.prompt ~= false then
+    local pending = pending_tasks[task_id]
+    local summary = level:upper()
+    if #reasons > 0 then
+      summary = summary .. ': ' .. table.concat(reasons, ', ')
+    end
+    vim.ui.select({ 'Run', 'Deny' }, {
+      prompt = string.format('AI suggests running:\n  %s\nin %s\n[%s]', pending.cmd, pending.cwd, summary),
+    }, function(choice)
+      if choice == 'Run' then
+        M.approve_task(task_id)
+      else
+        M.deny_task(task_id)
+      end
+    end)
+  end
+
+  return task_id
+end
+
+-- Pending (unapproved) commands
+function M.get_pending_tasks()
+  local tasks = {}
+  for _, pending in pairs(pending_tasks) do
+    table.insert(tasks, {
+      id = pending.id,
+      cmd = pending.cmd,
+      cwd = pending.cwd,
+      status = pending.status,
+      classification = pending.classification,
+      reasons = pending.reasons,
+      created_at = pending.created_at,
+    })
+  end
+  table.sort(tasks, function(a, b) return a.id < b.id end)
+  return tasks
+end
+
+-- Approve a pending command and start it
+function M.approve_task(task_id)
+  local pending = pending_tasks[task_id]
+  if not pending then
+    return false
+  end
+  if not require('zeke.privacy').check_action('command') then
+    return false
+  end
+  pending_tasks[task_id] = nil
+
+  logger.info('terminal', string.format('Approved task #%d: %s', task_id, pending.cmd))
+  require('zeke.audit').record('command', {
+    source = 'suggested',
+    command = pending.cmd,
+    cwd = pending.cwd,
+    task_id = task_id,
+    classification = pending.classification,
+  })
+  -- The user already saw the full command, so "confirm" rules don't ask again
+  local run_opts = vim.tbl_extend('force', pending.opts, { cwd = pending.cwd, task_id = task_id, approved = true })
+  -- Model-suggested commands run sandboxed when tasks.sandbox.enabled
+  if run_opts.sandbox == nil then
+    run_opts.sandbox = (task_config().sandbox or {}).enabled == true
+  end
+  return M.run(pending.command, run_opts) ~= nil
+end
+
+-- Reject a pending command
+function M.deny_task(task_id)
+  local pending = pending_tasks[task_id]
+  if not pending then
+    return false
+  end
+  pending_tasks[task_id] = nil
+
+  logger.info('terminal', string.format('Denied task #%d: %s', task_id, pending.cmd))
+  if pending.opts.on_denied then
+    pending.opts.on_denied()
+  end
+  return true
+end
+
+-- Long-running watcher tasks (cargo watch, npm run dev, ...) by name
+local watchers = {}
+
+local function start_watcher(watcher)
+  watcher.task_id = M.run(watcher.command, {
+    cwd = watcher.cwd,
+    buf = watcher.buf,
+    ansi = watcher.ansi,
+    timeout_ms = 0,
+    watcher = watcher.name,
+    on_exit = function(code, task, err)
+      watcher.task_id = nil
+      watcher.last_exit = code
+      -- Never started (policy, sandbox or spawn error): restarting won't help
+      if not task then
+        watcher.status = 'exited'
+        watcher.error = err
+        logger.warn('terminal', string.format('Watcher %s could not start: %s', watcher.name, tostring(err)))
+        return
+      end
+      watcher.error = nil
+      if watcher.stopped or task.status == 'cancelled' then
+        watcher.status = 'stopped'
+        return
+      end
+
+      local restart = watcher.restart == 'always' or (watcher.restart == 'on-failure' and code ~= 0)
+      if not restart or watcher.restarts >= watcher.max_restarts then
+        watcher.status = 'exited'
+        logger.info('terminal', string.format('Watcher %s exited (%s)', watcher.name, tostring(code)))
+        return
+      end
+
+      watcher.status = 'restarting'
+      watcher.restarts = watcher.restarts + 1
+      logger.info('terminal', string.format('Restarting watcher %s (%d/%d)', watcher.name, watcher.restarts, watcher.max_restarts))
+      vim.defer_fn(function()
+        if not watcher.stopped then
+          start_watcher(watcher)
+        end
+      end, watcher.restart_delay_ms)
+    end,
+  })
+  watcher.status = watcher.task_id and 'running' or 'exited'
+end
+
+-- Start a long-running watcher. Unlike one-shot tasks it has no timeout,
+-- doesn't count against tasks.max_concurrent, keeps one buffer across
+-- restarts and is restarted when it exits.
+-- opts: name, cwd, ansi, restart ("on-failure" | "always" | "never"),
+-- restart_delay_ms, max_restarts. Returns the watcher name.
+function M.watch(command, opts)
+  opts = opts or {}
+  local defaults = task_config().watchers or {}
+  local label = type(command) == 'table' and table.concat(command, ' ') or command
+  local name = opts.name or label
+
+  local previous = watchers[name]
+  if previous and previous.task_id then
+    vim.notify('Watcher already running: ' .. name, vim.log.levels.WARN)
+    return name
+  end
+
+  -- Starting a stopped or exited watcher again reuses its buffer (a second
+  -- buffer with the same name would fail) and cancels a pending restart
+  local buf
+  if previous then
+    previous.stopped = true
+    if vim.api.nvim_buf_is_valid(previous.buf) then
+      buf = previous.buf
+      vim.api.nvim_buf_set_lines(buf, 0, -1, false, {})
+    end
+  end
+  if not buf then
+    buf = vim.api.nvim_create_buf(false, true)
+    vim.api.nvim_buf_set_name(buf, 'Zeke Watch: ' .. name)
+    vim.api.nvim_buf_set_option(buf, 'buftype', 'nofile')
+    vim.api.nvim_buf_set_option(buf, 'filetype', 'log')
+  end
+
+  watchers[name] = {
+    name = name,
+    command = command,
+    cmd = label,
+    cwd = opts.cwd or vim.fn.getcwd(),
+    ansi = opts.ansi,
+    buf = buf,
+    restart = opts.restart or defaults.restart or 'on-failure',
+    restart_delay_ms = opts.restart_delay_ms or defaults.restart_delay_ms or 1000,
+    max_restarts = opts.max_restarts or defaults.max_restarts or 5,
+    restarts = 0,
+    stopped = false,
+  }
+  start_watcher(watchers[name])
+  return name
+end
+
+-- Stop a watcher and don't restart it
+function M.stop_watcher(name)
+  local watcher = watchers[name]
+  if not watcher then
+    return false
+  end
+  watcher.stopped = true
+  watcher.status = 'stopped'
+  if watcher.task_id then
+    M.cancel_task(watcher.task_id)
+  end
+  return true
+end
+
+-- Watchers with their state: { name, cmd, cwd, status, task_id, restarts, last_exit, error, buf }
+function M.get_watchers()
+  local list = {}
+  for _, watcher in pairs(watchers) do
+    table.insert(list, {
+      name = watcher.name,
+      cmd = watcher.cmd,
+      cwd = watcher.cwd,
+      status = watcher.status,
+      task_id = watcher.task_id,
+      restarts = watcher.restarts,
+      last_exit = watcher.last_exit,
+      error = watcher.error,
+      buf = watcher.buf,
+    })
+  end
+  table.sort(list, function(a, b) return a.name < b.name end)
+  return list
+end
+
+-- Last n lines (default 50) a watcher has written, e.g. to add to a prompt
+function M.watcher_tail(name, n)
+  local watcher = watchers[name]
+  if not watcher or not vim.api.nvim_buf_is_valid(watcher.buf) then
+    return nil
+  end
+  return vim.api.nvim_buf_get_lines(watcher.buf, -(n or 50) - 1, -1, false)
+end
+
+-- Show a watcher's output in a split that follows new lines
+function M.show_watcher(name)
+  local watcher = watchers[name]
+  if not watcher or not vim.api.nvim_buf_is_valid(watcher.buf) then
+    vim.notify('No watcher named ' .. tostring(name), vim.log.levels.WARN)
+    return
+  end
+  vim.cmd('botright split')
+  vim.api.nvim_win_set_buf(0, watcher.buf)
+  vim.cmd('normal! G')
+end
+
 -- Parse JSON response from zeke binary
 function M.parse_response(json_str)
   if not json_str or json_str == '' then
@@ -248,7 +1128,7 @@ function M.show_error(error_msg, buf)
   vim.api.nvim_buf_set_option(buf, 'modifiable', false)
   
   -- Show as notification or buffer based on config
-  local config_opts = config.get()
+  local config_opts = config.options or {}
   if config_opts.show_errors_as_notifications then
     vim.notify(error_msg, vim.log.levels.ERROR, { title = 'Zeke Error' })
   else
@@ -280,23 +1160,115 @@ function M.show_task_started(task_id, cmd, buf)
   vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
 end
 
--- Get active tasks
+-- Lua-facing view of a task record
+local function task_info(task)
+  local duration_ms = task.duration_ms or math.floor((uv.hrtime() - task.started_ns) / 1e6)
+  return {
+    id = task.id,
+    cmd = task.cmd,
+    pid = task.pid,
+    sandbox = task.sandbox,
+    cwd = task.cwd,
+    watcher = task.watcher,
+    status = task.status,
+    reason = task.reason,
+    exit_code = task.exit_code,
+    started_at = task.started_at,
+    finished_at = task.finished_at,
+    duration_ms = duration_ms,
+    duration = math.floor(duration_ms / 1000),
+  }
+end
+
+-- Find a task record, running or finished
+local function find_task(task_id)
+  if active_tasks[task_id] then
+    return active_tasks[task_id]
+  end
+  for _, task in ipairs(task_history) do
+    if task.id == task_id then
+      return task
+    end
+  end
+  return nil
+end
+
+-- Page through a task's retained output.
+-- offset is an absolute 1-based line number (default: oldest retained line).
+-- Returns { lines, spans, first, next, total, dropped } or nil for unknown tasks;
+-- spans[i] holds highlight spans for lines[i] when tasks.ansi = "preserve".
+function M.task_output(task_id, offset, limit)
+  local task = find_task(task_id)
+  if not task or not task.output then
+    return nil
+  end
+
+  local output = task.output
+  local from = math.max(offset or output.first, output.first)
+  local to = limit and math.min(from + limit - 1, output.last) or output.last
+
+  local lines = {}
+  local spans = {}
+  for i = from, to do
+    table.insert(lines, output.lines[i])
+    spans[#lines] = output.spans and output.spans[i]
+  end
+
+  return {
+    lines = lines,
+    spans = spans,
+    first = from,
+    next = to < output.last and to + 1 or nil,
+    total = output.last,
+    dropped = output.first - 1,
+  }
+end
+
+-- Info for one task, running or finished
+function M.get_task(task_id)
+  local task = find_task(task_id)
+  return task and task_info(task) or nil
+end
+
+-- Get active tasks: running ones, then queued ones with their queue_position
 function M.get_active_tasks()
   local tasks = {}
-  for task_id, task_info in pairs(active_tasks) do
+  for _, task in pairs(active_tasks) do
+    table.insert(tasks, task_info(task))
+  end
+  table.sort(tasks, function(a, b) return a.id < b.id end)
+
+  for position, entry in ipairs(task_queue) do
     table.insert(tasks, {
-      id = task_id,
-      cmd = task_info.cmd,
-      pid = task_info.pid,
-      start_time = task_info.start_time,
-      duration = vim.fn.localtime() - task_info.start_time
+      id = entry.id,
+      cmd = entry.label,
+      status = 'queued',
+      queue_position = position,
+      queued_at = entry.queued_at,
     })
   end
   return tasks
 end
 
+-- Get finished tasks (exit code, timestamps and duration), oldest first
+function M.get_task_history()
+  return vim.tbl_map(task_info, task_history)
+end
+
 -- Cancel a task
 function M.cancel_task(task_id)
+  for i, entry in ipairs(task_queue) do
+    if entry.id == task_id then
+      table.remove(task_queue, i)
+      emit('ZekeTaskCancelled', { id = task_id, cmd = entry.label, reason = 'cancelled' })
+      vim.schedule(function()
+        entry.opts.on_error('Task cancelled before it started')
+      end)
+      vim.notify('Task #' .. task_id .. ' removed from queue', vim.log.levels.INFO)
+      return true
+    end
+  end
+
   local task = active_tasks[task_id]
   if not task then
     vim.notify('Task #' .. task_id .. ' not found', vim.log.levels.WARN)
@@ -304,8 +1276,10 @@ function M.cancel_task(task_id)
   end
   
   if task.handle then
-    task.handle:kill('sigterm')
-    active_tasks[task_id] = nil
+    -- The exit callback reaps the process and moves the task to history
+    task.status = 'cancelled'
+    task.reason = 'cancelled'
+    terminate(task.handle, task.pid)
     vim.notify('Task #' .. task_id .. ' cancelled', vim.log.levels.INFO)
     return true
   end
@@ -313,9 +1287,14 @@ function M.cancel_task(task_id)
   return false
 end
 
--- Cancel all active tasks
+-- Cancel all queued and active tasks
 function M.cancel_all_tasks()
   local count = 0
+  -- Queued tasks first, so cancelling running ones doesn't start them
+  while #task_queue > 0 do
+    M.cancel_task(task_queue[1].id)
+    count = count + 1
+  end
   for task_id, _ in pairs(active_tasks) do
     if M.cancel_task(task_id) then
       count = count + 1
@@ -0,0 +1,284 @@
+--[[
+  Run-Tests-and-Fix Loop
+
+  Runs the project's test command as a task, parses the failures
+  (cargo test, pytest, jest), asks the AI to fix the files involved and
+  routes each proposed change through the approval prompt. Approved fixes
+  are written and the tests re-run, until they pass or max_rounds is hit.
+--]]
+
+local M = {}
+
+local cli = require('zeke.cli')
+local logger = require('zeke.logger')
+local terminal = require('zeke.terminal')
+local approval = require('zeke.approval')
+local audit = require('zeke.audit')
+local output_parsers = require('zeke.output_parsers')
+local postprocess = require('zeke.postprocess')
+local utils = require('zeke.utils')
+
+M.config = {
+  command = nil,     -- Test command (default: detected from the project)
+  max_rounds = 3,    -- Fix attempts before giving up
+  output_lines = 80, -- Tail of the test output sent with each fix request
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+---Detect the test command for a project
+---@param root string Project root
+---@return string|nil command
+---@return string|nil kind Parser to use ("cargo", "pytest", "jest")
+function M.detect_command(root)
+  local function exists(name)
+    return vim.loop.fs_stat(root .. '/' .. name) ~= nil
+  end
+
+  if exists('Cargo.toml') then
+    return 'cargo test', 'cargo'
+  elseif exists('package.json') then
+    return 'npx jest --ci', 'jest'
+  elseif exists('pytest.ini') or exists('pyproject.toml') or exists('setup.cfg') or exists('tests') then
+    return 'python -m pytest -q', 'pytest'
+  end
+  return nil, nil
+end
+
+-- Guess the parser from a command line
+local function kind_for(command)
+  if command:match('cargo') then
+    return 'cargo'
+  elseif command:match('pytest') then
+    return 'pytest'
+  elseif command:match('jest') or command:match('npm') or command:match('yarn') then
+    return 'jest'
+  end
+  return nil
+end
+
+-- Failure parsers: output lines -> { { name, file, line, message } }
+M.parsers = {
+  cargo = function(lines)
+    local failures = {}
+    for _, text in ipairs(lines) do
+      -- thread 'tests::it_adds' panicked at src/lib.rs:10:5:
+      local name, file, lnum = text:match("^thread '([^']+)' panicked at ([^:]+):(%d+)")
+      if name then
+        table.insert(failures, { name = name, file = file, line = tonumber(lnum), message = text })
+      end
+    end
+    return failures
+  end,
+
+  pytest = function(lines)
+    local failures = {}
+    local locations = {}
+    for _, text in ipairs(lines) do
+      -- tests/test_math.py:12: AssertionError
+      local file, lnum = text:match('^([%w_%-%./]+%.py):(%d+): ')
+      if file then
+        table.insert(locations, { file = file, line = tonumber(lnum) })
+      end
+      -- FAILED tests/test_math.py::test_add - assert 1 == 2
+      local path, name, message = text:match('^FAILED ([^:]+)::(%S+)%s*%-?%s*(.*)$')
+      if path then
+        table.insert(failures, { name = name, file = path, message = message })
+      end
+    end
+    -- Attach the deepest reported location for each failing file
+    for _, failure in ipairs(failures) do
+      for _, loc in ipairs(locations) do
+        if loc.file == failure.file then
+          failure.line = loc.line
+        end
+      end
+    end
+    return failures
+  end,
+
+  jest = function(lines)
+    local failures = {}
+    local current = nil
+    for _, text in ipairs(lines) do
+      -- ● Math › adds numbers
+      local name = text:match('^%s*● (.+)$')
+      if name then
+        current = { name = name, message = '' }
+        table.insert(failures, current)
+      elseif current and not current.file then
+        -- at Object.<anonymous> (src/math.test.js:10:5)
+        local file, lnum = text:match('%(([^():]+):(%d+):%d+%)')
+        if file and not file:match('node_modules') then
+          current.file, current.line = file, tonumber(lnum)
+        elseif current.message == '' and vim.trim(text) ~= '' then
+          current.message = vim.trim(text)
+        end
+      end
+    end
+    return failures
+  end,
+}
+
+---Parse test failures from output lines. Falls back to compiler/linter
+---diagnostics (zeke.output_parsers) when no test failed, e.g. a build error.
+---@param lines string[] Test output
+---@param kind string Parser name
+---@return table[] failures
+function M.parse_failures(lines, kind)
+  local parser = M.parsers[kind]
+  local failures = parser and parser(lines) or {}
+  if #failures > 0 then
+    return failures
+  end
+
+  for _, item in ipairs(output_parsers.parse(lines)) do
+    if item.severity == 'error' then
+      table.insert(failures, { name = item.severity, file = item.file, line = item.line, message = item.message })
+    end
+  end
+  return failures
+end
+
+-- Fix request for one file
+local function build_instruction(failures, output)
+  local parts = { 'Fix the code so these failing tests pass:' }
+  for _, failure in ipairs(failures) do
+    table.insert(parts, string.format('- %s (%s:%s): %s',
+      failure.name, failure.file, tostring(failure.line or '?'), failure.message or ''))
+  end
+  table.insert(parts, '')
+  table.insert(parts, 'Test output:')
+  table.insert(parts, table.concat(output, '\n'))
+  table.insert(parts, '')
+  table.insert(parts, 'Change only what is needed and return the complete updated file in one fenced code block.')
+  return table.concat(parts, '\n')
+end
+
+---The code of a fix response: its fenced block for the file's language, or
+---the reply without fences when there is none. Never the surrounding prose.
+---@param response string
+---@param path string File being fixed
+---@return string
+function M.fixed_code(response, path)
+  local filetype = vim.filetype.match({ filename = path }) or ''
+  return utils.first_code_block(response, filetype)
+    or postprocess.apply(response, 'edit', { 'strip_fences', 'trim_leading_blank' })
+end
+
+-- Ask for a fix per failing file and apply the approved ones.
+-- Calls done(applied_count) when every file has been handled.
+local function propose_fixes(root, failures, output, done)
+  local by_file = {}
+  local files = {}
+  for _, failure in ipairs(failures) do
+    local path = failure.file
+    if path and not path:match('^/') then
+      path = root .. '/' .. path
+    end
+    path = path and vim.fn.fnamemodify(path, ':p')
+    if path and vim.fn.filereadable(path) == 1 then
+      if not by_file[path] then
+        by_file[path] = {}
+        table.insert(files, path)
+      end
+      table.insert(by_file[path], failure)
+    end
+  end
+
+  local applied = 0
+  local function next_file(index)
+    local path = files[index]
+    if not path then
+      done(applied)
+      return
+    end
+
+    vim.notify(string.format('Zeke: proposing a fix for %s', vim.fn.fnamemodify(path, ':.')), vim.log.levels.INFO)
+    cli.file_edit(path, build_instruction(by_file[path], output), function(response, err)
+      local code = response and M.fixed_code(response, path)
+      if not code or vim.trim(code) == '' then
+        logger.error('testfix', 'Fix request failed: ' .. tostring(err or 'empty response'))
+        next_file(index + 1)
+        return
+      end
+
+      approval.request({ kind = 'modify', path = path, content = code }, function(approved)
+        if approved then
+          local previous = audit.read_file(path)
+          vim.fn.writefile(vim.split(code, '\n', { plain = true }), path)
+          audit.record('file_write', { source = 'testfix', path = path, before = previous, after = code })
+          applied = applied + 1
+        end
+        next_file(index + 1)
+      end)
+    end)
+  end
+
+  next_file(1)
+end
+
+---Run the tests and fix failures until they pass or max_rounds is reached
+---@param opts table|nil {command, max_rounds, cwd}
+function M.run(opts)
+  opts = opts or {}
+  local root = opts.cwd or vim.fn.getcwd()
+  local command, kind = opts.command or M.config.command, nil
+
+  if command then
+    kind = kind_for(command)
+  else
+    command, kind = M.detect_command(root)
+  end
+  if not command then
+    vim.notify('Zeke: no test command found (set test_fix.command)', vim.log.levels.WARN)
+    return
+  end
+
+  local max_rounds = opts.max_rounds or M.config.max_rounds
+
+  local function run_round(round)
+    vim.notify(string.format('Zeke: running tests (round %d/%d): %s', round, max_rounds, command), vim.log.levels.INFO)
+
+    terminal.run(command, {
+      cwd = root,
+      on_exit = function(code, task)
+        if code == 0 then
+          vim.cmd('checktime')
+          vim.notify(string.format('Zeke: tests pass (round %d)', round), vim.log.levels.INFO)
+          return
+        end
+        if not task then
+          return
+        end
+
+        local output = terminal.task_output(task.id).lines
+        local failures = M.parse_failures(output, kind)
+        if #failures == 0 then
+          vim.notify('Zeke: tests failed, but no failures could be parsed', vim.log.levels.WARN)
+          return
+        end
+        if round >= max_rounds then
+          vim.notify(string.format('Zeke: %d test(s) still failing after %d rounds', #failures, round), vim.log.levels.WARN)
+          return
+        end
+
+        local tail = vim.list_slice(output, math.max(1, #output - M.config.output_lines + 1))
+        propose_fixes(root, failures, tail, function(applied)
+          vim.cmd('checktime')
+          if applied == 0 then
+            vim.notify('Zeke: no fixes applied, stopping', vim.log.levels.INFO)
+            return
+          end
+          run_round(round + 1)
+        end)
+      end,
+    })
+  end
+
+  run_round(1)
+end
+
+return M
@@ -2,10 +2,11 @@
   Token Estimation and Cost Calculation
 
   Features:
+  - Per-model token counting (tokenizer approximations, pluggable)
   - Estimate token count for prompts
-  - Calculate API costs
+  - Calculate API costs (bundled per-model pricing, overridable via `pricing`)
   - Warn about large prompts
-  - Track token usage
+  - Track token usage and cost per session and per day
 --]]
 
 local M = {}
@@ -19,8 +20,19 @@ M.usage = {
   requests_count = 0,
 }
 
+-- Today's totals, persisted so they survive restarts
+M.daily = nil
+M.usage_file = vim.fn.stdpath('state') .. '/zeke/usage.json'
+
+-- Providers running on the user's machine; their requests cost nothing
+M.local_providers = { ollama = true, lmstudio = true, llamacpp = true }
+
 -- Cost per 1K tokens (USD) - Updated as of 2024
 M.pricing = {
+  -- Anthropic Claude (current ids)
+  ["claude-opus-4-1"] = { input = 0.015, output = 0.075 },
+  ["claude-sonnet-4-5"] = { input = 0.003, output = 0.015 },
+
   -- OpenAI
   ["gpt-4-turbo"] = { input = 0.01, output = 0.03 },
   ["gpt-4o"] = { input = 0.005, output = 0.015 },
@@ -48,6 +60,152 @@ M.pricing = {
   ["llama3:8b"] = { input = 0, output = 0 },
 }
 
+-- Count pre-tokenized pieces the way BPE tokenizers split text: letter
+-- runs (with their leading space), digit groups, punctuation runs,
+-- whitespace and non-ASCII characters. chars_per_token is the average
+-- length of a word token for the vocabulary.
+local function bpe_estimate(text, chars_per_token)
+  local count = 0
+  local i, len = 1, #text
+  while i <= len do
+    local s, e = text:find("^ ?%a+", i)
+    if s then
+      count = count + math.ceil((e - s + 1) / chars_per_token)
+    else
+      s, e = text:find("^%d+", i)
+      if s then
+        count = count + math.ceil((e - s + 1) / 3)
+      else
+        s, e = text:find("^%s+", i)
+        if s then
+          count = count + math.ceil((e - s + 1) / 16)
+        else
+          s, e = text:find("^[%p]+", i)
+          if s then
+            count = count + math.ceil((e - s + 1) / 2)
+          else
+            -- One non-ASCII character (UTF-8 sequence)
+            s, e = text:find("^[\192-\255][\128-\191]*", i)
+            e = e or i
+            count = count + 1
+          end
+        end
+      end
+    end
+    i = e + 1
+  end
+  return count
+end
+
+-- Tokenizers: function(text) -> token count
+M.tokenizers = {
+  -- OpenAI GPT-4o / o-series vocabulary
+  o200k = function(text)
+    return bpe_estimate(text, 6)
+  end,
+  -- OpenAI GPT-4 / GPT-3.5 vocabulary
+  cl100k = function(text)
+    return bpe_estimate(text, 5)
+  end,
+  claude = function(text)
+    return bpe_estimate(text, 4)
+  end,
+  -- SentencePiece-style vocabularies (Llama, Qwen, Mistral, ...)
+  sentencepiece = function(text)
+    return bpe_estimate(text, 4)
+  end,
+  default = function(text)
+    return M.estimate_tokens(text)
+  end,
+}
+
+-- Model id patterns -> tokenizer, checked in order; the first match wins
+M.model_tokenizers = {
+  { pattern = "^gpt%-4o", tokenizer = "o200k" },
+  { pattern = "^gpt%-5", tokenizer = "o200k" },
+  { pattern = "^o%d", tokenizer = "o200k" },
+  { pattern = "^gpt%-", tokenizer = "cl100k" },
+  { pattern = "claude", tokenizer = "claude" },
+  { pattern = "llama", tokenizer = "sentencepiece" },
+  { pattern = "qwen", tokenizer = "sentencepiece" },
+  { pattern = "mistral", tokenizer = "sentencepiece" },
+  { pattern = "codellama", tokenizer = "sentencepiece" },
+  { pattern = "deepseek", tokenizer = "sentencepiece" },
+}
+
+---Register a tokenizer (e.g. an exact one backed by an external counter)
+---@param name string
+---@param fn function(text) -> number
+---@param patterns string[]|nil Model id patterns that should use it
+function M.register_tokenizer(name, fn, patterns)
+  M.tokenizers[name] = fn
+  for i, pattern in ipairs(patterns or {}) do
+    table.insert(M.model_tokenizers, i, { pattern = pattern, tokenizer = name })
+  end
+end
+
+-- Id of a model name/id, or of the current model
+local function model_id(model)
+  if model then
+    return model
+  end
+  local ok, models = pcall(require, 'zeke.models')
+  local current = ok and models.get_current()
+  return current and current.id or nil
+end
+
+---Tokenizer name used for a model
+---@param model string|nil Model id (default: current model)
+---@return string
+function M.tokenizer_for(model)
+  local id = model_id(model)
+  if id then
+    id = id:lower()
+    for _, entry in ipairs(M.model_tokenizers) do
+      if id:match(entry.pattern) and M.tokenizers[entry.tokenizer] then
+        return entry.tokenizer
+      end
+    end
+  end
+  return "default"
+end
+
+---Count tokens of text for a model
+---@param text string
+---@param model string|nil Model id (default: current model)
+---@return number
+function M.count(text, model)
+  if not text or text == "" then
+    return 0
+  end
+  return M.tokenizers[M.tokenizer_for(model)](text)
+end
+
+---Truncate text to a token budget for a model, cutting at a line boundary
+---@param text string
+---@param max_tokens number
+---@param model string|nil
+---@return string text
+---@return number tokens Count of the kept text
+function M.truncate(text, max_tokens, model)
+  local count = M.count(text, model)
+  if count <= max_tokens then
+    return text, count
+  end
+
+  -- Keep whole lines while they fit
+  local kept, used = {}, 0
+  for _, line in ipairs(vim.split(text, "\n", { plain = true })) do
+    local line_tokens = M.count(line .. "\n", model)
+    if used + line_tokens > max_tokens then
+      break
+    end
+    table.insert(kept, line)
+    used = used + line_tokens
+  end
+  return table.concat(kept, "\n") .. "\n\n[... truncated ...]", used
+end
+
 ---Estimate token count for text (simple approximation)
 ---@param text string Text to estimate
 ---@return number Estimated token count
@@ -87,12 +245,17 @@ function M.get_pricing(model_name)
     return M.pricing[model_name]
   end
 
-  -- Try partial match (e.g., "gpt-4" matches "gpt-4-turbo")
+  -- Try partial match, longest key first (e.g. "gpt-4o-mini" uses "gpt-4o", not "gpt-4")
+  local name = model_name:lower()
+  local best, best_len = nil, 0
   for price_key, price in pairs(M.pricing) do
-    if model_name:match(price_key) or price_key:match(model_name) then
-      return price
+    if name:find(price_key, 1, true) and #price_key > best_len then
+      best, best_len = price, #price_key
     end
   end
+  if best then
+    return best
+  end
 
   -- Default fallback (assume GPT-3.5 pricing)
   return M.pricing["gpt-3.5-turbo"]
@@ -130,7 +293,7 @@ end
 function M.estimate_prompt_cost(prompt, model_name, estimated_output_tokens)
   estimated_output_tokens = estimated_output_tokens or 500
 
-  local input_tokens = M.estimate_tokens(prompt)
+  local input_tokens = M.count(prompt, model_name)
   local cost, breakdown = M.calculate_cost(input_tokens, estimated_output_tokens, model_name)
 
   return {
@@ -145,10 +308,11 @@ end
 
 ---Check if prompt is large and should warn user
 ---@param prompt string The prompt
+---@param model string|nil Model id (default: current model)
 ---@return boolean Is large
 ---@return string|nil Warning message
-function M.check_large_prompt(prompt)
-  local tokens = M.estimate_tokens(prompt)
+function M.check_large_prompt(prompt, model)
+  local tokens = M.count(prompt, model)
 
   -- Define thresholds
   local thresholds = {
@@ -185,7 +349,7 @@ end
 ---@return string Formatted string
 function M.format_estimate(estimate)
   local lines = {
-    string.format("Model: %s", estimate.model or "unknown"),
+    string.format("Model: %s (tokenizer: %s)", estimate.model or "unknown", M.tokenizer_for(estimate.model)),
     string.format("Input tokens: %d (~%d chars)", estimate.input_tokens, estimate.input_tokens * 4),
     string.format("Estimated output: %d tokens", estimate.output_tokens),
     string.format("Total: %d tokens", estimate.total_tokens),
@@ -224,7 +388,7 @@ function M.show_estimate_prompt(prompt, model_name, callback)
   local formatted = M.format_estimate(estimate)
 
   -- Check for large prompt warning
-  local is_large, warning = M.check_large_prompt(prompt)
+  local is_large, warning = M.check_large_prompt(prompt, model_name)
 
   local message = formatted
   if is_large and warning then
@@ -256,7 +420,7 @@ function M.track_usage(input_tokens, output_tokens, cost)
   M.usage.total_estimated_cost = M.usage.total_estimated_cost + cost
   M.usage.requests_count = M.usage.requests_count + 1
 
-  logger.info('tokens', string.format(
+  logger.debug('tokens', string.format(
     'Usage tracked: %d input + %d output tokens, $%.4f',
     input_tokens,
     output_tokens,
@@ -264,8 +428,66 @@ function M.track_usage(input_tokens, output_tokens, cost)
   ))
 end
 
+---Set up pricing overrides
+---@param opts table|nil {pricing = {[model] = {input, output}} per 1K tokens, usage_file}
+function M.setup(opts)
+  opts = opts or {}
+  M.pricing = vim.tbl_extend('force', M.pricing, opts.pricing or {})
+  if opts.usage_file then
+    M.usage_file = vim.fn.expand(opts.usage_file)
+    M.daily = nil
+  end
+end
+
+-- Today's totals, starting fresh on a new day
+local function today()
+  local date = os.date('%Y-%m-%d')
+  if not M.daily then
+    local ok, saved = pcall(function()
+      return vim.json.decode(table.concat(vim.fn.readfile(M.usage_file), '\n'))
+    end)
+    M.daily = ok and type(saved) == 'table' and saved or {}
+  end
+  if M.daily.date ~= date then
+    M.daily = { date = date, tokens = 0, cost = 0, requests = 0 }
+  end
+  return M.daily
+end
+
+local function save_daily()
+  vim.fn.mkdir(vim.fn.fnamemodify(M.usage_file, ':h'), 'p')
+  pcall(vim.fn.writefile, { vim.json.encode(M.daily) }, M.usage_file)
+end
+
+---Record a completed request: estimate its cost and add it to the totals
+---@param request table {model, provider, input_tokens, output_tokens}
+---@return table cost {model, input_tokens, output_tokens, cost, input_cost, output_cost}
+function M.record_request(request)
+  local cost, breakdown = 0, { input_cost = 0, output_cost = 0 }
+  if not M.local_providers[request.provider or ''] then
+    cost, breakdown = M.calculate_cost(request.input_tokens, request.output_tokens, request.model)
+  end
+
+  M.track_usage(request.input_tokens, request.output_tokens, cost)
+
+  local day = today()
+  day.tokens = day.tokens + request.input_tokens + request.output_tokens
+  day.cost = day.cost + cost
+  day.requests = day.requests + 1
+  save_daily()
+
+  return {
+    model = request.model,
+    input_tokens = request.input_tokens,
+    output_tokens = request.output_tokens,
+    cost = cost,
+    input_cost = breakdown.input_cost,
+    output_cost = breakdown.output_cost,
+  }
+end
+
 ---Get usage statistics
----@return table Statistics
+---@return table Statistics (session totals, plus `today`)
 function M.get_usage_stats()
   return {
     total_tokens = M.usage.total_estimated_tokens,
@@ -275,6 +497,7 @@ function M.get_usage_stats()
       (M.usage.total_estimated_tokens / M.usage.requests_count) or 0,
     avg_cost_per_request = M.usage.requests_count > 0 and
       (M.usage.total_estimated_cost / M.usage.requests_count) or 0,
+    today = vim.deepcopy(today()),
   }
 end
 
@@ -292,6 +515,9 @@ function M.format_usage_stats()
     "",
     string.format("Avg tokens/request: %.0f", stats.avg_tokens_per_request),
     string.format("Avg cost/request: $%.4f", stats.avg_cost_per_request),
+    "",
+    string.format("Today (%s): %d requests, %d tokens, $%.4f USD",
+      stats.today.date, stats.today.requests, stats.today.tokens, stats.today.cost),
   }
 
   return table.concat(lines, "\n")
@@ -0,0 +1,89 @@
+--[[
+  Stream Transcripts
+
+  Tees streamed responses into one Markdown file per Neovim session, line by
+  line as they arrive, so an interrupted session still leaves a record of
+  what the model produced. Likely secrets are redacted before writing.
+--]]
+
+local M = {}
+
+local secrets = require('zeke.secrets')
+
+M.config = {
+  enabled = false,
+  dir = vim.fn.stdpath('state') .. '/zeke/transcripts',
+  redact = true,
+  exclude_tasks = { 'completion' },  -- Inline completions would drown out the rest
+}
+
+local session_path = nil
+-- Stream that wrote last, to mark where concurrent streams interleave
+local last_stream = nil
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+---Transcript file of this session (created on first write)
+---@return string
+function M.path()
+  if not session_path then
+    session_path = string.format('%s/%s-%d.md', M.config.dir, os.date('%Y%m%d-%H%M%S'), vim.fn.getpid())
+  end
+  return session_path
+end
+
+local function append(text)
+  if M.config.redact then
+    text = secrets.redact(text)
+  end
+
+  local path = M.path()
+  vim.fn.mkdir(M.config.dir, 'p')
+  local file = io.open(path, 'a')
+  if file then
+    file:write(text)
+    file:close()
+  end
+end
+
+---Whether streams of a task are recorded
+---@param task string|nil
+function M.records(task)
+  return M.config.enabled and not vim.tbl_contains(M.config.exclude_tasks or {}, task or 'chat')
+end
+
+---Start a stream's section: header and (redacted) prompt
+---@param stream_id number
+---@param prompt string
+---@param info table|nil {task, provider}
+function M.begin(stream_id, prompt, info)
+  info = info or {}
+  last_stream = stream_id
+  append(string.format('\n## Stream #%d — %s (%s, %s)\n\n### Prompt\n\n%s\n\n### Response\n\n',
+    stream_id, os.date('%Y-%m-%d %H:%M:%S'), info.task or 'chat', info.provider or 'default', prompt))
+end
+
+---Append one streamed line
+---@param stream_id number
+---@param line string
+function M.write(stream_id, line)
+  if last_stream ~= stream_id then
+    append(string.format('\n_(stream #%d continued)_\n\n', stream_id))
+    last_stream = stream_id
+  end
+  append(line .. '\n')
+end
+
+---Close a stream's section with its outcome
+---@param stream_id number
+---@param exit_code number
+---@param cancelled boolean
+function M.finish(stream_id, exit_code, cancelled)
+  local outcome = cancelled and 'cancelled' or (exit_code == 0 and 'completed' or 'failed (exit ' .. exit_code .. ')')
+  append(string.format('\n_Stream #%d %s_\n', stream_id, outcome))
+  last_stream = nil
+end
+
+return M
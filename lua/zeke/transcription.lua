@@ -0,0 +1,143 @@
+--[[
+  Audio Transcription
+
+  Turns recorded voice notes into text for chat prompts or commit
+  messages:
+
+    require('zeke').transcribe("~/notes/idea.wav", function(text, err)
+      ...
+    end)
+
+  Accepts a file path or the audio bytes themselves. Backends: openai
+  (Whisper API, needs OPENAI_API_KEY or providers.openai.api_key) and
+  whispercpp, a local whisper.cpp server (examples/server, /inference).
+  More backends can be added with M.register_backend().
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local config = require('zeke.config')
+local privacy = require('zeke.privacy')
+local embeddings = require('zeke.embeddings')
+
+M.config = {
+  provider = 'whispercpp',
+  model = nil,      -- Default: the backend's model
+  url = nil,        -- Base URL of the server (default: providers.<name>.base_url)
+  language = nil,   -- ISO-639-1 code, e.g. "en"; nil = detect
+  timeout_s = 120,
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_extend('force', M.config, opts or {})
+end
+
+-- name -> { model, url, key_env, request(model, path, opts) -> path, body, parse(response) -> text }
+M.backends = {
+  openai = {
+    model = 'whisper-1',
+    url = 'https://api.openai.com/v1',
+    key_env = 'OPENAI_API_KEY',
+    request = function(model, path, opts)
+      return '/audio/transcriptions', { form = {
+        file = { path = path },
+        model = model,
+        language = opts.language,
+        prompt = opts.prompt,
+        response_format = 'json',
+      } }
+    end,
+    parse = function(response) return response.text end,
+  },
+  whispercpp = {
+    url = 'http://localhost:8080',
+    request = function(_, path, opts)
+      return '/inference', { form = {
+        file = { path = path },
+        language = opts.language,
+        prompt = opts.prompt,
+        response_format = 'json',
+      } }
+    end,
+    parse = function(response) return response.text end,
+  },
+}
+
+---Add (or replace) a transcription backend
+---@param name string
+---@param spec table {model, url, key_env, request(model, path, opts), parse(response)}
+function M.register_backend(name, spec)
+  M.backends[name] = spec
+end
+
+-- Audio bytes are written to a temporary file for the upload
+local function audio_file(audio, opts)
+  if #audio < 4096 and not audio:find('%z') and vim.fn.filereadable(vim.fn.expand(audio)) == 1 then
+    return vim.fn.fnamemodify(vim.fn.expand(audio), ':p'), false
+  end
+
+  local path = vim.fn.tempname() .. '.' .. (opts.format or 'wav')
+  local fd = vim.loop.fs_open(path, 'w', 384)
+  if not fd then
+    return nil
+  end
+  vim.loop.fs_write(fd, audio)
+  vim.loop.fs_close(fd)
+  return path, true
+end
+
+---Transcribe audio
+---@param audio string File path or audio bytes
+---@param callback function Called with (text|nil, error)
+---@param opts table|nil {provider, model, language, prompt (hint words/spelling), format = extension of audio bytes}
+function M.transcribe(audio, callback, opts)
+  opts = opts or {}
+  local provider = opts.provider or M.config.provider
+  local backend = M.backends[provider]
+  if not backend then
+    callback(nil, 'Unknown transcription provider: ' .. tostring(provider))
+    return
+  end
+  local allowed, refused = privacy.check_provider(provider)
+  if not allowed then
+    callback(nil, refused)
+    return
+  end
+
+  local providers = (config.options and config.options.providers) or {}
+  local settings = providers[provider] or {}
+  local base_url = M.config.url or settings.base_url or backend.url
+  local api_key = settings.api_key or (backend.key_env and vim.env[backend.key_env])
+  if backend.key_env and not api_key then
+    callback(nil, string.format('No API key for %s (set %s or providers.%s.api_key)', provider, backend.key_env, provider))
+    return
+  end
+
+  local path, temporary = audio_file(audio, opts)
+  if not path then
+    callback(nil, 'Could not write audio to a temporary file')
+    return
+  end
+
+  local request_opts = vim.tbl_extend('force', { language = M.config.language }, opts)
+  local endpoint, body = backend.request(opts.model or M.config.model or backend.model, path, request_opts)
+  local started = vim.loop.hrtime()
+  embeddings.post_json(base_url:gsub('/+$', '') .. endpoint, body, api_key, vim.schedule_wrap(function(response, err)
+    if temporary then
+      vim.fn.delete(path)
+    end
+    local text = response and backend.parse(response)
+    if type(text) ~= 'string' then
+      err = err or 'No transcript in the response from ' .. provider
+      logger.warn('transcription', err)
+      callback(nil, err)
+      return
+    end
+    logger.debug('transcription', string.format('Transcribed %s with %s in %dms', vim.fn.fnamemodify(path, ':t'),
+      provider, math.floor((vim.loop.hrtime() - started) / 1e6)))
+    callback(vim.trim(text), nil)
+  end), M.config.timeout_s)
+end
+
+return M
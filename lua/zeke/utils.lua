@@ -3,12 +3,9 @@ local M = {}
 
 local logger = require('zeke.logger')
 
--- Token estimation (rough approximation)
-function M.estimate_tokens(text)
-  -- Rough estimate: ~4 characters per token
-  -- This is simplified; actual tokenization varies by model
-  local char_count = #text
-  return math.ceil(char_count / 4)
+-- Token count for the current (or given) model
+function M.estimate_tokens(text, model)
+  return require('zeke.tokens').count(text, model)
 end
 
 -- Format token count for display
@@ -97,24 +94,8 @@ function M.format_latency(ms)
 end
 
 -- Truncate text to max tokens
-function M.truncate_to_tokens(text, max_tokens)
-  local estimated = M.estimate_tokens(text)
-
-  if estimated <= max_tokens then
-    return text, estimated
-  end
-
-  -- Truncate to approximately max_tokens
-  local char_limit = max_tokens * 4
-  local truncated = text:sub(1, char_limit)
-
-  -- Try to truncate at a line boundary
-  local last_newline = truncated:match(".*\n")
-  if last_newline then
-    truncated = last_newline
-  end
-
-  return truncated .. "\n\n[... truncated ...]", M.estimate_tokens(truncated)
+function M.truncate_to_tokens(text, max_tokens, model)
+  return require('zeke.tokens').truncate(text, max_tokens, model)
 end
 
 -- Get visual selection range
@@ -177,4 +158,150 @@ function M.format_size(bytes)
   end
 end
 
+-- Filetypes whose Markdown fence tag differs from the filetype
+local fence_tags = {
+  typescriptreact = "tsx",
+  javascriptreact = "jsx",
+  sh = "bash",
+  cs = "csharp",
+  objc = "objective-c",
+  objcpp = "objective-cpp",
+  dockerfile = "dockerfile",
+  text = "",
+}
+
+-- Human-readable language names for prompts
+local language_names = {
+  rust = "Rust", lua = "Lua", python = "Python", go = "Go", zig = "Zig",
+  javascript = "JavaScript", typescript = "TypeScript",
+  javascriptreact = "JavaScript (JSX)", typescriptreact = "TypeScript (TSX)",
+  c = "C", cpp = "C++", cs = "C#", java = "Java", kotlin = "Kotlin", swift = "Swift",
+  ruby = "Ruby", php = "PHP", sh = "shell", bash = "Bash", zsh = "Zsh", fish = "fish",
+  html = "HTML", css = "CSS", scss = "SCSS", json = "JSON", yaml = "YAML", toml = "TOML",
+  sql = "SQL", markdown = "Markdown", vim = "Vim script", elixir = "Elixir",
+  haskell = "Haskell", ocaml = "OCaml", scala = "Scala", dart = "Dart", nix = "Nix",
+}
+
+-- Filetype of a buffer or file path ("" when unknown)
+function M.filetype_of(source)
+  if type(source) == "number" then
+    return vim.bo[source].filetype
+  end
+  if type(source) == "string" and source ~= "" then
+    local bufnr = vim.fn.bufnr(source)
+    if bufnr ~= -1 and vim.bo[bufnr].filetype ~= "" then
+      return vim.bo[bufnr].filetype
+    end
+    return vim.filetype.match({ filename = source }) or ""
+  end
+  return ""
+end
+
+-- Markdown fence tag for a filetype
+function M.fence_language(filetype)
+  if not filetype or filetype == "" then
+    return ""
+  end
+  return fence_tags[filetype] or filetype
+end
+
+-- Language name for prompts ("the following Rust code"); "" when unknown
+function M.language_name(filetype)
+  if not filetype or filetype == "" then
+    return ""
+  end
+  return language_names[filetype] or filetype
+end
+
+-- Fenced code block tagged with the filetype's language
+function M.format_code_block(code, filetype)
+  -- Use a longer fence when the code itself contains one
+  local fence = "```"
+  while code:find(fence, 1, true) do
+    fence = fence .. "`"
+  end
+  return string.format("%s%s\n%s\n%s", fence, M.fence_language(filetype), code, fence)
+end
+
+-- Opening fence: indent, run of 3+ ` or ~, info string
+local function open_fence(line)
+  local indent, fence, info = line:match("^( ? ? ?)(```+)%s*([^`]-)%s*$")
+  if not fence then
+    indent, fence, info = line:match("^( ? ? ?)(~~~+)%s*(.-)%s*$")
+  end
+  if not fence then
+    return nil
+  end
+  return { indent = #indent, char = fence:sub(1, 1), len = #fence, language = info:match("^[^%s{]*") or "" }
+end
+
+-- Bare fence of the same kind, at least as long as the opening one
+local function closes(line, open)
+  local fence = line:match("^%s*([`~]+)%s*$")
+  return fence and fence:sub(1, 1) == open.char and #fence >= open.len
+    and not fence:find(open.char == "`" and "~" or "`", 1, true)
+end
+
+-- Fenced code blocks in a response:
+--   { {language = "rust", content = "...", char_range = {first, last}}, ... }
+-- char_range holds the byte offsets of the content in text (last < first
+-- for an empty block). Handles ``` and ~~~ fences, longer fences around
+-- shorter ones, fences indented up to three spaces, tagged fences nested
+-- inside a block of the same length, and an unterminated final block.
+-- With a filetype, blocks tagged with another language are skipped.
+function M.extract_code_blocks(text, filetype)
+  local want = filetype and M.fence_language(filetype) or nil
+  local blocks = {}
+  local open, depth, first, lines = nil, 0, nil, {}
+
+  local function close(last)
+    local block = {
+      language = open.language,
+      content = table.concat(lines, "\n"),
+      char_range = { first, last },
+    }
+    if not want or want == "" or block.language == "" or block.language == want or block.language == filetype then
+      table.insert(blocks, block)
+    end
+    open, depth, lines = nil, 0, {}
+  end
+
+  local pos = 1
+  while pos <= #text do
+    local nl = text:find("\n", pos, true)
+    local line_end = nl and nl - 1 or #text
+    local line = text:sub(pos, line_end)
+
+    if not open then
+      open = open_fence(line)
+      first = nl and nl + 1 or #text + 1
+    elseif closes(line, open) and depth == 0 then
+      close(pos - 2)
+    else
+      -- A tagged fence of the same kind and length opens a nested block
+      local inner = open_fence(line)
+      if inner and inner.char == open.char and inner.len == open.len and inner.language ~= "" then
+        depth = depth + 1
+      elseif depth > 0 and closes(line, open) then
+        depth = depth - 1
+      end
+      -- Remove the opening fence's indentation from content lines
+      table.insert(lines, open.indent > 0 and line:gsub("^" .. string.rep(" ?", open.indent), "", 1) or line)
+    end
+
+    pos = (nl or #text) + 1
+  end
+
+  if open then
+    close(#text)
+  end
+  return blocks
+end
+
+-- Content of the first code block (matching filetype when given), or nil
+function M.first_code_block(text, filetype)
+  local block = M.extract_code_blocks(text, filetype)[1]
+  return block and block.content or nil
+end
+
 return M
@@ -0,0 +1,153 @@
+--[[
+  Local Model Warm-up
+
+  Loading a model into an Ollama or llama.cpp server can take seconds, and
+  by default the first real request pays for it. With warm-up enabled,
+  selecting a local model sends a tiny background request that loads it,
+  and Ollama requests carry a keep_alive so it stays loaded between uses:
+
+    warmup = {
+      enabled = true,
+      keep_alive = "30m",
+      models = {
+        ["qwen2.5-coder:32b"] = { keep_alive = "2h" },
+        ["llama3.2:1b"] = false,  -- never warm up
+      },
+    }
+--]]
+
+local M = {}
+
+local logger = require('zeke.logger')
+local config = require('zeke.config')
+
+M.config = {
+  enabled = false,
+  keep_alive = '30m',
+  models = {},
+}
+
+function M.setup(opts)
+  M.config = vim.tbl_deep_extend('force', M.config, opts or {})
+end
+
+local function base_url(provider)
+  local providers = (config.options and config.options.providers) or {}
+  local settings = providers[provider] or {}
+  if settings.base_url then
+    return settings.base_url
+  end
+  if provider == 'ollama' then
+    return config.get_ollama_host()
+  elseif provider == 'llamacpp' then
+    return 'http://localhost:8080'
+  end
+  return nil
+end
+
+---Warm-up settings for a model
+---@param model_id string|nil Model ID or alias (default: current model)
+---@return table|nil {model, provider, keep_alive} (nil when not a local model or disabled for it)
+function M.settings(model_id)
+  local models = require('zeke.models')
+  if not model_id then
+    local current = models.get_current()
+    model_id = current and current.id
+  end
+  if not model_id then
+    return nil
+  end
+
+  local id, provider = models.resolve(model_id)
+  if provider ~= 'ollama' and provider ~= 'llamacpp' then
+    return nil
+  end
+
+  local per_model = M.config.models[id]
+  if per_model == nil then
+    per_model = M.config.models[model_id]
+  end
+  if per_model == false then
+    return nil
+  end
+  per_model = per_model or {}
+
+  local enabled = per_model.enabled
+  if enabled == nil then
+    enabled = M.config.enabled
+  end
+  if not enabled then
+    return nil
+  end
+
+  return {
+    model = id,
+    provider = provider,
+    keep_alive = per_model.keep_alive or M.config.keep_alive,
+  }
+end
+
+---Ollama keep_alive for requests to a model (nil when warm-up is off for it)
+---@param model_id string|nil
+---@return string|nil
+function M.keep_alive(model_id)
+  local settings = M.settings(model_id)
+  return settings and settings.provider == 'ollama' and settings.keep_alive or nil
+end
+
+---Load a local model in the background
+---@param model_id string|nil Default: current model
+---@param callback function|nil Called with (ok, elapsed_ms)
+---@return boolean started
+function M.warm(model_id, callback)
+  local settings = M.settings(model_id)
+  local url = settings and base_url(settings.provider)
+  if not url then
+    return false
+  end
+
+  local endpoint, body
+  if settings.provider == 'ollama' then
+    -- An empty prompt loads the model without generating anything
+    endpoint = url .. '/api/generate'
+    body = { model = settings.model, prompt = '', keep_alive = settings.keep_alive }
+  else
+    endpoint = url .. '/completion'
+    body = { prompt = ' ', n_predict = 1, cache_prompt = true }
+  end
+
+  local started = vim.loop.hrtime()
+  local job = vim.fn.jobstart({ 'curl', '-sS', '--max-time', '300', '-o', '/dev/null', '-w', '%{http_code}',
+    '-X', 'POST', '-H', 'Content-Type: application/json', '--data-binary', '@-', endpoint }, {
+    stdout_buffered = true,
+    on_stdout = function(_, data)
+      local status = tonumber(data and data[1] or '')
+      local ok = status ~= nil and status < 300
+      local elapsed_ms = math.floor((vim.loop.hrtime() - started) / 1e6)
+      if ok then
+        logger.info('warmup', string.format('Warmed up %s in %dms', settings.model, elapsed_ms))
+      else
+        logger.warn('warmup', string.format('Warm-up of %s failed (HTTP %s)', settings.model, data and data[1] or '?'))
+      end
+      if callback then
+        callback(ok, elapsed_ms)
+      end
+    end,
+  })
+  if job <= 0 then
+    logger.warn('warmup', 'Could not start curl for warm-up')
+    return false
+  end
+  vim.fn.chansend(job, vim.json.encode(body))
+  vim.fn.chanclose(job, 'stdin')
+  logger.debug('warmup', 'Warming up ' .. settings.model .. ' at ' .. endpoint)
+  return true
+end
+
+---Called when a model is selected
+---@param model_id string
+function M.model_selected(model_id)
+  M.warm(model_id)
+end
+
+return M
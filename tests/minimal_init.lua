@@ -0,0 +1,12 @@
+-- Minimal init for the plenary test suite:
+--   nvim --headless -u tests/minimal_init.lua -c "PlenaryBustedDirectory tests/ {minimal_init = 'tests/minimal_init.lua'}"
+-- plenary.nvim is taken from $PLENARY_DIR, or lazy.nvim's install path.
+
+local root = vim.fn.fnamemodify(debug.getinfo(1, 'S').source:sub(2), ':p:h:h')
+local plenary = os.getenv('PLENARY_DIR') or (vim.fn.stdpath('data') .. '/lazy/plenary.nvim')
+
+vim.opt.runtimepath:prepend(root)
+vim.opt.runtimepath:prepend(plenary)
+vim.opt.swapfile = false
+
+vim.cmd('runtime plugin/plenary.vim')
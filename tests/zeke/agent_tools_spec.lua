@@ -0,0 +1,55 @@
+local agent_tools = require('zeke.agent_tools')
+
+describe('agent_tools.resolve_path', function()
+  local root, outside, ctx
+
+  before_each(function()
+    local base = vim.fn.resolve(vim.fn.tempname())
+    root = base .. '/project'
+    outside = base .. '/project-other'
+    vim.fn.mkdir(root .. '/src', 'p')
+    vim.fn.mkdir(outside, 'p')
+    vim.fn.writefile({ 'secret' }, outside .. '/notes.txt')
+    vim.loop.fs_symlink(outside, root .. '/escape')
+    ctx = { cwd = root }
+  end)
+
+  after_each(function()
+    vim.fn.delete(vim.fn.fnamemodify(root, ':h'), 'rf')
+  end)
+
+  it('resolves relative paths against the project root', function()
+    assert.are.equal(root .. '/src/main.lua', agent_tools.resolve_path('src/main.lua', ctx))
+    assert.are.equal(root .. '/src', agent_tools.resolve_path('./src/', ctx))
+    assert.are.equal(root, agent_tools.resolve_path('.', ctx))
+    assert.are.equal(root, agent_tools.resolve_path(nil, ctx))
+    assert.are.equal(root, agent_tools.resolve_path('', ctx))
+  end)
+
+  it('accepts absolute paths inside the root', function()
+    assert.are.equal(root .. '/src', agent_tools.resolve_path(root .. '/src', ctx))
+  end)
+
+  it('refuses paths that leave the root', function()
+    local path, err = agent_tools.resolve_path('../project-other/notes.txt', ctx)
+    assert.is_nil(path)
+    assert.matches('outside the project', err)
+    assert.is_nil(agent_tools.resolve_path('src/../../project-other', ctx))
+    assert.is_nil(agent_tools.resolve_path(outside .. '/notes.txt', ctx))
+    assert.is_nil(agent_tools.resolve_path('/etc/passwd', ctx))
+  end)
+
+  it('refuses a sibling directory sharing the root as a prefix', function()
+    assert.is_nil(agent_tools.resolve_path(outside, ctx))
+  end)
+
+  it('follows symlinks before checking containment', function()
+    assert.is_nil(agent_tools.resolve_path('escape/notes.txt', ctx))
+  end)
+
+  it('never expands variables, ~ or wildcards', function()
+    assert.are.equal(root .. '/$HOME/x', agent_tools.resolve_path('$HOME/x', ctx))
+    assert.are.equal(root .. '/*', agent_tools.resolve_path('*', ctx))
+    assert.is_nil(agent_tools.resolve_path('~/.ssh/id_rsa', ctx))
+  end)
+end)
@@ -0,0 +1,44 @@
+local cli = require('zeke.cli')
+local config = require('zeke.config')
+
+describe('cli.command_line', function()
+  local build_env, options
+
+  before_each(function()
+    build_env, options = cli.build_env, config.options
+    cli.build_env = function()
+      return { ZEKE_MODEL = 'gpt-4o', ZEKE_PROVIDER = 'openai' }
+    end
+  end)
+
+  after_each(function()
+    cli.build_env, config.options = build_env, options
+  end)
+
+  it('passes arguments through untouched when running locally', function()
+    config.options = {}
+    local prompt = [[it's `rm -rf ~` and $(reboot); "quoted"]]
+    local argv, env = cli.command_line({ 'zeke', 'chat', prompt }, {})
+
+    assert.are.same({ 'zeke', 'chat', prompt }, argv)
+    assert.are.same({ ZEKE_MODEL = 'gpt-4o', ZEKE_PROVIDER = 'openai' }, env)
+  end)
+
+  it('quotes every argument and variable for the remote shell', function()
+    config.options = { remote = { host = 'devbox', cwd = '/srv/my app', ssh_args = { '-o', 'BatchMode=yes' } } }
+    local argv, env = cli.command_line({ 'zeke', 'chat', "it's $(reboot)" }, {})
+
+    assert.is_nil(env)
+    assert.are.same({ 'ssh', '-o', 'BatchMode=yes', 'devbox' }, vim.list_slice(argv, 1, 4))
+    assert.are.equal(5, #argv)
+    assert.are.equal("cd '/srv/my app' && env ZEKE_MODEL='gpt-4o' ZEKE_PROVIDER='openai' "
+      .. [['zeke' 'chat' 'it'\''s $(reboot)']], argv[5])
+  end)
+
+  it('leaves out cd without a remote working directory', function()
+    config.options = { remote = { host = 'devbox' } }
+    local argv = cli.command_line({ 'zeke', '--version' }, {})
+
+    assert.are.same({ 'ssh', 'devbox', "env ZEKE_MODEL='gpt-4o' ZEKE_PROVIDER='openai' 'zeke' '--version'" }, argv)
+  end)
+end)
@@ -0,0 +1,71 @@
+local mcp = require('zeke.mcp')
+local approval = require('zeke.approval')
+local privacy = require('zeke.privacy')
+
+describe('mcp tools', function()
+  local root, confirm, prompts, saved
+
+  local function open(path, lines)
+    local buf = vim.fn.bufadd(path)
+    vim.fn.bufload(buf)
+    vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+    return buf
+  end
+
+  before_each(function()
+    root = vim.fn.resolve(vim.fn.tempname())
+    vim.fn.mkdir(root, 'p')
+    saved = vim.deepcopy(mcp.config)
+    approval.reset()
+    prompts = {}
+    confirm = vim.fn.confirm
+    vim.fn.confirm = function(message)
+      table.insert(prompts, message)
+      return 3 -- Deny
+    end
+  end)
+
+  after_each(function()
+    vim.fn.confirm = confirm
+    mcp.config = saved
+    approval.reset()
+    privacy.set(false)
+    vim.fn.delete(root, 'rf')
+  end)
+
+  it('looks buffers up by exact name, not as a pattern', function()
+    mcp.config.require_approval = false
+    open(root .. '/app.lua', { 'return 1' })
+
+    assert.are.equal('return 1', mcp.tools.read_buffer.handler({ path = root .. '/app.lua' }))
+    assert.are.equal('', mcp.tools.read_buffer.handler({ path = root .. '/app.lu?' }))
+  end)
+
+  it('keeps sensitive buffers out of diagnostics in privacy mode', function()
+    mcp.config.require_approval = false
+    open(root .. '/.env', { 'TOKEN=abc' })
+    privacy.set(true)
+
+    local ok, err = pcall(mcp.tools.list_diagnostics.handler, { path = root .. '/.env' })
+    assert.is_false(ok)
+    assert.matches('Privacy mode', err)
+  end)
+
+  it("doesn't reuse session approvals given to Zeke's own changes", function()
+    open(root .. '/app.lua', { 'return 1' })
+    approval.allow_session('modify')
+
+    local ok = pcall(mcp.tools.apply_edit.handler,
+      { path = root .. '/app.lua', start_line = 1, end_line = 1, new_text = 'return 2' })
+    assert.is_false(ok)
+    assert.are.equal(1, #prompts)
+  end)
+
+  it('shows the edit as a diff when asking', function()
+    open(root .. '/app.lua', { 'return 1' })
+    pcall(mcp.tools.apply_edit.handler, { path = root .. '/app.lua', start_line = 1, end_line = 1, new_text = 'return 2' })
+
+    assert.matches('%-return 1', prompts[1])
+    assert.matches('%+return 2', prompts[1])
+  end)
+end)
@@ -0,0 +1,59 @@
+local server = require('zeke.openai_server')
+
+local function request(headers)
+  return { method = 'GET', path = '/v1/models', headers = headers }
+end
+
+describe('openai_server.authorize', function()
+  local saved
+
+  before_each(function()
+    saved = vim.deepcopy(server.config)
+    server.config.port = 8790
+    server.config.token = nil
+  end)
+
+  after_each(function()
+    server.config = saved
+  end)
+
+  describe('without a token', function()
+    it('serves local clients', function()
+      assert.is_nil(server.authorize(request({ host = '127.0.0.1:8790' })))
+      assert.is_nil(server.authorize(request({ host = 'localhost:8790' })))
+      assert.is_nil(server.authorize(request({ host = '[::1]:8790' })))
+    end)
+
+    it('refuses browsers', function()
+      local status, _, kind = server.authorize(request({ host = '127.0.0.1:8790', origin = 'https://example.com' }))
+      assert.are.equal(403, status)
+      assert.are.equal('permission_error', kind)
+    end)
+
+    it('refuses other hosts (DNS rebinding)', function()
+      assert.are.equal(403, server.authorize(request({ host = 'attacker.example:8790' })))
+      assert.are.equal(403, server.authorize(request({ host = '127.0.0.1:9999' })))
+      assert.are.equal(403, server.authorize(request({})))
+    end)
+  end)
+
+  describe('with a token', function()
+    before_each(function()
+      server.config.token = 's3cret'
+    end)
+
+    it('serves requests with the bearer token, from anywhere', function()
+      assert.is_nil(server.authorize(request({ authorization = 'Bearer s3cret', host = 'devbox:8790' })))
+      assert.is_nil(server.authorize(request({ authorization = 'Bearer s3cret', origin = 'https://example.com' })))
+    end)
+
+    it('refuses a missing or wrong token', function()
+      local status, _, kind = server.authorize(request({ host = '127.0.0.1:8790' }))
+      assert.are.equal(401, status)
+      assert.are.equal('authentication_error', kind)
+      assert.are.equal(401, server.authorize(request({ authorization = 'Bearer s3cres' })))
+      assert.are.equal(401, server.authorize(request({ authorization = 'Bearer s3cret2' })))
+      assert.are.equal(401, server.authorize(request({ authorization = 's3cret' })))
+    end)
+  end)
+end)
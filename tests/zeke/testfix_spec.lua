@@ -0,0 +1,35 @@
+local testfix = require('zeke.testfix')
+
+describe('testfix.fixed_code', function()
+  it('keeps only the fenced code of a reply', function()
+    local response = table.concat({
+      'The parser skipped empty lines. Here is the fix:',
+      '',
+      '```rust',
+      'fn parse() -> bool {',
+      '    true',
+      '}',
+      '```',
+      '',
+      'This makes `parses_empty_input` pass.',
+    }, '\n')
+    assert.are.equal('fn parse() -> bool {\n    true\n}', testfix.fixed_code(response, 'src/parser.rs'))
+  end)
+
+  it('prefers the block in the file\'s language', function()
+    local response = table.concat({
+      'Run it with:',
+      '```sh',
+      'cargo test',
+      '```',
+      '```rust',
+      'fn main() {}',
+      '```',
+    }, '\n')
+    assert.are.equal('fn main() {}', testfix.fixed_code(response, 'src/main.rs'))
+  end)
+
+  it('uses an unfenced reply as is', function()
+    assert.are.equal('def add(a, b):\n    return a + b', testfix.fixed_code('def add(a, b):\n    return a + b', 'calc.py'))
+  end)
+end)